@@ -0,0 +1,128 @@
+/*наименьшая программа на библиотеке: окно, один проход и треугольник через Batcher, без MSAA, кеша pipeline`ов и эффектов*/
+use vulkan_2d_triangle::ash::vk;
+use vulkan_2d_triangle::batch::Batcher;
+use vulkan_2d_triangle::pipeline_desc::PipelineConfig;
+use vulkan_2d_triangle::vertex::Vertex;
+use vulkan_2d_triangle::winit::event::{Event, WindowEvent};
+use vulkan_2d_triangle::{AppBase, CommandBase, FramesBase, MAX_FRAMES_IN_FLIGHT, PipelineSet, RenderBase, RenderPassDesc};
+
+fn main() {
+    let app_base = AppBase::new(640, 480).unwrap();
+    let device = &app_base.device;
+    let new_frames = || {
+        FramesBase::new(
+            &app_base.instance,
+            device,
+            app_base.surface,
+            &app_base.surface_loader,
+            app_base.physical_device,
+            app_base.queue_family_index,
+            &app_base.window,
+        )
+        .unwrap()
+    };
+    let desc = RenderPassDesc {
+        clear_color: [0.1, 0.1, 0.15, 1.0],
+        ..Default::default()
+    };
+    let samples = vk::SampleCountFlags::TYPE_1;
+    let new_render = |frames_base: &FramesBase| {
+        RenderBase::new(device, vk::PipelineCache::null(), &app_base.device_memory_properties, frames_base, samples, &desc, None)
+            .unwrap()
+    };
+
+    let mut frames_base = new_frames();
+    let mut render_base = new_render(&frames_base);
+    let config = PipelineConfig::default();
+    let mut pipeline_set = PipelineSet::new(
+        device,
+        vk::PipelineCache::null(),
+        render_base.pass_info(),
+        frames_base.extent,
+        &config,
+        &app_base.features,
+    )
+    .unwrap();
+    let mut command_base = CommandBase::new(device, app_base.queue_family_index, MAX_FRAMES_IN_FLIGHT).unwrap();
+    let mut batcher = Batcher::new(device, &app_base.device_memory_properties, MAX_FRAMES_IN_FLIGHT).unwrap();
+    let mut swapchain_dirty = false;
+
+    app_base
+        .render_loop(|event, event_loop| match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => event_loop.exit(),
+            Event::WindowEvent {
+                event: WindowEvent::Resized(_),
+                ..
+            } => swapchain_dirty = true,
+            Event::AboutToWait => {
+                let size = app_base.window.inner_size();
+                if size.width == 0 || size.height == 0 {
+                    return;
+                }
+                if swapchain_dirty {
+                    unsafe {
+                        device.device_wait_idle().unwrap();
+                        render_base.destroy(device);
+                        frames_base.destroy(device);
+                    }
+                    frames_base = new_frames();
+                    render_base = new_render(&frames_base);
+                    //pipeline`ы с динамическими viewport/scissor переживают пересоздание прохода
+                    pipeline_set
+                        .recreate(
+                            device,
+                            vk::PipelineCache::null(),
+                            render_base.pass_info(),
+                            frames_base.extent,
+                            &config,
+                            &app_base.features,
+                        )
+                        .unwrap();
+                    swapchain_dirty = false;
+                }
+
+                let [width, height] = [frames_base.extent.width as f32, frames_base.extent.height as f32];
+                batcher.begin(frames_base.extent);
+                pipeline_set.bind(&mut batcher);
+                batcher.draw_mesh(
+                    &[
+                        Vertex::new([width * 0.5, height * 0.2], [1.0, 0.0, 0.0, 1.0]),
+                        Vertex::new([width * 0.8, height * 0.8], [0.0, 1.0, 0.0, 1.0]),
+                        Vertex::new([width * 0.2, height * 0.8], [0.0, 0.0, 1.0, 1.0]),
+                    ],
+                    &[0, 1, 2],
+                );
+                let result = command_base.render_frame(
+                    device,
+                    app_base.present_queue,
+                    &frames_base,
+                    &[],
+                    None,
+                    &[&render_base],
+                    |_, _| {},
+                    |command_buffer, frame, _| {
+                        batcher.flush(device, &app_base.device_memory_properties, command_buffer, frame).unwrap()
+                    },
+                );
+                match result {
+                    Ok(suboptimal) => swapchain_dirty |= suboptimal,
+                    Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => swapchain_dirty = true,
+                    Err(err) => panic!("Render frame error: {:?}", err),
+                }
+            }
+            _ => (),
+        })
+        .unwrap();
+
+    unsafe {
+        device.device_wait_idle().unwrap();
+        batcher.destroy(device);
+        command_base.destroy(device);
+        pipeline_set.destroy(device);
+        render_base.destroy(device);
+        frames_base.destroy(device);
+    }
+}
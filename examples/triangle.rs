@@ -0,0 +1,993 @@
+/*демо библиотеки: сцена с фигурами, спрайтами, текстом и частицами, миникарта в offscreen цели,
+цепочка эффектов, оверлей, отладочные режимы, виды split-screen и дополнительные окна.
+Клавиши: B фон, P/C цепочка эффектов, G/V эффекты subpass`а, I статистика, W/X/T отладка, S виды, N новое окно*/
+use std::time::Instant;
+use vulkan_2d_triangle::ash::vk;
+use vulkan_2d_triangle::atlas::{Atlas, AtlasBuilder, SpriteAnimation};
+use vulkan_2d_triangle::batch::Batcher;
+use vulkan_2d_triangle::blend::BlendMode;
+use vulkan_2d_triangle::debug_view::DebugView;
+use vulkan_2d_triangle::particles::{EmitterParams, ParticleSystem};
+use vulkan_2d_triangle::pipeline_cache::{self, PipelineCacheBase};
+use vulkan_2d_triangle::pipeline_desc::PipelineConfig;
+use vulkan_2d_triangle::post::{ChainEffect, PostChain, PostEffects};
+use vulkan_2d_triangle::queries::GpuStatistics;
+use vulkan_2d_triangle::stroke::{LineCap, LineJoin, StrokeStyle};
+use vulkan_2d_triangle::target::RenderTarget;
+use vulkan_2d_triangle::text::{Font, FontAtlas, GlyphMode, TextAlign, TextStyle};
+use vulkan_2d_triangle::texture::{Sprite, TextureBase, TextureDescriptors, TextureOptions};
+use vulkan_2d_triangle::timing::{FrameLimiter, FrameStats, GpuTimer};
+use vulkan_2d_triangle::vertex::Vertex;
+use vulkan_2d_triangle::view::{Camera, View, ViewRect};
+use vulkan_2d_triangle::winit::{
+    event::{ElementState, Event, KeyEvent, WindowEvent},
+    keyboard::{Key, NamedKey},
+    window::WindowBuilder,
+};
+use vulkan_2d_triangle::{AppBase, CommandBase, FramesBase, MAX_FRAMES_IN_FLIGHT, PipelineSet, RenderBase, RenderPassDesc, WindowBase};
+
+const DEFAULT_MSAA_SAMPLES: u32 = 4; //переопределяется переменной окружения MSAA_SAMPLES, 1 выключает MSAA
+
+fn main() {
+    //сообщения библиотеки идут через log, демо по умолчанию показывает их с уровня info, RUST_LOG меняет уровень
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let app_base = AppBase::new(800, 600).unwrap();
+    let device_properties = unsafe {
+        app_base
+            .instance
+            .get_physical_device_properties(app_base.physical_device)
+    };
+
+    let device_name = unsafe { std::ffi::CStr::from_ptr(device_properties.device_name.as_ptr()) };
+
+    // not the same
+    // let device_name = unsafe { &std::ffi::CStr::from_ptr(device_properties.device_name[0] as *const i8) };
+
+    println!("Device name 1: {:?} ", device_name);
+    println!(
+        "Vulkan {} (instance {}.{}), enabled features: {:?}",
+        app_base.features.version_string(),
+        vk::api_version_major(app_base.api_version),
+        vk::api_version_minor(app_base.api_version),
+        app_base.features
+    );
+
+    let mut frames_base = FramesBase::new(
+        &app_base.instance,
+        &app_base.device,
+        app_base.surface,
+        &app_base.surface_loader,
+        app_base.physical_device,
+        app_base.queue_family_index,
+        &app_base.window,
+    )
+    .unwrap();
+
+    let samples = app_base.sample_count(
+        std::env::var("MSAA_SAMPLES")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MSAA_SAMPLES),
+    );
+    println!("MSAA samples: {:?}", samples);
+
+    //FPS_CAP ограничивает частоту кадров сном на CPU при любом present mode, 0 или без переменной ограничения нет
+    let fps_cap = std::env::var("FPS_CAP").ok().and_then(|value| value.parse().ok());
+    let mut frame_limiter = FrameLimiter::new(fps_cap);
+    let mut frame_stats = FrameStats::new(240);
+    let mut title_updated = Instant::now();
+
+    //RENDER_BACKEND=dynamic рисует проходы в swapchain через cmd_begin_rendering вместо render pass, если устройство это умеет
+    let dynamic_rendering = match std::env::var("RENDER_BACKEND").as_deref() {
+        Ok("dynamic") if app_base.dynamic_rendering.is_none() => {
+            println!("Dynamic rendering is not supported, falling back to render pass");
+            None
+        }
+        Ok("dynamic") => app_base.dynamic_rendering.as_ref(),
+        _ => None,
+    };
+    println!("Render backend: {}", if dynamic_rendering.is_some() { "dynamic rendering" } else { "render pass" });
+
+    //все pipeline`ы создаются через один кеш, он читается с диска здесь и записывается обратно при выходе
+    let pipeline_cache =
+        PipelineCacheBase::load(&app_base.device, &device_properties, &pipeline_cache::cache_dir()).unwrap();
+
+    /*описания pipeline`ов Batcher`а: PIPELINE_CONFIG или shader/pipelines.json, без файла встроенные значения,
+    файлы шейдеров ищутся рядом с конфигурацией, иначе берутся встроенные*/
+    let pipeline_config_path = std::env::var("PIPELINE_CONFIG").unwrap_or_else(|_| "shader/pipelines.json".to_string());
+    let pipeline_config = PipelineConfig::load(&pipeline_config_path).unwrap_or_else(|err| {
+        println!("Pipeline config {}: {}, using built-in pipelines", pipeline_config_path, err);
+        PipelineConfig::default()
+    });
+
+    let mut render_base = RenderBase::new(
+        &app_base.device,
+        pipeline_cache.cache,
+        &app_base.device_memory_properties,
+        &frames_base,
+        samples,
+        &RenderPassDesc {
+            //сцена проходит через subpass эффектов, оверлей рисуется после и эффекты на него не влияют
+            post_process: Some(PostEffects {
+                vignette: 0.5,
+                contrast: 1.05,
+                saturation: 1.1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        dynamic_rendering,
+    )
+    .unwrap();
+
+    //оверлей рисуется вторым проходом поверх готовой сцены
+    let mut overlay_render_base = RenderBase::new(
+        &app_base.device,
+        pipeline_cache.cache,
+        &app_base.device_memory_properties,
+        &frames_base,
+        samples,
+        &RenderPassDesc::overlay(),
+        dynamic_rendering,
+    )
+    .unwrap();
+
+    let mut command_base =
+        CommandBase::new(&app_base.device, app_base.queue_family_index, MAX_FRAMES_IN_FLIGHT).unwrap();
+    //время проходов на GPU, период тика из уже полученных свойств устройства
+    command_base.gpu_timer = GpuTimer::new(&app_base, &device_properties.limits, MAX_FRAMES_IN_FLIGHT).unwrap();
+    if command_base.gpu_timer.is_none() {
+        println!("GPU timestamps are not supported by the queue family");
+    }
+    //счетчики вершин и фрагментов для поиска overdraw, I печатает сводку последнего кадра
+    command_base.gpu_statistics = GpuStatistics::new(&app_base, MAX_FRAMES_IN_FLIGHT).unwrap();
+
+    let texture_descriptors = TextureDescriptors::new(&app_base.device, 16).unwrap();
+
+    //первый аргумент командной строки это путь к PNG/JPEG, без него рисуется сгенерированная шахматная доска
+    let texture = match std::env::args().nth(1) {
+        Some(path) => TextureBase::load(&app_base, command_base.pool, &path, &TextureOptions::default()).unwrap(),
+        None => {
+            let size = 64;
+            let pixels: Vec<u8> = (0..size * size)
+                .flat_map(|i| {
+                    let (x, y) = (i % size, i / size);
+                    if (x / 8 + y / 8) % 2 == 0 { [255, 255, 255, 255] } else { [40, 40, 40, 255] }
+                })
+                .collect();
+            let options = TextureOptions {
+                mag_filter: vk::Filter::NEAREST, //четкие клетки при увеличении
+                ..Default::default()
+            };
+            TextureBase::from_rgba(&app_base, command_base.pool, size, size, &pixels, &options).unwrap()
+        }
+    };
+    println!(
+        "Texture: extent = {:?}, format = {:?}, mip levels = {}",
+        texture.extent, texture.format, texture.mip_levels
+    );
+    let sprite = Sprite::new(
+        texture_descriptors.allocate(&app_base.device, &texture).unwrap(),
+        &texture,
+    );
+
+    //второй аргумент командной строки это каталог для atlas.png и atlas.json, если они там уже есть атлас загружается без упаковки
+    let atlas = match std::env::args().nth(2) {
+        Some(dir) => {
+            let dir = std::path::PathBuf::from(dir);
+            let (image_path, layout_path) = (dir.join("atlas.png"), dir.join("atlas.json"));
+            if !image_path.exists() || !layout_path.exists() {
+                let (layout, image) = build_demo_atlas().pack(2048).unwrap();
+                Atlas::save(&layout, &image, &image_path, &layout_path).unwrap();
+            }
+            Atlas::load(&app_base, command_base.pool, &texture_descriptors, &image_path, &layout_path, &TextureOptions::default())
+                .unwrap()
+        }
+        None => {
+            let (layout, image) = build_demo_atlas().pack(2048).unwrap();
+            Atlas::new(&app_base, command_base.pool, &texture_descriptors, layout, &image, &TextureOptions::default()).unwrap()
+        }
+    };
+    println!(
+        "Atlas: {}x{}, {} regions, {} animations",
+        atlas.layout.width,
+        atlas.layout.height,
+        atlas.layout.regions.len(),
+        atlas.layout.animations.len()
+    );
+    let atlas_sprites: Vec<Sprite> = ["ring", "diamond", "gradient"]
+        .iter()
+        .map(|name| atlas.sprite(name).unwrap())
+        .collect();
+    let spinner = atlas.animation("spinner").unwrap();
+    let start_time = Instant::now();
+
+    //фонтан частиц: симуляция в compute шейдере, отрисовка прямо из того же storage буффера
+    let mut particles = ParticleSystem::new(&app_base, pipeline_cache.cache, command_base.pool, 4096, EmitterParams::default()).unwrap();
+    let mut last_frame_time = 0.0;
+
+    //третий аргумент командной строки это путь к TTF/OTF, без него пробуется системный DejaVu Sans, без шрифта текст не рисуется
+    let font_path = std::env::args()
+        .nth(3)
+        .unwrap_or_else(|| "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf".to_string());
+    let demo_chars = || (' '..='~').chain('А'..='я');
+    let fonts = match (Font::load(&font_path), Font::load(&font_path)) {
+        (Ok(bitmap_font), Ok(sdf_font)) => Some((
+            FontAtlas::new(&app_base, command_base.pool, &texture_descriptors, bitmap_font, 16.0, demo_chars(), GlyphMode::Bitmap)
+                .unwrap(),
+            FontAtlas::new(
+                &app_base,
+                command_base.pool,
+                &texture_descriptors,
+                sdf_font,
+                48.0,
+                demo_chars(),
+                GlyphMode::Sdf { spread: 6 },
+            )
+            .unwrap(),
+        )),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("Font {} not loaded, text disabled: {}", font_path, err);
+            None
+        }
+    };
+
+    let mut pipeline_set = PipelineSet::new(
+        &app_base.device,
+        pipeline_cache.cache,
+        render_base.pass_info(),
+        frames_base.extent,
+        &pipeline_config,
+        &app_base.features,
+    )
+    .unwrap();
+    println!("Batcher pipelines: {} distinct per render pass", pipeline_set.pipeline_count());
+    let mut overlay_pipeline_set = PipelineSet::new(
+        &app_base.device,
+        pipeline_cache.cache,
+        overlay_render_base.pass_info(),
+        frames_base.extent,
+        &pipeline_config,
+        &app_base.features,
+    )
+    .unwrap();
+
+    /*миникарта рисуется в offscreen цель своим Batcher`ом и затем показывается в сцене как обычный спрайт,
+    размер цели не зависит от окна, поэтому при пересоздании swapchain она не меняется*/
+    let minimap = RenderTarget::new(
+        &app_base,
+        command_base.pool,
+        vk::Extent2D { width: 192, height: 192 },
+        vk::Format::R8G8B8A8_UNORM,
+        &RenderPassDesc {
+            clear_color: [0.05, 0.08, 0.12, 0.9],
+            ..Default::default()
+        },
+        &TextureOptions::default(),
+    )
+    .unwrap();
+    let minimap_pipeline_set = PipelineSet::new(
+        &app_base.device,
+        pipeline_cache.cache,
+        minimap.pass_info(),
+        minimap.extent(),
+        &pipeline_config,
+        &app_base.features,
+    )
+    .unwrap();
+    let minimap_sprite = Sprite::new(
+        texture_descriptors.allocate(&app_base.device, &minimap.texture).unwrap(),
+        &minimap.texture,
+    );
+    let mut minimap_batcher = Batcher::new(
+        &app_base.device,
+        &app_base.device_memory_properties,
+        MAX_FRAMES_IN_FLIGHT,
+    )
+    .unwrap();
+
+    /*цепочка эффектов, клавиша P включает ее, C переключает набор и порядок эффектов,
+    со включенной цепочкой сцена рисуется в post_chain.source (без MSAA), а в кадр swapchain попадает только blit результата*/
+    let chain_presets = [
+        vec![
+            ChainEffect::Bloom { threshold: 0.6, intensity: 1.5, radius: 12.0 },
+            ChainEffect::Tonemap { exposure: 1.0, aces: true },
+        ],
+        vec![ChainEffect::Blur { radius: 4.0 }, ChainEffect::Tonemap { exposure: 1.2, aces: false }],
+        vec![
+            ChainEffect::Pixelate { size: 4.0 },
+            ChainEffect::Crt { curvature: 0.08, scanlines: 0.35, aberration: 1.5 },
+        ],
+        vec![
+            ChainEffect::Bloom { threshold: 0.5, intensity: 2.0, radius: 16.0 },
+            ChainEffect::Blur { radius: 1.5 },
+            ChainEffect::Crt { curvature: 0.05, scanlines: 0.25, aberration: 1.0 },
+            ChainEffect::Tonemap { exposure: 1.1, aces: true },
+        ],
+    ];
+    let mut chain_preset = 0;
+    let mut chain_enabled = false;
+    let mut post_chain = PostChain::new(
+        &app_base,
+        pipeline_cache.cache,
+        command_base.pool,
+        frames_base.extent,
+        &RenderPassDesc {
+            clear_color: render_base.desc.clear_color,
+            ..Default::default()
+        },
+        render_base.render_pass,
+        render_base.format,
+        render_base.samples,
+        chain_presets[chain_preset].clone(),
+    )
+    .unwrap();
+    let mut chain_pipeline_set = PipelineSet::new(
+        &app_base.device,
+        pipeline_cache.cache,
+        post_chain.source.pass_info(),
+        frames_base.extent,
+        &pipeline_config,
+        &app_base.features,
+    )
+    .unwrap();
+
+    let mut batcher = Batcher::new(
+        &app_base.device,
+        &app_base.device_memory_properties,
+        MAX_FRAMES_IN_FLIGHT,
+    )
+    .unwrap();
+
+    //второй Batcher для оверлея, у каждого свои буфферы, поэтому оба можно сбросить в одном кадре
+    let mut overlay_batcher = Batcher::new(
+        &app_base.device,
+        &app_base.device_memory_properties,
+        MAX_FRAMES_IN_FLIGHT,
+    )
+    .unwrap();
+
+    //клавиша B переключает цвет фона, G и V эффекты subpass`а, P и C цепочку эффектов
+    let backgrounds = [[0.0, 0.0, 0.0, 1.0], [0.08, 0.1, 0.16, 1.0], [0.2, 0.12, 0.1, 1.0], [0.85, 0.85, 0.8, 1.0]];
+    let mut background = 0;
+
+    //W каркас, X точки вершин, T номера треугольников основной сцены
+    let mut debug_view = DebugView::default();
+    if !app_base.features.fill_mode_non_solid {
+        println!("fillModeNonSolid is not supported, wireframe edges are built on the CPU");
+    }
+
+    //S переключает раскладку видов сцены: одно окно, split-screen из двух видов, картинка в картинке
+    let mut view_layout = ViewLayout::Single;
+
+    //N открывает еще одно окно с той же сценой, окна закрываются крестиком или Escape независимо от основного
+    let mut windows: Vec<WindowBase> = Vec::new();
+    let mut windows_opened = 0;
+
+    let mut swapchain_dirty = false; //окно изменило размер, swapchain и все что от него зависит нужно пересоздать
+
+    app_base
+        .render_loop(|event, event_loop| match event {
+            Event::WindowEvent {
+                event: WindowEvent::Resized(_),
+                window_id,
+            } => match windows.iter_mut().find(|window| window.id() == *window_id) {
+                Some(window) => window.swapchain_dirty = true,
+                None => swapchain_dirty = true,
+            },
+            //закрытие или Escape: дополнительное окно закрывается, основное завершает демо
+            Event::WindowEvent {
+                event:
+                    WindowEvent::CloseRequested
+                    | WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                logical_key: Key::Named(NamedKey::Escape),
+                                ..
+                            },
+                        ..
+                    },
+                window_id,
+            } => match windows.iter().position(|window| window.id() == *window_id) {
+                Some(index) => {
+                    let window = windows.remove(index);
+                    unsafe {
+                        app_base.device.device_wait_idle().unwrap(); //кадры окна еще могут быть в очереди
+                        window.destroy(&app_base);
+                    }
+                }
+                None => event_loop.exit(),
+            },
+            Event::WindowEvent {
+                window_id,
+                event:
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                logical_key: Key::Character(key),
+                                ..
+                            },
+                        ..
+                    },
+            } if *window_id == app_base.window.id() => match key.as_str() {
+                "n" => {
+                    windows_opened += 1;
+                    let window = WindowBuilder::new()
+                        .with_title(format!("vulkan_2d_triangle - window {}", windows_opened))
+                        .with_inner_size(winit::dpi::LogicalSize::new(480.0, 360.0))
+                        .build(event_loop)
+                        .unwrap();
+                    //тот же RenderPassDesc что у render_base, поэтому окно рисует pipeline`ами pipeline_set
+                    match WindowBase::new(
+                        &app_base,
+                        window,
+                        pipeline_cache.cache,
+                        samples,
+                        &render_base.desc,
+                        dynamic_rendering,
+                        &pipeline_set,
+                        &pipeline_config,
+                    ) {
+                        Ok(window) => {
+                            let shared = if window.pipeline_set.is_none() { "shared" } else { "own" };
+                            println!("Opened window {} with {} pipelines", windows_opened, shared);
+                            windows.push(window);
+                        }
+                        Err(err) => println!("Failed to open window: {}", err),
+                    }
+                }
+                "b" => {
+                    background = (background + 1) % backgrounds.len();
+                    render_base.desc.clear_color = backgrounds[background]; //без пересоздания render pass
+                    post_chain.source.desc.clear_color = backgrounds[background];
+                }
+                "p" => {
+                    chain_enabled = !chain_enabled;
+                    println!("Post chain enabled: {}", chain_enabled);
+                }
+                "c" => {
+                    chain_preset = (chain_preset + 1) % chain_presets.len();
+                    post_chain.effects = chain_presets[chain_preset].clone(); //проходы записываются заново каждый кадр
+                    println!("Post chain: {:?}", post_chain.effects);
+                }
+                //G и V переключают черно-белый режим и виньетку, параметры эффектов уходят push constant`ами
+                "g" => {
+                    if let Some(effects) = &mut render_base.desc.post_process {
+                        effects.grayscale = 1.0 - effects.grayscale;
+                    }
+                }
+                "v" => {
+                    if let Some(effects) = &mut render_base.desc.post_process {
+                        effects.vignette = if effects.vignette > 0.0 { 0.0 } else { 0.5 };
+                    }
+                }
+                "s" => {
+                    view_layout = view_layout.next();
+                    println!("View layout: {:?}", view_layout);
+                }
+                "w" | "x" | "t" => {
+                    match key.as_str() {
+                        "w" => debug_view.wireframe = !debug_view.wireframe,
+                        "x" => debug_view.points = !debug_view.points,
+                        _ => debug_view.triangle_indices = !debug_view.triangle_indices,
+                    }
+                    println!("Debug view: {}", debug_view.describe());
+                }
+                "i" => match &command_base.gpu_statistics {
+                    Some(statistics) => {
+                        let pixels = frames_base.extent.width as u64 * frames_base.extent.height as u64;
+                        let fragments: u64 = statistics.latest().iter().map(|(_, counters)| counters.fragment_invocations).sum();
+                        println!(
+                            "{}\ntotal fragment invocations {}, {:.2} per window pixel",
+                            statistics.summary(pixels),
+                            fragments,
+                            fragments as f64 / pixels as f64
+                        );
+                    }
+                    None => println!("Pipeline statistics queries are not supported by the device"),
+                },
+                _ => {}
+            },
+            Event::AboutToWait => {
+                //остальные окна рисуются и когда основное свернуто
+                let time = start_time.elapsed().as_secs_f32();
+                for window in &mut windows {
+                    window
+                        .render(&app_base, pipeline_cache.cache, &pipeline_set, &pipeline_config, |batcher, extent| {
+                            draw_scene(
+                                batcher,
+                                extent,
+                                &sprite,
+                                &atlas_sprites,
+                                &spinner,
+                                time,
+                                fonts.as_ref().map(|(bitmap, sdf)| (bitmap, sdf)),
+                            )
+                        })
+                        .unwrap();
+                }
+
+                let size = app_base.window.inner_size();
+                if size.width == 0 || size.height == 0 {
+                    frame_stats.skip();
+                    return; //окно свернуто, рисовать некуда
+                }
+                frame_limiter.wait();
+
+                if swapchain_dirty {
+                    frame_stats.skip();
+                    unsafe {
+                        app_base.device.device_wait_idle().unwrap();
+                        post_chain.destroy(&app_base.device); //наборы pipeline`ов переживают свои проходы, см. PipelineSet::recreate
+                        render_base.destroy(&app_base.device);
+                        overlay_render_base.destroy(&app_base.device);
+                        frames_base.destroy(&app_base.device);
+                    }
+                    frames_base = FramesBase::new(
+                        &app_base.instance,
+                        &app_base.device,
+                        app_base.surface,
+                        &app_base.surface_loader,
+                        app_base.physical_device,
+                        app_base.queue_family_index,
+                        &app_base.window,
+                    )
+                    .unwrap();
+                    render_base = RenderBase::new(
+                        &app_base.device,
+                        pipeline_cache.cache,
+                        &app_base.device_memory_properties,
+                        &frames_base,
+                        samples,
+                        &render_base.desc, //clear_color мог поменяться во время работы
+                        dynamic_rendering,
+                    )
+                    .unwrap();
+                    overlay_render_base = RenderBase::new(
+                        &app_base.device,
+                        pipeline_cache.cache,
+                        &app_base.device_memory_properties,
+                        &frames_base,
+                        samples,
+                        &overlay_render_base.desc,
+                        dynamic_rendering,
+                    )
+                    .unwrap();
+                    pipeline_set
+                        .recreate(
+                            &app_base.device,
+                            pipeline_cache.cache,
+                            render_base.pass_info(),
+                            frames_base.extent,
+                            &pipeline_config,
+                            &app_base.features,
+                        )
+                        .unwrap();
+                    overlay_pipeline_set
+                        .recreate(
+                            &app_base.device,
+                            pipeline_cache.cache,
+                            overlay_render_base.pass_info(),
+                            frames_base.extent,
+                            &pipeline_config,
+                            &app_base.features,
+                        )
+                        .unwrap();
+                    post_chain = PostChain::new(
+                        &app_base,
+                        pipeline_cache.cache,
+                        command_base.pool,
+                        frames_base.extent,
+                        &post_chain.source.desc,
+                        render_base.render_pass,
+                        render_base.format,
+                        render_base.samples,
+                        post_chain.effects.clone(),
+                    )
+                    .unwrap();
+                    chain_pipeline_set
+                        .recreate(
+                            &app_base.device,
+                            pipeline_cache.cache,
+                            post_chain.source.pass_info(),
+                            frames_base.extent,
+                            &pipeline_config,
+                            &app_base.features,
+                        )
+                        .unwrap();
+                    swapchain_dirty = false;
+                }
+
+                let time = start_time.elapsed().as_secs_f32();
+                particles.emitter.position = [
+                    frames_base.extent.width as f32 * (0.5 + 0.3 * time.sin()),
+                    frames_base.extent.height as f32 - 40.0,
+                ];
+                particles.update(time - last_frame_time, time);
+                last_frame_time = time;
+
+                minimap_batcher.begin(minimap.extent());
+                minimap_pipeline_set.bind(&mut minimap_batcher);
+                draw_minimap(&mut minimap_batcher, minimap.extent(), time);
+
+                batcher.begin(frames_base.extent);
+                if chain_enabled {
+                    chain_pipeline_set.bind(&mut batcher);
+                } else {
+                    pipeline_set.bind(&mut batcher);
+                }
+                batcher.set_debug_view(debug_view);
+                //сцена рисуется в мировых координатах размера окна, каждый вид показывает ее своей камерой
+                for view in view_layout.views(frames_base.extent, time) {
+                    if view.rect != ViewRect::FULL {
+                        let rect = view.rect.pixels(frames_base.extent);
+                        batcher.draw_rect(
+                            [rect.offset.x as f32, rect.offset.y as f32],
+                            [rect.extent.width as f32, rect.extent.height as f32],
+                            [0.05, 0.05, 0.1, 1.0],
+                        ); //фон вида, под картинкой в картинке иначе видна основная сцена
+                    }
+                    batcher.begin_view(&view);
+                    draw_scene(
+                        &mut batcher,
+                        frames_base.extent,
+                        &sprite,
+                        &atlas_sprites,
+                        &spinner,
+                        time,
+                        fonts.as_ref().map(|(bitmap, sdf)| (bitmap, sdf)),
+                    );
+                    batcher.end_view();
+                }
+                //результат offscreen прохода этого же кадра
+                let minimap_position = [frames_base.extent.width as f32 - 202.0, 48.0];
+                batcher.draw_rect(
+                    [minimap_position[0] - 2.0, minimap_position[1] - 2.0],
+                    [minimap_sprite.size[0] + 4.0, minimap_sprite.size[1] + 4.0],
+                    [0.8, 0.8, 0.8, 1.0],
+                );
+                batcher.draw_sprite(&minimap_sprite, minimap_position, minimap_sprite.size, [1.0, 1.0, 1.0, 1.0]);
+                batcher.draw_debug_overlay(fonts.as_ref().map(|(_, sdf)| sdf));
+
+                overlay_batcher.begin(frames_base.extent);
+                overlay_pipeline_set.bind(&mut overlay_batcher);
+                overlay_batcher.draw_rect([frames_base.extent.width as f32 - 250.0, 10.0], [240.0, 28.0], [0.0, 0.0, 0.0, 0.6]);
+                if let Some((_, sdf)) = &fonts {
+                    let style = TextStyle {
+                        size: 14.0,
+                        ..Default::default()
+                    };
+                    overlay_batcher.draw_text(
+                        sdf,
+                        "Overlay pass (LOAD), B: фон",
+                        [frames_base.extent.width as f32 - 242.0, 16.0],
+                        &style,
+                        [1.0, 1.0, 1.0, 1.0],
+                    );
+                    //средние за последние кадры, мс: pass 0 миникарта, pass 1 сцена, дальше основной и overlay проходы
+                    if let Some(gpu_timer) = &command_base.gpu_timer {
+                        let report = format!("GPU ms: {}", gpu_timer.report());
+                        let position = [10.0, frames_base.extent.height as f32 - 30.0];
+                        overlay_batcher.draw_rect(position, [sdf.glyphs.layout(&report, &style).size[0] + 16.0, 24.0], [0.0, 0.0, 0.0, 0.6]);
+                        overlay_batcher.draw_text(sdf, &report, [position[0] + 8.0, position[1] + 4.0], &style, [1.0, 1.0, 1.0, 1.0]);
+                    }
+                }
+
+                let targets = [&minimap, &post_chain.source];
+                let result = command_base.render_frame(
+                    &app_base.device,
+                    app_base.present_queue,
+                    &frames_base,
+                    &targets[..if chain_enabled { 2 } else { 1 }],
+                    chain_enabled.then_some(&post_chain),
+                    &[&render_base, &overlay_render_base],
+                    |command_buffer, _| particles.record(&app_base.device, command_buffer),
+                    |command_buffer, frame, pass| {
+                        //со включенной цепочкой сцена рисуется в source, а основной проход только копирует результат
+                        let batcher = match (pass, chain_enabled) {
+                            (0, _) => &mut minimap_batcher,
+                            (1, _) => {
+                                batcher
+                                    .flush(&app_base.device, &app_base.device_memory_properties, command_buffer, frame)
+                                    .unwrap();
+                                //частицы поверх сцены, pipeline под тот проход в который сейчас рисуется сцена
+                                let particle_pipeline =
+                                    if chain_enabled { &chain_pipeline_set.particles } else { &pipeline_set.particles };
+                                return particles.draw(&app_base.device, command_buffer, particle_pipeline, frames_base.extent, 6.0);
+                            }
+                            (2, true) => return post_chain.blit(&app_base.device, command_buffer),
+                            _ => &mut overlay_batcher,
+                        };
+                        batcher
+                            .flush(
+                                &app_base.device,
+                                &app_base.device_memory_properties,
+                                command_buffer,
+                                frame,
+                            )
+                            .unwrap()
+                    },
+                );
+                match result {
+                    Ok(suboptimal) => {
+                        swapchain_dirty |= suboptimal;
+                        frame_stats.record(Instant::now(), &command_base.frame_timings);
+                        if title_updated.elapsed().as_secs_f32() >= 0.5 {
+                            title_updated = Instant::now();
+                            app_base.window.set_title(&format!(
+                                "vulkan_2d_triangle - {:.0} FPS, {:.2} ms (p99 {:.2} ms)",
+                                frame_stats.fps(),
+                                frame_stats.frame.average(),
+                                frame_stats.frame.percentile(0.99)
+                            ));
+                        }
+                    }
+                    Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => swapchain_dirty = true,
+                    Err(err) => panic!("Render frame error: {:?}", err),
+                }
+            }
+            _ => (),
+        })
+        .unwrap();
+    println!("CPU frame timings, ms over the last {} frames:\n{}", frame_stats.frame.len(), frame_stats.report());
+
+    unsafe {
+        app_base.device.device_wait_idle().unwrap();
+        for window in &windows {
+            window.destroy(&app_base);
+        }
+        //кеш содержит и pipeline`ы пересозданные при изменении размера окна
+        if let Err(err) = pipeline_cache.save(&app_base.device) {
+            println!("Failed to save pipeline cache: {}", err);
+        }
+        pipeline_cache.destroy(&app_base.device);
+        batcher.destroy(&app_base.device);
+        overlay_batcher.destroy(&app_base.device);
+        minimap_batcher.destroy(&app_base.device);
+        minimap_pipeline_set.destroy(&app_base.device);
+        minimap.destroy(&app_base.device);
+        chain_pipeline_set.destroy(&app_base.device);
+        post_chain.destroy(&app_base.device);
+        particles.destroy(&app_base.device);
+        command_base.destroy(&app_base.device);
+        pipeline_set.destroy(&app_base.device);
+        overlay_pipeline_set.destroy(&app_base.device);
+        texture.destroy(&app_base.device);
+        atlas.destroy(&app_base.device);
+        if let Some((bitmap, sdf)) = &fonts {
+            bitmap.destroy(&app_base.device);
+            sdf.destroy(&app_base.device);
+        }
+        texture_descriptors.destroy(&app_base.device);
+        render_base.destroy(&app_base.device);
+        overlay_render_base.destroy(&app_base.device);
+        frames_base.destroy(&app_base.device);
+    }
+}
+
+//демонстрационная сцена, координаты в пикселях от левого верхнего угла
+/*миникарта: вращающиеся метки вокруг центра, координаты в пикселях цели, а не окна*/
+fn draw_minimap(batcher: &mut Batcher, extent: vk::Extent2D, time: f32) {
+    let center = [extent.width as f32 * 0.5, extent.height as f32 * 0.5];
+    let radius = extent.width.min(extent.height) as f32 * 0.4;
+    batcher.draw_line([center[0] - radius, center[1]], [center[0] + radius, center[1]], 1.0, [0.3, 0.4, 0.5, 1.0]);
+    batcher.draw_line([center[0], center[1] - radius], [center[0], center[1] + radius], 1.0, [0.3, 0.4, 0.5, 1.0]);
+    for i in 0..5 {
+        let angle = time * (0.3 + i as f32 * 0.15) + i as f32 * 1.3;
+        let distance = radius * (0.3 + i as f32 * 0.15);
+        let position = [center[0] + angle.cos() * distance, center[1] + angle.sin() * distance];
+        batcher.draw_circle(position, 5.0, [1.0, 0.5 + i as f32 * 0.1, 0.2, 1.0]);
+    }
+    batcher.draw_circle(center, 4.0, [0.3, 1.0, 0.4, 1.0]); //игрок
+}
+
+/*раскладки видов демо, переключаются клавишей S*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ViewLayout {
+    Single,
+    SplitScreen,
+    PictureInPicture,
+}
+
+impl ViewLayout {
+    fn next(self) -> Self {
+        match self {
+            ViewLayout::Single => ViewLayout::SplitScreen,
+            ViewLayout::SplitScreen => ViewLayout::PictureInPicture,
+            ViewLayout::PictureInPicture => ViewLayout::Single,
+        }
+    }
+
+    /*виды для сцены размером окна extent: в первом вся сцена, во втором приближенная и покачивающаяся камера,
+    в широком окне виды стоят рядом, в высоком друг под другом,
+    в картинке в картинке увеличенный центр сцены*/
+    fn views(self, extent: vk::Extent2D, time: f32) -> Vec<View> {
+        let world = [extent.width as f32, extent.height as f32];
+        let size = |rect: ViewRect| {
+            let pixels = rect.pixels(extent);
+            [pixels.extent.width as f32, pixels.extent.height as f32]
+        };
+        let full = View {
+            rect: ViewRect::FULL,
+            camera: Camera::screen(world),
+        };
+        match self {
+            ViewLayout::Single => vec![full],
+            ViewLayout::SplitScreen => {
+                let halves = if extent.width >= extent.height { ViewRect::columns(2) } else { ViewRect::rows(2) };
+                let [left, right] = [halves[0], halves[1]];
+                let overview = Camera::fit(world, size(left));
+                let follow = Camera {
+                    center: [world[0] * (0.5 + 0.2 * (time * 0.5).sin()), world[1] * 0.5],
+                    zoom: overview.zoom * 2.0,
+                    rotation: 0.15 * time.sin(),
+                };
+                vec![View { rect: left, camera: overview }, View { rect: right, camera: follow }]
+            }
+            ViewLayout::PictureInPicture => {
+                let inset = ViewRect::picture_in_picture(0.3, 0.02);
+                let magnifier = Camera {
+                    zoom: 2.5 * Camera::fit(world, size(inset)).zoom,
+                    ..Camera::screen(world)
+                };
+                vec![full, View { rect: inset, camera: magnifier }]
+            }
+        }
+    }
+}
+
+fn draw_scene(
+    batcher: &mut Batcher,
+    extent: vk::Extent2D,
+    sprite: &Sprite,
+    atlas_sprites: &[Sprite],
+    spinner: &SpriteAnimation,
+    time: f32,
+    fonts: Option<(&FontAtlas, &FontAtlas)>, //bitmap и SDF шрифты
+) {
+    let (width, height) = (extent.width as f32, extent.height as f32);
+
+    batcher.draw_mesh(
+        &[
+            Vertex::new([width * 0.5, height * 0.25], [1.0, 0.0, 0.0, 1.0]),
+            Vertex::new([width * 0.75, height * 0.75], [0.0, 1.0, 0.0, 1.0]),
+            Vertex::new([width * 0.25, height * 0.75], [0.0, 0.0, 1.0, 1.0]),
+        ],
+        &[0, 1, 2],
+    );
+    batcher.draw_rect([20.0, 20.0], [120.0, 60.0], [0.9, 0.6, 0.1, 1.0]);
+    batcher.draw_circle([width - 80.0, 80.0], 50.0, [0.2, 0.7, 0.9, 1.0]);
+    batcher.draw_line([20.0, height - 20.0], [width - 20.0, height - 60.0], 4.0, [1.0, 1.0, 1.0, 1.0]);
+    batcher.draw_polygon(
+        &[[60.0, 160.0], [110.0, 190.0], [100.0, 250.0], [40.0, 250.0], [20.0, 200.0]],
+        [0.6, 0.3, 0.8, 1.0],
+    );
+
+    let zigzag = [[200.0, height - 150.0], [260.0, height - 100.0], [320.0, height - 150.0], [380.0, height - 100.0]];
+    for (i, (join, cap)) in [
+        (LineJoin::Miter, LineCap::Butt),
+        (LineJoin::Bevel, LineCap::Square),
+        (LineJoin::Round, LineCap::Round),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let offset = i as f32 * 220.0;
+        let points: Vec<[f32; 2]> = zigzag.iter().map(|&[x, y]| [x + offset, y]).collect();
+        let style = StrokeStyle {
+            width: 12.0,
+            join,
+            cap,
+            ..Default::default()
+        };
+        batcher.draw_polyline(&points, false, &style, [0.9, 0.9, 0.3, 1.0]);
+    }
+
+    //вогнутая рамка с двумя дырами
+    batcher.draw_polygon_with_holes(
+        &[[width - 260.0, 180.0], [width - 40.0, 180.0], [width - 40.0, 320.0], [width - 150.0, 260.0], [width - 260.0, 320.0]],
+        &[
+            [[width - 240.0, 200.0], [width - 200.0, 200.0], [width - 200.0, 240.0], [width - 240.0, 240.0]],
+            [[width - 100.0, 200.0], [width - 60.0, 200.0], [width - 60.0, 240.0], [width - 100.0, 240.0]],
+        ],
+        [0.3, 0.8, 0.4, 1.0],
+    );
+
+    batcher.draw_sprite(sprite, [width * 0.5 - 64.0, 20.0], [128.0, 128.0], [1.0, 1.0, 1.0, 1.0]);
+    batcher.draw_sprite(sprite, [width * 0.5 + 80.0, 52.0], sprite.size, [1.0, 0.5, 0.5, 1.0]);
+
+    //спрайты из одного атласа идут одной партией
+    for (i, atlas_sprite) in atlas_sprites.iter().enumerate() {
+        batcher.draw_sprite(atlas_sprite, [20.0 + i as f32 * 72.0, 290.0], atlas_sprite.size, [1.0, 1.0, 1.0, 1.0]);
+    }
+    batcher.draw_sprite(spinner.frame(time), [236.0, 290.0], [64.0, 64.0], [1.0, 1.0, 1.0, 1.0]);
+
+    if let Some((bitmap, sdf)) = fonts {
+        let [label_width, label_height] =
+            batcher.draw_text(bitmap, "Bitmap 16px: AVAW Tokyo, kerning", [20.0, 370.0], &TextStyle::default(), [1.0, 1.0, 1.0, 1.0]);
+        batcher.draw_line([20.0, 372.0 + label_height], [20.0 + label_width, 372.0 + label_height], 1.0, [0.6, 0.6, 0.6, 1.0]);
+        let style = TextStyle {
+            size: 20.0,
+            align: TextAlign::Center,
+            max_width: Some(260.0),
+            ..Default::default()
+        };
+        batcher.draw_text(
+            sdf,
+            "SDF текст переносится по словам и выравнивается по центру блока",
+            [20.0, 400.0],
+            &style,
+            [0.9, 0.8, 0.4, 1.0],
+        );
+        //SDF не размывается при увеличении
+        let pulse = 40.0 + 16.0 * (time * 2.0).sin();
+        let style = TextStyle {
+            size: pulse,
+            align: TextAlign::Right,
+            ..Default::default()
+        };
+        batcher.draw_text(sdf, "Vulkan", [width - 220.0, height - 220.0], &style, [0.5, 0.8, 1.0, 1.0]);
+    }
+
+    //по квадрату на каждый режим смешивания, поверх серого фона полупрозрачные красный и синий круги
+    for (i, mode) in BlendMode::ALL.into_iter().enumerate() {
+        let x = 20.0 + i as f32 * 60.0;
+        let y = height - 100.0;
+        batcher.set_blend_mode(BlendMode::Alpha);
+        batcher.draw_rect([x, y], [50.0, 50.0], [0.5, 0.5, 0.5, 1.0]);
+        batcher.set_blend_mode(mode);
+        let (red, blue) = if mode == BlendMode::PremultipliedAlpha {
+            ([0.6, 0.18, 0.12, 0.6], [0.12, 0.24, 0.6, 0.6])
+        } else {
+            ([1.0, 0.3, 0.2, 0.6], [0.2, 0.4, 1.0, 0.6])
+        };
+        batcher.draw_circle([x + 19.0, y + 21.0], 15.0, red);
+        batcher.draw_circle([x + 31.0, y + 29.0], 15.0, blue);
+        if let Some((_, sdf)) = fonts {
+            batcher.set_blend_mode(BlendMode::Alpha);
+            let style = TextStyle {
+                size: 10.0,
+                align: TextAlign::Center,
+                max_width: Some(50.0),
+                ..Default::default()
+            };
+            batcher.draw_text(sdf, &format!("{:?}", mode), [x, y + 52.0], &style, [1.0, 1.0, 1.0, 1.0]);
+        }
+    }
+    batcher.set_blend_mode(BlendMode::Alpha);
+}
+
+//процедурно сгенерированные картинки для демонстрации атласа
+fn build_demo_atlas() -> AtlasBuilder {
+    let mut builder = AtlasBuilder::new(2);
+    builder.add_image(
+        "ring",
+        image::RgbaImage::from_fn(64, 64, |x, y| {
+            let distance = ((x as f32 - 31.5).powi(2) + (y as f32 - 31.5).powi(2)).sqrt();
+            if (20.0..30.0).contains(&distance) { image::Rgba([240, 80, 60, 255]) } else { image::Rgba([0, 0, 0, 0]) }
+        }),
+    );
+    builder.add_image(
+        "diamond",
+        image::RgbaImage::from_fn(48, 64, |x, y| {
+            let inside = (x as f32 - 23.5).abs() / 24.0 + (y as f32 - 31.5).abs() / 32.0 <= 1.0;
+            if inside { image::Rgba([80, 200, 240, 255]) } else { image::Rgba([0, 0, 0, 0]) }
+        }),
+    );
+    builder.add_image(
+        "gradient",
+        image::RgbaImage::from_fn(64, 32, |x, y| image::Rgba([(x * 4) as u8, (y * 8) as u8, 160, 255])),
+    );
+    //лист 4x2 кадров 32x32, в каждом кадре точка повернута на 45 градусов дальше
+    let sheet = image::RgbaImage::from_fn(128, 64, |x, y| {
+        let frame = (y / 32) * 4 + x / 32;
+        let angle = frame as f32 * std::f32::consts::FRAC_PI_4;
+        let (cx, cy) = (15.5 + 10.0 * angle.cos(), 15.5 + 10.0 * angle.sin());
+        let distance = (((x % 32) as f32 - cx).powi(2) + (((y % 32) as f32) - cy).powi(2)).sqrt();
+        if distance < 5.0 { image::Rgba([250, 220, 60, 255]) } else { image::Rgba([30, 30, 30, 255]) }
+    });
+    builder.add_sheet("spinner", &sheet, 4, 2, 0.1, true).unwrap();
+    builder
+}
@@ -0,0 +1,9 @@
+#version 450
+layout(location = 0) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+//последний шаг цепочки эффектов, копия результата в кадр swapchain
+void main() {
+    outColor = vec4(texture(source, fragUV).rgb, 1.0);
+}
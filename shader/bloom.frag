@@ -0,0 +1,29 @@
+#version 450
+layout(location = 0) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform Params {
+    vec4 params; //x порог яркости, y сила свечения, z радиус в пикселях
+    vec2 resolution;
+} pc;
+
+vec3 bright(vec2 uv) {
+    vec3 color = texture(source, uv).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return color * max(luminance - pc.params.x, 0.0) / max(luminance, 0.0001);
+}
+
+//свечение за один проход: яркие пиксели с двух колец вокруг фрагмента прибавляются к исходному цвету
+void main() {
+    vec4 base = texture(source, fragUV);
+    vec2 radius = pc.params.z / pc.resolution;
+    vec3 glow = vec3(0.0);
+    for (int i = 0; i < 12; i++) {
+        float angle = float(i) * 0.5235988; //30 градусов
+        vec2 direction = vec2(cos(angle), sin(angle));
+        glow += bright(fragUV + direction * radius * 0.5) * 0.6;
+        glow += bright(fragUV + direction * radius) * 0.4;
+    }
+    outColor = vec4(base.rgb + glow / 12.0 * pc.params.y, base.a);
+}
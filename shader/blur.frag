@@ -0,0 +1,23 @@
+#version 450
+layout(location = 0) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+//совпадает с ChainConstants в post.rs
+layout(push_constant) uniform Params {
+    vec4 params; //xy направление размытия в пикселях (радиус), один проход размывает только по одной оси
+    vec2 resolution;
+} pc;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+//гауссово размытие, горизонтальный и вертикальный проходы вместе дают двумерное
+void main() {
+    vec2 step = pc.params.xy / 4.0 / pc.resolution;
+    vec4 color = texture(source, fragUV) * weights[0];
+    for (int i = 1; i < 5; i++) {
+        color += texture(source, fragUV + step * float(i)) * weights[i];
+        color += texture(source, fragUV - step * float(i)) * weights[i];
+    }
+    outColor = color;
+}
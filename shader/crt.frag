@@ -0,0 +1,26 @@
+#version 450
+layout(location = 0) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform Params {
+    vec4 params; //x изгиб экрана, y сила строк развертки, z смещение каналов в пикселях
+    vec2 resolution;
+} pc;
+
+//кинескоп: бочкообразное искажение, сдвиг красного и синего каналов, темные строки и черная рамка за краем
+void main() {
+    vec2 centered = fragUV * 2.0 - 1.0;
+    centered *= 1.0 + pc.params.x * dot(centered, centered);
+    vec2 uv = centered * 0.5 + 0.5;
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        outColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec2 shift = vec2(pc.params.z / pc.resolution.x, 0.0);
+    vec3 color = vec3(texture(source, uv + shift).r, texture(source, uv).g, texture(source, uv - shift).b);
+    float scanline = 0.5 + 0.5 * cos(uv.y * pc.resolution.y * 3.14159265);
+    color *= 1.0 - pc.params.y * scanline;
+    outColor = vec4(color, 1.0);
+}
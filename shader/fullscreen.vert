@@ -0,0 +1,8 @@
+#version 450
+layout(location = 0) out vec2 fragUV;
+
+//один треугольник накрывающий весь экран, вершины из gl_VertexIndex без вершинного буффера
+void main() {
+    fragUV = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(fragUV * 2.0 - 1.0, 0.0, 1.0);
+}
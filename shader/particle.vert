@@ -0,0 +1,26 @@
+#version 450
+//атрибуты экземпляра читаются прямо из буффера который пишет particles.comp
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec4 color;
+
+layout(location = 0) out vec4 fragColor;
+layout(location = 1) out vec2 fragCorner;
+
+layout(push_constant) uniform Params {
+    vec2 resolution;
+    float size; //радиус частицы в пикселях
+} pc;
+
+const vec2 corners[6] = vec2[](
+    vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+    vec2(-1.0, -1.0), vec2(1.0, 1.0), vec2(-1.0, 1.0)
+);
+
+//квадрат из двух треугольников вокруг частицы, вершины из gl_VertexIndex
+void main() {
+    vec2 corner = corners[gl_VertexIndex];
+    vec2 pixel = position + corner * pc.size;
+    gl_Position = vec4(pixel / pc.resolution * 2.0 - 1.0, 0.0, 1.0);
+    fragColor = color;
+    fragCorner = corner;
+}
@@ -0,0 +1,16 @@
+#version 450
+layout(location = 0) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform Params {
+    vec4 params; //x размер крупного пикселя в пикселях экрана
+    vec2 resolution;
+} pc;
+
+//все фрагменты блока читают цвет из его центра
+void main() {
+    vec2 block = max(pc.params.x, 1.0) / pc.resolution;
+    vec2 uv = (floor(fragUV / block) + 0.5) * block;
+    outColor = texture(source, uv);
+}
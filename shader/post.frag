@@ -0,0 +1,34 @@
+#version 450
+layout(location = 0) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput sceneColor;
+
+//совпадает с PostConstants в post.rs
+layout(push_constant) uniform Params {
+    vec4 tint;
+    float vignette;
+    float vignetteRadius;
+    float grayscale;
+    float brightness;
+    float contrast;
+    float saturation;
+    vec2 resolution;
+} params;
+
+void main() {
+    vec3 color = subpassLoad(sceneColor).rgb; //пиксель сцены под этим фрагментом
+
+    //цветокоррекция
+    color = color * params.tint.rgb + params.brightness;
+    color = (color - 0.5) * params.contrast + 0.5;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(luminance), color, params.saturation);
+    color = mix(color, vec3(luminance), params.grayscale);
+
+    //виньетка, расстояние от центра с учетом соотношения сторон
+    vec2 centered = (fragUV - 0.5) * vec2(params.resolution.x / params.resolution.y, 1.0);
+    float distance = length(centered) / length(vec2(params.resolution.x / params.resolution.y, 1.0) * 0.5);
+    color *= 1.0 - params.vignette * smoothstep(params.vignetteRadius, 1.0, distance);
+
+    outColor = vec4(clamp(color, 0.0, 1.0), 1.0);
+}
@@ -0,0 +1,12 @@
+#version 450
+layout(location = 0) in vec4 fragColor;
+layout(location = 1) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform sampler2D texSampler;
+
+void main(){
+    float distance = texture(texSampler, fragUV).a; //0.5 на контуре глифа
+    float width = fwidth(distance); //сглаживание на ширину одного пикселя экрана при любом масштабе
+    float alpha = smoothstep(0.5 - width, 0.5 + width, distance);
+    outColor = vec4(fragColor.rgb, fragColor.a * alpha);
+}
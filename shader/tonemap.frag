@@ -0,0 +1,22 @@
+#version 450
+layout(location = 0) in vec2 fragUV;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform Params {
+    vec4 params; //x экспозиция, y 0 Reinhard, 1 ACES
+    vec2 resolution;
+} pc;
+
+//аппроксимация ACES filmic от Krzysztof Narkowicz
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+//перевод HDR цвета (после свечения и аддитивного смешивания может быть больше 1) в диапазон 0..1
+void main() {
+    vec4 sourceColor = texture(source, fragUV);
+    vec3 color = sourceColor.rgb * pc.params.x;
+    color = pc.params.y > 0.5 ? aces(color) : color / (1.0 + color);
+    outColor = vec4(color, sourceColor.a);
+}
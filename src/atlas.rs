@@ -0,0 +1,466 @@
+use crate::AppBase;
+use crate::texture::{Sprite, TextureBase, TextureDescriptors, TextureError, TextureOptions};
+use ash::Device;
+use ash::vk;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/*атлас текстур: много маленьких изображений упаковываются в одно большое,
+тогда спрайты с разными картинками рисуются одним набором дескрипторов и не разбивают партии Batcher`а*/
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Texture(TextureError),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    TooLarge, //изображения не поместились в атлас максимального размера
+    InvalidSheet { columns: u32, rows: u32 }, //лист спрайтов не делится на columns x rows непустых кадров
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Texture(err) => write!(f, "atlas texture error: {}", err),
+            AtlasError::Io(err) => write!(f, "atlas io error: {}", err),
+            AtlasError::Json(err) => write!(f, "atlas layout error: {}", err),
+            AtlasError::TooLarge => write!(f, "atlas images do not fit into the maximum atlas size"),
+            AtlasError::InvalidSheet { columns, rows } => {
+                write!(f, "sprite sheet cannot be split into {}x{} non-empty frames", columns, rows)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+impl From<TextureError> for AtlasError {
+    fn from(err: TextureError) -> Self {
+        AtlasError::Texture(err)
+    }
+}
+
+impl From<image::ImageError> for AtlasError {
+    fn from(err: image::ImageError) -> Self {
+        AtlasError::Texture(TextureError::Image(err))
+    }
+}
+
+impl From<vk::Result> for AtlasError {
+    fn from(err: vk::Result) -> Self {
+        AtlasError::Texture(TextureError::Vulkan(err))
+    }
+}
+
+impl From<std::io::Error> for AtlasError {
+    fn from(err: std::io::Error) -> Self {
+        AtlasError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for AtlasError {
+    fn from(err: serde_json::Error) -> Self {
+        AtlasError::Json(err)
+    }
+}
+
+//прямоугольник в пикселях атласа
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+//анимация из кадров атласа, кадры ссылаются на регионы по имени
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnimationDesc {
+    pub frames: Vec<String>,
+    pub frame_duration: f32, //секунды на кадр
+    pub looping: bool,
+}
+
+/*раскладка атласа, сохраняется в JSON рядом с PNG атласа чтобы не упаковывать заново при каждом запуске*/
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AtlasLayout {
+    pub width: u32,
+    pub height: u32,
+    pub regions: BTreeMap<String, AtlasRect>,
+    pub animations: BTreeMap<String, AnimationDesc>,
+}
+
+impl AtlasLayout {
+    pub fn to_json(&self) -> Result<String, AtlasError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, AtlasError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/*упаковщик прямоугольников методом skyline: хранится верхняя граница уже занятой области
+в виде ступенек, новый прямоугольник ставится туда где его нижний край окажется ниже всего*/
+pub struct SkylinePacker {
+    width: u32,
+    height: u32,
+    skyline: Vec<(u32, u32, u32)>, //ступеньки (x, y, ширина) слева направо
+}
+
+impl SkylinePacker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            skyline: vec![(0, 0, width)],
+        }
+    }
+
+    //высота на которую встанет прямоугольник шириной width начиная со ступеньки index, None если не влезает
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.skyline[index].0;
+        if x + width > self.width {
+            return None;
+        }
+        let mut remaining = width as i64;
+        let mut y = 0;
+        let mut i = index;
+        while remaining > 0 {
+            let (_, step_y, step_width) = self.skyline[i];
+            y = y.max(step_y);
+            if y + height > self.height {
+                return None;
+            }
+            remaining -= step_width as i64;
+            i += 1;
+        }
+        Some(y)
+    }
+
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<AtlasRect> {
+        let mut best: Option<(usize, u32, u32)> = None; //(ступенька, y, ширина ступеньки)
+        for index in 0..self.skyline.len() {
+            if let Some(y) = self.fit(index, width, height) {
+                let step_width = self.skyline[index].2;
+                let better = match best {
+                    None => true,
+                    Some((_, best_y, best_width)) => y < best_y || (y == best_y && step_width < best_width),
+                };
+                if better {
+                    best = Some((index, y, step_width));
+                }
+            }
+        }
+        let (index, y, _) = best?;
+        let x = self.skyline[index].0;
+
+        //новая ступенька поверх прямоугольника, перекрытые ступеньки справа обрезаются или удаляются
+        self.skyline.insert(index, (x, y + height, width));
+        let i = index + 1;
+        while let Some(&(step_x, step_y, step_width)) = self.skyline.get(i) {
+            let right = x + width;
+            if step_x >= right {
+                break;
+            }
+            let shrink = right - step_x;
+            if shrink >= step_width {
+                self.skyline.remove(i);
+            } else {
+                self.skyline[i] = (right, step_y, step_width - shrink);
+                break;
+            }
+        }
+        //соседние ступеньки одной высоты склеиваются
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].1 == self.skyline[i + 1].1 {
+                self.skyline[i].2 += self.skyline[i + 1].2;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+        Some(AtlasRect { x, y, width, height })
+    }
+}
+
+/*сборщик атласа на CPU: добавляем изображения и листы спрайтов, pack упаковывает их в одно изображение*/
+pub struct AtlasBuilder {
+    images: Vec<(String, RgbaImage)>,
+    animations: BTreeMap<String, AnimationDesc>,
+    padding: u32, //пустые пиксели между регионами, чтобы линейная фильтрация не подмешивала соседей
+}
+
+impl AtlasBuilder {
+    pub fn new(padding: u32) -> Self {
+        Self {
+            images: Vec::new(),
+            animations: BTreeMap::new(),
+            padding,
+        }
+    }
+
+    pub fn add_image(&mut self, name: &str, image: RgbaImage) {
+        self.images.push((name.to_string(), image));
+    }
+
+    /*лист спрайтов: сетка columns x rows одинаковых кадров слева направо сверху вниз,
+    кадры добавляются как регионы name/0, name/1 ... и объединяются в анимацию name*/
+    pub fn add_sheet(
+        &mut self,
+        name: &str,
+        sheet: &RgbaImage,
+        columns: u32,
+        rows: u32,
+        frame_duration: f32,
+        looping: bool,
+    ) -> Result<(), AtlasError> {
+        if columns == 0 || rows == 0 || sheet.width() < columns || sheet.height() < rows {
+            return Err(AtlasError::InvalidSheet { columns, rows });
+        }
+        let frame_width = sheet.width() / columns;
+        let frame_height = sheet.height() / rows;
+        let mut frames = Vec::with_capacity((columns * rows) as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                let frame_name = format!("{}/{}", name, frames.len());
+                let frame = image::imageops::crop_imm(sheet, column * frame_width, row * frame_height, frame_width, frame_height).to_image();
+                self.add_image(&frame_name, frame);
+                frames.push(frame_name);
+            }
+        }
+        self.animations.insert(
+            name.to_string(),
+            AnimationDesc {
+                frames,
+                frame_duration,
+                looping,
+            },
+        );
+        Ok(())
+    }
+
+    //упаковка в квадратный атлас по pack_square, высокие изображения первыми, так skyline получается ровнее
+    pub fn pack(mut self, max_size: u32) -> Result<(AtlasLayout, RgbaImage), AtlasError> {
+        self.images
+            .sort_by(|(_, a), (_, b)| b.height().cmp(&a.height()).then(b.width().cmp(&a.width())));
+
+        let sizes: Vec<[u32; 2]> = self.images.iter().map(|(_, image)| [image.width(), image.height()]).collect();
+        let (size, regions) = pack_square(&sizes, self.padding, max_size)?;
+        let mut atlas = RgbaImage::new(size, size);
+        let mut layout = AtlasLayout {
+            width: size,
+            height: size,
+            regions: BTreeMap::new(),
+            animations: self.animations,
+        };
+        for ((name, image), rect) in self.images.into_iter().zip(regions) {
+            image::imageops::replace(&mut atlas, &image, rect.x as i64, rect.y as i64);
+            layout.regions.insert(name, rect);
+        }
+        Ok((layout, atlas))
+    }
+}
+
+/*упаковка прямоугольников sizes (ширина, высота) в квадрат, который начинается с 256x256 (или max_size, если он меньше)
+и удваивается пока все не поместится, но не больше max_size. Справа и снизу каждого прямоугольника остается padding пустых пикселей.
+Возвращает сторону квадрата и места прямоугольников в порядке sizes*/
+pub fn pack_square(sizes: &[[u32; 2]], padding: u32, max_size: u32) -> Result<(u32, Vec<AtlasRect>), AtlasError> {
+    let mut size = 256.min(max_size);
+    loop {
+        let mut packer = SkylinePacker::new(size, size);
+        let rects: Option<Vec<AtlasRect>> = sizes
+            .iter()
+            .map(|&[width, height]| {
+                let rect = packer.insert(width + padding, height + padding)?;
+                Some(AtlasRect { width, height, ..rect })
+            })
+            .collect();
+        if let Some(rects) = rects {
+            return Ok((size, rects));
+        }
+        if size >= max_size {
+            return Err(AtlasError::TooLarge);
+        }
+        size = (size * 2).min(max_size);
+    }
+}
+
+//анимация из спрайтов атласа
+#[derive(Clone, Debug)]
+pub struct SpriteAnimation {
+    pub frames: Vec<Sprite>,
+    pub frame_duration: f32,
+    pub looping: bool,
+}
+
+impl SpriteAnimation {
+    //кадр на момент time секунд от начала анимации, без повтора анимация останавливается на последнем кадре
+    pub fn frame(&self, time: f32) -> &Sprite {
+        let index = (time.max(0.0) / self.frame_duration) as usize;
+        if self.looping {
+            &self.frames[index % self.frames.len()]
+        } else {
+            &self.frames[index.min(self.frames.len() - 1)]
+        }
+    }
+}
+
+/*атлас загруженный на GPU: одна текстура, один набор дескрипторов и раскладка регионов*/
+pub struct Atlas {
+    pub texture: TextureBase,
+    pub descriptor_set: vk::DescriptorSet,
+    pub layout: AtlasLayout,
+}
+
+impl Atlas {
+    pub fn new(
+        app_base: &AppBase,
+        command_pool: vk::CommandPool,
+        descriptors: &TextureDescriptors,
+        layout: AtlasLayout,
+        image: &RgbaImage,
+        options: &TextureOptions,
+    ) -> Result<Self, AtlasError> {
+        let texture = TextureBase::from_rgba(app_base, command_pool, image.width(), image.height(), image.as_raw(), options)?;
+        let descriptor_set = descriptors.allocate(&app_base.device, &texture)?;
+        Ok(Self {
+            texture,
+            descriptor_set,
+            layout,
+        })
+    }
+
+    //атлас сохраненный ранее через AtlasBuilder::pack и save
+    pub fn load<P: AsRef<Path>>(
+        app_base: &AppBase,
+        command_pool: vk::CommandPool,
+        descriptors: &TextureDescriptors,
+        image_path: P,
+        layout_path: P,
+        options: &TextureOptions,
+    ) -> Result<Self, AtlasError> {
+        let layout = AtlasLayout::from_json(&std::fs::read_to_string(layout_path)?)?;
+        let image = image::open(image_path)?.to_rgba8();
+        Self::new(app_base, command_pool, descriptors, layout, &image, options)
+    }
+
+    //сохранение упакованного изображения и раскладки для повторного использования
+    pub fn save<P: AsRef<Path>>(layout: &AtlasLayout, image: &RgbaImage, image_path: P, layout_path: P) -> Result<(), AtlasError> {
+        image.save(image_path)?;
+        std::fs::write(layout_path, layout.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn sprite(&self, name: &str) -> Option<Sprite> {
+        let rect = self.layout.regions.get(name)?;
+        let (width, height) = (self.layout.width as f32, self.layout.height as f32);
+        Some(Sprite {
+            texture: self.descriptor_set,
+            uv_min: [rect.x as f32 / width, rect.y as f32 / height],
+            uv_max: [(rect.x + rect.width) as f32 / width, (rect.y + rect.height) as f32 / height],
+            size: [rect.width as f32, rect.height as f32],
+        })
+    }
+
+    pub fn animation(&self, name: &str) -> Option<SpriteAnimation> {
+        let desc = self.layout.animations.get(name)?;
+        let frames = desc
+            .frames
+            .iter()
+            .map(|frame| self.sprite(frame))
+            .collect::<Option<Vec<_>>>()?;
+        if frames.is_empty() {
+            return None;
+        }
+        Some(SpriteAnimation {
+            frames,
+            frame_duration: desc.frame_duration,
+            looping: desc.looping,
+        })
+    }
+
+    //набор дескрипторов освобождается вместе с пулом TextureDescriptors
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe { self.texture.destroy(device) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn overlaps(a: &AtlasRect, b: &AtlasRect) -> bool {
+        a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    #[test]
+    fn fills_exactly() {
+        let mut packer = SkylinePacker::new(64, 64);
+        for _ in 0..16 {
+            assert!(packer.insert(16, 16).is_some());
+        }
+        assert_eq!(packer.insert(1, 1), None);
+    }
+
+    #[test]
+    fn rejects_too_large() {
+        let mut packer = SkylinePacker::new(64, 64);
+        assert_eq!(packer.insert(65, 1), None);
+        assert_eq!(packer.insert(1, 65), None);
+    }
+
+    #[test]
+    fn sheet_becomes_animation() {
+        let mut builder = AtlasBuilder::new(1);
+        builder.add_sheet("walk", &RgbaImage::new(96, 32), 3, 1, 0.1, true).unwrap();
+        let (layout, _) = builder.pack(1024).unwrap();
+        let walk = &layout.animations["walk"];
+        assert_eq!(walk.frames, ["walk/0", "walk/1", "walk/2"]);
+        assert!(walk.frames.iter().all(|frame| layout.regions[frame].width == 32));
+        assert_eq!(AtlasLayout::from_json(&layout.to_json().unwrap()).unwrap(), layout);
+    }
+
+    #[test]
+    fn small_max_size_limits_the_atlas() {
+        let mut builder = AtlasBuilder::new(1);
+        builder.add_image("dot", RgbaImage::new(16, 16));
+        let (layout, image) = builder.pack(64).unwrap();
+        assert_eq!((layout.width, layout.height, image.width(), image.height()), (64, 64, 64, 64));
+
+        let mut builder = AtlasBuilder::new(1);
+        builder.add_image("big", RgbaImage::new(100, 100));
+        assert!(matches!(builder.pack(64), Err(AtlasError::TooLarge)));
+    }
+
+    #[test]
+    fn empty_sheet_grid_is_an_error() {
+        let mut builder = AtlasBuilder::new(0);
+        let sheet = RgbaImage::new(4, 4);
+        for (columns, rows) in [(0, 1), (1, 0), (5, 1)] {
+            let result = builder.add_sheet("sheet", &sheet, columns, rows, 0.1, false);
+            assert!(matches!(result, Err(AtlasError::InvalidSheet { .. })));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn packed_rects_do_not_overlap(sizes in prop::collection::vec((1u32..64, 1u32..64), 1..80)) {
+            let mut packer = SkylinePacker::new(256, 256);
+            let mut placed: Vec<AtlasRect> = Vec::new();
+            for (width, height) in sizes {
+                if let Some(rect) = packer.insert(width, height) {
+                    prop_assert_eq!((rect.width, rect.height), (width, height));
+                    prop_assert!(rect.x + rect.width <= 256 && rect.y + rect.height <= 256);
+                    prop_assert!(placed.iter().all(|other| !overlaps(&rect, other)));
+                    placed.push(rect);
+                }
+            }
+        }
+    }
+}
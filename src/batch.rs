@@ -0,0 +1,623 @@
+use crate::blend::BlendMode;
+use crate::buffer::BufferBase;
+use crate::debug_view::{DebugView, MAX_INDEX_LABELS, centroid, unique_edges};
+use crate::stroke::{StrokeStyle, arc_segments, stroke_polyline};
+use crate::text::{FontAtlas, GlyphMode, TextStyle};
+use crate::texture::Sprite;
+use crate::triangulate::triangulate;
+use crate::vertex::Vertex;
+use crate::view::{Camera, View, ViewRect, viewport};
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::f32::consts::PI;
+
+const INITIAL_BUFFER_SIZE: vk::DeviceSize = 64 * 1024; //стартовый размер вершинного+индексного буффера на кадр, растет при необходимости
+
+/*одна партия отрисовки, все индексы партии рисуются одним draw call с одним pipeline и одной текстурой*/
+#[derive(Clone, Copy, Debug)]
+pub struct Batch {
+    pub pipeline: vk::Pipeline,
+    pub pipeline_layout: vk::PipelineLayout,
+    pub texture: vk::DescriptorSet, //null если партия без текстуры
+    pub view: usize, //индекс вида кадра, задает viewport и scissor партии
+    pub first_index: u32,
+    pub index_count: u32,
+}
+
+/*Batcher собирает фигуры immediate-mode стиля (draw_rect, draw_circle и т.д.) в один массив вершин и индексов за кадр,
+координаты задаются в пикселях от левого верхнего угла окна и переводятся в NDC Vulkan (-1..1, ось Y вниз),
+после begin_view координаты мировые, их переводит в область вида его камера,
+партии разделяются при смене pipeline или текстуры, flush загружает данные в буффер текущего кадра и записывает draw call`ы
+в command buffer внутри render pass который начат с RenderBase::render_pass*/
+pub struct Batcher {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    batches: Vec<Batch>,
+    pipelines: [vk::Pipeline; BlendMode::COUNT], //варианты pipeline по режимам смешивания, индекс BlendMode::index
+    pipeline_layout: vk::PipelineLayout,
+    sprite_pipelines: [vk::Pipeline; BlendMode::COUNT], //pipeline с sprite.vert/sprite.frag для текстурированных вершин
+    sprite_pipeline_layout: vk::PipelineLayout,
+    sdf_pipelines: [vk::Pipeline; BlendMode::COUNT], //pipeline с text_sdf.frag для шрифтов в режиме GlyphMode::Sdf
+    sdf_pipeline_layout: vk::PipelineLayout,
+    wireframe_pipeline: vk::Pipeline, //PolygonMode::LINE для DebugView::wireframe, null если устройство не умеет
+    wireframe_pipeline_layout: vk::PipelineLayout,
+    debug_view: DebugView,
+    blend_mode: BlendMode,
+    extent: vk::Extent2D,
+    views: Vec<(vk::Rect2D, Camera)>, //виды кадра в пикселях окна, первый всегда окно целиком с экранной камерой
+    view: usize, //вид для следующих фигур
+    buffers: Vec<BufferBase>, //по одному буфферу на каждый кадр в полете, чтобы не перезаписывать данные которые GPU еще читает
+} //immediate mode 2d shapes
+
+impl Batcher {
+    pub fn new(
+        device: &Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        frames_in_flight: usize,
+    ) -> VkResult<Self> {
+        let buffers = (0..frames_in_flight)
+            .map(|_| Self::create_buffer(device, memory_properties, INITIAL_BUFFER_SIZE))
+            .collect::<VkResult<Vec<_>>>()?;
+
+        Ok(Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            batches: Vec::new(),
+            pipelines: [vk::Pipeline::null(); BlendMode::COUNT],
+            pipeline_layout: vk::PipelineLayout::null(),
+            sprite_pipelines: [vk::Pipeline::null(); BlendMode::COUNT],
+            sprite_pipeline_layout: vk::PipelineLayout::null(),
+            sdf_pipelines: [vk::Pipeline::null(); BlendMode::COUNT],
+            sdf_pipeline_layout: vk::PipelineLayout::null(),
+            wireframe_pipeline: vk::Pipeline::null(),
+            wireframe_pipeline_layout: vk::PipelineLayout::null(),
+            debug_view: DebugView::default(),
+            blend_mode: BlendMode::default(),
+            extent: vk::Extent2D::default(),
+            views: Vec::new(),
+            view: 0,
+            buffers,
+        })
+    }
+
+    fn create_buffer(
+        device: &Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        size: vk::DeviceSize,
+    ) -> VkResult<BufferBase> {
+        BufferBase::new(
+            device,
+            memory_properties,
+            size,
+            vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::INDEX_BUFFER, //вершины в начале буффера, индексы после них
+        )
+    }
+
+    //начало нового кадра, extent нужен для перевода пикселей в NDC, режим смешивания сбрасывается в BlendMode::Alpha
+    pub fn begin(&mut self, extent: vk::Extent2D) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.batches.clear();
+        self.extent = extent;
+        self.blend_mode = BlendMode::default();
+        self.views.clear();
+        self.views.push((ViewRect::FULL.pixels(extent), Camera::screen([extent.width as f32, extent.height as f32])));
+        self.view = 0;
+    }
+
+    /*следующие фигуры рисуются в область view.rect через его камеру, вне области они отсекаются scissor`ом,
+    партии разных видов не сливаются, viewport и scissor меняются между ними без смены pipeline*/
+    pub fn begin_view(&mut self, view: &View) {
+        self.views.push((view.rect.pixels(self.extent), view.camera));
+        self.view = self.views.len() - 1;
+    }
+
+    //обратно к пикселям окна целиком, как после begin
+    pub fn end_view(&mut self) {
+        self.view = 0;
+    }
+
+    pub fn set_pipeline(&mut self, pipelines: [vk::Pipeline; BlendMode::COUNT], pipeline_layout: vk::PipelineLayout) {
+        self.pipelines = pipelines;
+        self.pipeline_layout = pipeline_layout;
+    }
+
+    pub fn set_sprite_pipeline(&mut self, pipelines: [vk::Pipeline; BlendMode::COUNT], pipeline_layout: vk::PipelineLayout) {
+        self.sprite_pipelines = pipelines;
+        self.sprite_pipeline_layout = pipeline_layout;
+    }
+
+    pub fn set_sdf_pipeline(&mut self, pipelines: [vk::Pipeline; BlendMode::COUNT], pipeline_layout: vk::PipelineLayout) {
+        self.sdf_pipelines = pipelines;
+        self.sdf_pipeline_layout = pipeline_layout;
+    }
+
+    //null pipeline значит fillModeNonSolid нет и каркас строится на CPU в draw_debug_overlay
+    pub fn set_wireframe_pipeline(&mut self, pipeline: vk::Pipeline, pipeline_layout: vk::PipelineLayout) {
+        self.wireframe_pipeline = pipeline;
+        self.wireframe_pipeline_layout = pipeline_layout;
+    }
+
+    //действует на фигуры записанные после вызова, в отличие от режима смешивания не сбрасывается в begin
+    pub fn set_debug_view(&mut self, debug_view: DebugView) {
+        self.debug_view = debug_view;
+    }
+
+    //режим смешивания для всех следующих фигур, смена режима начинает новую партию
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    //NDC относительно viewport`а текущего вида
+    fn to_ndc(&self, position: [f32; 2]) -> [f32; 2] {
+        let (rect, camera) = self.views[self.view];
+        let size = [rect.extent.width as f32, rect.extent.height as f32];
+        let [x, y] = camera.world_to_view(position, size);
+        [x / size[0] * 2.0 - 1.0, y / size[1] * 2.0 - 1.0]
+    }
+
+    //обратно из NDC вида view в пиксели окна
+    fn to_pixels(&self, [x, y]: [f32; 2], view: usize) -> [f32; 2] {
+        let (rect, _) = self.views[view];
+        [
+            rect.offset.x as f32 + (x + 1.0) * 0.5 * rect.extent.width as f32,
+            rect.offset.y as f32 + (y + 1.0) * 0.5 * rect.extent.height as f32,
+        ]
+    }
+
+    /*базовый путь, все остальные фигуры сводятся к нему: вершины в пикселях и индексы относительно начала среза vertices*/
+    pub fn draw_mesh(&mut self, vertices: &[Vertex], indices: &[u32]) {
+        self.push(self.pipelines[self.blend_mode.index()], self.pipeline_layout, vk::DescriptorSet::null(), vertices, indices);
+    }
+
+    //то же что draw_mesh, но через sprite pipeline с текстурой texture, uv вершин должны быть заполнены
+    pub fn draw_textured_mesh(&mut self, texture: vk::DescriptorSet, vertices: &[Vertex], indices: &[u32]) {
+        self.push(self.sprite_pipelines[self.blend_mode.index()], self.sprite_pipeline_layout, texture, vertices, indices);
+    }
+
+    //то же что draw_textured_mesh, но через SDF pipeline, texture должна быть атласом глифов GlyphMode::Sdf
+    pub fn draw_sdf_mesh(&mut self, texture: vk::DescriptorSet, vertices: &[Vertex], indices: &[u32]) {
+        self.push(self.sdf_pipelines[self.blend_mode.index()], self.sdf_pipeline_layout, texture, vertices, indices);
+    }
+
+    fn push(
+        &mut self,
+        mut pipeline: vk::Pipeline,
+        mut pipeline_layout: vk::PipelineLayout,
+        mut texture: vk::DescriptorSet,
+        vertices: &[Vertex],
+        indices: &[u32],
+    ) {
+        if pipeline == vk::Pipeline::null() {
+            return; //set_pipeline/set_sprite_pipeline/set_sdf_pipeline еще не вызван, привязывать нечего
+        }
+        let (rect, _) = self.views[self.view];
+        if rect.extent.width == 0 || rect.extent.height == 0 {
+            return; //вид нулевого размера ничего не показывает, а перевод в его NDC делил бы на ноль
+        }
+        if self.debug_view.wireframe && self.wireframe_pipeline != vk::Pipeline::null() {
+            //каркас в цветах вершин, текстура не нужна, поэтому спрайты и текст попадают в одну партию с фигурами
+            (pipeline, pipeline_layout, texture) =
+                (self.wireframe_pipeline, self.wireframe_pipeline_layout, vk::DescriptorSet::null());
+        }
+
+        let base_vertex = self.vertices.len() as u32;
+        let first_index = self.indices.len() as u32;
+
+        for vertex in vertices {
+            let position = self.to_ndc(vertex.position);
+            self.vertices.push(Vertex { position, ..*vertex });
+        }
+        self.indices
+            .extend(indices.iter().map(|index| base_vertex + index));
+
+        match self.batches.last_mut() {
+            Some(batch)
+                if batch.pipeline == pipeline
+                    && batch.texture == texture
+                    && batch.view == self.view
+                    && batch.first_index + batch.index_count == first_index =>
+            {
+                batch.index_count += indices.len() as u32 //состояние не поменялось, продолжаем текущую партию
+            }
+            _ => self.batches.push(Batch {
+                pipeline,
+                pipeline_layout,
+                texture,
+                view: self.view,
+                first_index,
+                index_count: indices.len() as u32,
+            }),
+        }
+    }
+
+    pub fn draw_rect(&mut self, position: [f32; 2], size: [f32; 2], color: [f32; 4]) {
+        let [x, y] = position;
+        let [w, h] = size;
+        self.draw_mesh(
+            &[
+                Vertex::new([x, y], color),
+                Vertex::new([x + w, y], color),
+                Vertex::new([x + w, y + h], color),
+                Vertex::new([x, y + h], color),
+            ],
+            &[0, 1, 2, 2, 3, 0],
+        );
+    }
+
+    //спрайт растягивается на прямоугольник position..position+size, tint умножается на цвет текстуры
+    pub fn draw_sprite(&mut self, sprite: &Sprite, position: [f32; 2], size: [f32; 2], tint: [f32; 4]) {
+        let [x, y] = position;
+        let [w, h] = size;
+        let ([u0, v0], [u1, v1]) = (sprite.uv_min, sprite.uv_max);
+        self.draw_textured_mesh(
+            sprite.texture,
+            &[
+                Vertex::textured([x, y], tint, [u0, v0]),
+                Vertex::textured([x + w, y], tint, [u1, v0]),
+                Vertex::textured([x + w, y + h], tint, [u1, v1]),
+                Vertex::textured([x, y + h], tint, [u0, v1]),
+            ],
+            &[0, 1, 2, 2, 3, 0],
+        );
+    }
+
+    /*текст с левым верхним углом блока в position, раскладка по style (перенос, выравнивание, кернинг),
+    все глифы одного шрифта попадают в одну партию, возвращает размер блока текста*/
+    pub fn draw_text(&mut self, font: &FontAtlas, text: &str, position: [f32; 2], style: &TextStyle, color: [f32; 4]) -> [f32; 2] {
+        let layout = font.glyphs.layout(text, style);
+        if layout.quads.is_empty() {
+            return layout.size;
+        }
+        let mut vertices = Vec::with_capacity(layout.quads.len() * 4);
+        let mut indices = Vec::with_capacity(layout.quads.len() * 6);
+        for quad in &layout.quads {
+            let [x, y] = [position[0] + quad.position[0], position[1] + quad.position[1]];
+            let [w, h] = quad.size;
+            let ([u0, v0], [u1, v1]) = (quad.uv_min, quad.uv_max);
+            let first = vertices.len() as u32;
+            vertices.extend_from_slice(&[
+                Vertex::textured([x, y], color, [u0, v0]),
+                Vertex::textured([x + w, y], color, [u1, v0]),
+                Vertex::textured([x + w, y + h], color, [u1, v1]),
+                Vertex::textured([x, y + h], color, [u0, v1]),
+            ]);
+            indices.extend_from_slice(&[first, first + 1, first + 2, first + 2, first + 3, first]);
+        }
+        match font.glyphs.mode {
+            GlyphMode::Bitmap => self.draw_textured_mesh(font.descriptor_set, &vertices, &indices),
+            GlyphMode::Sdf { .. } => self.draw_sdf_mesh(font.descriptor_set, &vertices, &indices),
+        }
+        layout.size
+    }
+
+    pub fn draw_circle(&mut self, center: [f32; 2], radius: f32, color: [f32; 4]) {
+        let segments = arc_segments(radius, 2.0 * PI).max(8);
+
+        let mut vertices = Vec::with_capacity(segments as usize + 1);
+        vertices.push(Vertex::new(center, color)); //центр веера треугольников
+        vertices.extend((0..segments).map(|i| {
+            let angle = i as f32 / segments as f32 * 2.0 * PI;
+            Vertex::new(
+                [center[0] + radius * angle.cos(), center[1] + radius * angle.sin()],
+                color,
+            )
+        }));
+
+        let indices: Vec<u32> = (0..segments)
+            .flat_map(|i| [0, i + 1, (i + 1) % segments + 1])
+            .collect();
+
+        self.draw_mesh(&vertices, &indices);
+    }
+
+    //отрезок как прямоугольник толщиной width вдоль направления from -> to
+    pub fn draw_line(&mut self, from: [f32; 2], to: [f32; 2], width: f32, color: [f32; 4]) {
+        let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            return;
+        }
+        let (nx, ny) = (-dy / length * width * 0.5, dx / length * width * 0.5); //нормаль к отрезку на половину толщины
+
+        self.draw_mesh(
+            &[
+                Vertex::new([from[0] + nx, from[1] + ny], color),
+                Vertex::new([to[0] + nx, to[1] + ny], color),
+                Vertex::new([to[0] - nx, to[1] - ny], color),
+                Vertex::new([from[0] - nx, from[1] - ny], color),
+            ],
+            &[0, 1, 2, 2, 3, 0],
+        );
+    }
+
+    //толстая ломаная с соединениями и концами из style, closed замыкает ее в контур
+    pub fn draw_polyline(&mut self, points: &[[f32; 2]], closed: bool, style: &StrokeStyle, color: [f32; 4]) {
+        let (vertices, indices) = stroke_polyline(points, closed, style, color);
+        self.draw_mesh(&vertices, &indices);
+    }
+
+    //произвольный (в том числе вогнутый) многоугольник
+    pub fn draw_polygon(&mut self, points: &[[f32; 2]], color: [f32; 4]) {
+        self.draw_polygon_with_holes::<&[[f32; 2]]>(points, &[], color);
+    }
+
+    //многоугольник с дырами, контуры дыр задаются отдельно от внешнего контура
+    pub fn draw_polygon_with_holes<H: AsRef<[[f32; 2]]>>(&mut self, outer: &[[f32; 2]], holes: &[H], color: [f32; 4]) {
+        let indices = triangulate(outer, holes);
+        if indices.is_empty() {
+            return;
+        }
+        let vertices: Vec<Vertex> = outer
+            .iter()
+            .chain(holes.iter().flat_map(|hole| hole.as_ref()))
+            .map(|&point| Vertex::new(point, color))
+            .collect(); //порядок вершин совпадает с индексами triangulate: сначала outer, затем дыры
+
+        self.draw_mesh(&vertices, &indices);
+    }
+
+    /*отладочные слои DebugView поверх уже записанных фигур, вызывается перед flush:
+    точки в вершинах, номера треугольников (если есть font) и каркас на CPU, когда нет wireframe pipeline,
+    в этом случае заливка выбрасывается и от фигур остаются только ребра в цвете первой вершины ребра*/
+    pub fn draw_debug_overlay(&mut self, font: Option<&FontAtlas>) {
+        let debug_view = self.debug_view;
+        let cpu_wireframe = self.wireframe_pipeline == vk::Pipeline::null();
+        if !debug_view.needs_overlay(cpu_wireframe) {
+            return;
+        }
+        //вершины в пикселях окна, вид вершины известен только из партии, в которую попали ее индексы
+        let mut positions = vec![[0.0; 2]; self.vertices.len()];
+        for batch in &self.batches {
+            let range = batch.first_index as usize..(batch.first_index + batch.index_count) as usize;
+            for &index in &self.indices[range] {
+                positions[index as usize] = self.to_pixels(self.vertices[index as usize].position, batch.view);
+            }
+        }
+        let colors: Vec<[f32; 4]> = self.vertices.iter().map(|vertex| vertex.color).collect();
+        let indices = self.indices.clone();
+        let (blend_mode, view) = (self.blend_mode, self.view);
+        self.debug_view = DebugView::default(); //сами слои рисуются заливкой
+        self.blend_mode = BlendMode::Alpha;
+        self.view = 0; //слои в пикселях окна поверх всех видов
+
+        if debug_view.wireframe && cpu_wireframe {
+            self.vertices.clear();
+            self.indices.clear();
+            self.batches.clear();
+            for [from, to] in unique_edges(&indices) {
+                let [r, g, b, _] = colors[from as usize];
+                self.draw_line(positions[from as usize], positions[to as usize], 1.0, [r, g, b, 1.0]);
+            }
+        }
+        if debug_view.points {
+            for &[x, y] in &positions {
+                self.draw_rect([x - 1.5, y - 1.5], [3.0, 3.0], [1.0, 0.9, 0.1, 1.0]);
+            }
+        }
+        if let (true, Some(font)) = (debug_view.triangle_indices, font) {
+            let style = TextStyle {
+                size: 10.0,
+                ..Default::default()
+            };
+            for (number, triangle) in indices.chunks_exact(3).take(MAX_INDEX_LABELS).enumerate() {
+                let [x, y] = centroid([0, 1, 2].map(|corner| positions[triangle[corner] as usize]));
+                let label = number.to_string();
+                let [w, h] = font.glyphs.layout(&label, &style).size;
+                self.draw_text(font, &label, [x - w * 0.5, y - h * 0.5], &style, [1.0, 1.0, 1.0, 1.0]);
+            }
+        }
+
+        self.blend_mode = blend_mode;
+        self.view = view;
+        self.debug_view = debug_view;
+    }
+
+    /*загружает накопленные вершины и индексы в буффер кадра frame_index и записывает команды отрисовки,
+    command buffer должен находиться внутри начатого render pass, а fence кадра frame_index уже пройден*/
+    pub fn flush(
+        &mut self,
+        device: &Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        command_buffer: vk::CommandBuffer,
+        frame_index: usize,
+    ) -> VkResult<()> {
+        if self.batches.is_empty() {
+            return Ok(());
+        }
+
+        let vertices_size = size_of_val(self.vertices.as_slice()) as vk::DeviceSize;
+        let indices_size = size_of_val(self.indices.as_slice()) as vk::DeviceSize;
+        let required_size = vertices_size + indices_size;
+
+        if self.buffers[frame_index].size < required_size {
+            //буффер этого кадра GPU уже не использует (fence пройден), поэтому его можно пересоздать большего размера
+            let new_size = required_size.next_power_of_two();
+            unsafe { self.buffers[frame_index].destroy(device) };
+            self.buffers[frame_index] = Self::create_buffer(device, memory_properties, new_size)?;
+        }
+
+        let buffer = &self.buffers[frame_index];
+        buffer.write(0, &self.vertices);
+        buffer.write(vertices_size, &self.indices); //размер Vertex кратен 4, поэтому смещение индексов u32 выровнено
+
+        unsafe {
+            device.cmd_bind_vertex_buffers(command_buffer, 0, &[buffer.buffer], &[0]);
+            device.cmd_bind_index_buffer(
+                command_buffer,
+                buffer.buffer,
+                vertices_size,
+                vk::IndexType::UINT32,
+            );
+        }
+
+        let mut bound_pipeline = vk::Pipeline::null();
+        let mut bound_texture = vk::DescriptorSet::null();
+        let mut bound_view = None;
+        for batch in &self.batches {
+            unsafe {
+                //viewport и scissor динамические, задаются до первого draw call и при смене вида
+                if bound_view != Some(batch.view) {
+                    let (rect, _) = self.views[batch.view]; //партий видов нулевого размера нет, их отбрасывает push
+                    device.cmd_set_viewport(command_buffer, 0, &[viewport(rect)]);
+                    device.cmd_set_scissor(command_buffer, 0, &[rect]);
+                    bound_view = Some(batch.view);
+                }
+                if batch.pipeline != bound_pipeline {
+                    device.cmd_bind_pipeline(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        batch.pipeline,
+                    );
+                    bound_pipeline = batch.pipeline;
+                    bound_texture = vk::DescriptorSet::null(); //после смены pipeline набор дескрипторов нужно привязать заново
+                }
+                if batch.texture != vk::DescriptorSet::null() && batch.texture != bound_texture {
+                    device.cmd_bind_descriptor_sets(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        batch.pipeline_layout,
+                        0,
+                        &[batch.texture],
+                        &[],
+                    );
+                    bound_texture = batch.texture;
+                }
+                device.cmd_draw_indexed(command_buffer, batch.index_count, 1, batch.first_index, 0, 0);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        for buffer in &self.buffers {
+            unsafe { buffer.destroy(device) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    //Batcher без буфферов кадра: все кроме flush работает без устройства
+    fn cpu_batcher(width: u32, height: u32) -> Batcher {
+        let mut batcher = Batcher {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            batches: Vec::new(),
+            pipelines: std::array::from_fn(|i| vk::Pipeline::from_raw(1 + i as u64)),
+            pipeline_layout: vk::PipelineLayout::from_raw(10),
+            sprite_pipelines: std::array::from_fn(|i| vk::Pipeline::from_raw(11 + i as u64)),
+            sprite_pipeline_layout: vk::PipelineLayout::from_raw(20),
+            sdf_pipelines: std::array::from_fn(|i| vk::Pipeline::from_raw(21 + i as u64)),
+            sdf_pipeline_layout: vk::PipelineLayout::from_raw(30),
+            wireframe_pipeline: vk::Pipeline::null(),
+            wireframe_pipeline_layout: vk::PipelineLayout::null(),
+            debug_view: DebugView::default(),
+            blend_mode: BlendMode::default(),
+            extent: vk::Extent2D::default(),
+            views: Vec::new(),
+            view: 0,
+            buffers: Vec::new(),
+        };
+        batcher.begin(vk::Extent2D { width, height });
+        batcher
+    }
+
+    #[test]
+    fn batches_split_on_pipeline_texture_and_view() {
+        let mut batcher = cpu_batcher(200, 100);
+        let texture = vk::DescriptorSet::from_raw(100);
+        batcher.draw_rect([0.0, 0.0], [10.0, 10.0], RED);
+        batcher.draw_line([0.0, 0.0], [10.0, 10.0], 2.0, RED); //тот же pipeline, та же партия
+        batcher.draw_textured_mesh(texture, &[Vertex::new([0.0, 0.0], RED); 3], &[0, 1, 2]);
+        batcher.draw_textured_mesh(texture, &[Vertex::new([0.0, 0.0], RED); 3], &[0, 1, 2]);
+        batcher.draw_textured_mesh(vk::DescriptorSet::from_raw(101), &[Vertex::new([0.0, 0.0], RED); 3], &[0, 1, 2]);
+        batcher.set_blend_mode(BlendMode::Additive);
+        batcher.draw_rect([0.0, 0.0], [10.0, 10.0], RED);
+        batcher.begin_view(&View {
+            rect: ViewRect::columns(2)[1],
+            camera: Camera::screen([100.0, 100.0]),
+        });
+        batcher.draw_rect([0.0, 0.0], [10.0, 10.0], RED);
+
+        let summary: Vec<(vk::Pipeline, vk::DescriptorSet, usize, u32, u32)> = batcher
+            .batches
+            .iter()
+            .map(|batch| (batch.pipeline, batch.texture, batch.view, batch.first_index, batch.index_count))
+            .collect();
+        let additive = batcher.pipelines[BlendMode::Additive.index()];
+        assert_eq!(
+            summary,
+            vec![
+                (batcher.pipelines[BlendMode::Alpha.index()], vk::DescriptorSet::null(), 0, 0, 12),
+                (batcher.sprite_pipelines[BlendMode::Alpha.index()], texture, 0, 12, 6),
+                (batcher.sprite_pipelines[BlendMode::Alpha.index()], vk::DescriptorSet::from_raw(101), 0, 18, 3),
+                (additive, vk::DescriptorSet::null(), 0, 21, 6),
+                (additive, vk::DescriptorSet::null(), 1, 27, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn appended_indices_are_offset_by_previous_vertices() {
+        let mut batcher = cpu_batcher(200, 100);
+        batcher.draw_mesh(&[Vertex::new([0.0, 0.0], RED); 3], &[0, 1, 2]);
+        batcher.draw_rect([0.0, 0.0], [10.0, 10.0], RED);
+        assert_eq!(batcher.vertices.len(), 7);
+        assert_eq!(batcher.indices, vec![0, 1, 2, 3, 4, 5, 5, 6, 3]);
+    }
+
+    #[test]
+    fn positions_map_to_ndc_of_the_view() {
+        let mut batcher = cpu_batcher(200, 100);
+        assert_eq!(batcher.to_ndc([0.0, 0.0]), [-1.0, -1.0]);
+        assert_eq!(batcher.to_ndc([100.0, 50.0]), [0.0, 0.0]);
+        assert_eq!(batcher.to_ndc([200.0, 100.0]), [1.0, 1.0]);
+
+        //правая половина окна 100x100, камера с центром мира в начале координат и двукратным увеличением
+        batcher.begin_view(&View {
+            rect: ViewRect::columns(2)[1],
+            camera: Camera {
+                center: [0.0, 0.0],
+                zoom: 2.0,
+                rotation: 0.0,
+            },
+        });
+        assert_eq!(batcher.to_ndc([0.0, 0.0]), [0.0, 0.0]);
+        assert_eq!(batcher.to_ndc([25.0, -25.0]), [1.0, -1.0]);
+        batcher.end_view();
+        assert_eq!(batcher.to_ndc([0.0, 0.0]), [-1.0, -1.0]);
+    }
+
+    #[test]
+    fn zero_sized_view_draws_nothing() {
+        let mut batcher = cpu_batcher(200, 100);
+        batcher.begin_view(&View {
+            rect: ViewRect { width: 0.0, ..ViewRect::FULL },
+            camera: Camera::screen([1.0, 1.0]),
+        });
+        batcher.draw_rect([0.0, 0.0], [10.0, 10.0], RED);
+        assert!(batcher.vertices.is_empty() && batcher.batches.is_empty());
+
+        let mut minimized = cpu_batcher(0, 0);
+        minimized.draw_circle([0.0, 0.0], 5.0, RED);
+        assert!(minimized.vertices.is_empty() && minimized.batches.is_empty());
+    }
+
+    #[test]
+    fn draws_without_pipeline_are_dropped() {
+        let mut batcher = cpu_batcher(200, 100);
+        batcher.sprite_pipelines = [vk::Pipeline::null(); BlendMode::COUNT];
+        batcher.draw_textured_mesh(vk::DescriptorSet::from_raw(40), &[Vertex::new([0.0, 0.0], RED)], &[0]);
+        assert!(batcher.vertices.is_empty() && batcher.batches.is_empty());
+        batcher.draw_rect([0.0, 0.0], [10.0, 10.0], RED);
+        assert_eq!(batcher.batches.len(), 1);
+    }
+}
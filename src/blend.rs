@@ -0,0 +1,64 @@
+use ash::vk;
+use serde::{Deserialize, Serialize};
+
+/*режим смешивания цвета фрагмента с цветом уже нарисованного пикселя,
+состояние смешивания запекается в pipeline, поэтому на каждый режим создается отдельный вариант pipeline*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    Opaque, //фрагмент перезаписывает пиксель, альфа игнорируется
+    #[default]
+    Alpha, //обычная прозрачность, цвет фрагмента не умножен на альфу
+    PremultipliedAlpha, //цвет фрагмента уже умножен на альфу
+    Additive, //цвет фрагмента с учетом альфы прибавляется к пикселю, для свечения и частиц
+    Multiply, //пиксель умножается на цвет фрагмента (затемнение), альфа игнорируется
+    Screen, //1 - (1 - src) * (1 - dst), осветление, альфа игнорируется
+}
+
+impl BlendMode {
+    pub const COUNT: usize = 6;
+    pub const ALL: [BlendMode; Self::COUNT] = [
+        BlendMode::Opaque,
+        BlendMode::Alpha,
+        BlendMode::PremultipliedAlpha,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ];
+
+    //индекс варианта pipeline в массивах размера COUNT
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /*result = src * src_factor + dst * dst_factor отдельно для цвета и для альфы,
+    альфа результата считается как для PremultipliedAlpha, чтобы она оставалась покрытием пикселя в offscreen целях*/
+    pub fn attachment_state(self) -> vk::PipelineColorBlendAttachmentState {
+        use vk::BlendFactor as F;
+        let (src_color, dst_color) = match self {
+            BlendMode::Opaque => {
+                return vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA);
+            }
+            BlendMode::Alpha => (F::SRC_ALPHA, F::ONE_MINUS_SRC_ALPHA),
+            BlendMode::PremultipliedAlpha => (F::ONE, F::ONE_MINUS_SRC_ALPHA),
+            BlendMode::Additive => (F::SRC_ALPHA, F::ONE),
+            BlendMode::Multiply => (F::DST_COLOR, F::ZERO),
+            BlendMode::Screen => (F::ONE, F::ONE_MINUS_SRC_COLOR),
+        };
+        let (src_alpha, dst_alpha) = match self {
+            BlendMode::Additive | BlendMode::Multiply | BlendMode::Screen => (F::ZERO, F::ONE), //альфа пикселя не меняется
+            _ => (F::ONE, F::ONE_MINUS_SRC_ALPHA),
+        };
+        vk::PipelineColorBlendAttachmentState::default()
+            .blend_enable(true)
+            .src_color_blend_factor(src_color)
+            .dst_color_blend_factor(dst_color)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(src_alpha)
+            .dst_alpha_blend_factor(dst_alpha)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(vk::ColorComponentFlags::RGBA)
+    }
+}
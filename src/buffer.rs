@@ -0,0 +1,132 @@
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::ffi::c_void;
+
+/*ищем индекс типа памяти у физического устройства, который подходит буфферу (memory_type_bits из MemoryRequirements)
+и имеет все нужные флаги, например HOST_VISIBLE | HOST_COHERENT чтобы CPU мог писать в память напрямую*/
+pub fn find_memorytype_index(
+    memory_req: &vk::MemoryRequirements,
+    memory_prop: &vk::PhysicalDeviceMemoryProperties,
+    flags: vk::MemoryPropertyFlags,
+) -> Option<u32> {
+    memory_prop.memory_types[..memory_prop.memory_type_count as _]
+        .iter()
+        .enumerate()
+        .find(|(index, memory_type)| {
+            (1 << index) & memory_req.memory_type_bits != 0
+                && memory_type.property_flags & flags == flags
+        })
+        .map(|(index, _memory_type)| index as _)
+}
+
+/*буффер в памяти видимой для CPU, постоянно отображенный (mapped) в адресное пространство процесса,
+используется для данных которые меняются каждый кадр: вершины, индексы, uniform`ы*/
+pub struct BufferBase {
+    pub buffer: vk::Buffer,
+    pub memory: vk::DeviceMemory,
+    pub size: vk::DeviceSize,
+    mapped: *mut c_void,
+} //vulkan host visible buffer resources
+
+impl BufferBase {
+    pub fn new(
+        device: &Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) -> VkResult<Self> {
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE); //буффер используется одним семейством очередей
+
+        let buffer = unsafe { device.create_buffer(&buffer_info, None)? };
+        let memory_req = unsafe { device.get_buffer_memory_requirements(buffer) }; //размер и выравнивание которые хочет драйвер
+
+        let memory_index = find_memorytype_index(
+            &memory_req,
+            memory_properties,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT, //COHERENT значит не нужно вручную делать flush памяти после записи
+        )
+        .ok_or(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)?;
+
+        let allocate_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(memory_req.size)
+            .memory_type_index(memory_index);
+
+        let memory = unsafe { device.allocate_memory(&allocate_info, None)? };
+
+        unsafe { device.bind_buffer_memory(buffer, memory, 0)? };
+
+        let mapped = unsafe { device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())? }; //память остается отображенной все время жизни буффера
+
+        Ok(Self {
+            buffer,
+            memory,
+            size,
+            mapped,
+        })
+    }
+
+    /*копируем срез в буффер со смещением offset в байтах, вызывающий должен убедиться что GPU сейчас не читает этот участок памяти*/
+    pub fn write<T: Copy>(&self, offset: vk::DeviceSize, data: &[T]) {
+        let bytes = std::mem::size_of_val(data) as vk::DeviceSize;
+        assert!(offset + bytes <= self.size, "buffer write out of bounds");
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr() as *const u8,
+                (self.mapped as *mut u8).add(offset as usize),
+                bytes as usize,
+            );
+        }
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            device.unmap_memory(self.memory);
+            device.destroy_buffer(self.buffer, None);
+            device.free_memory(self.memory, None);
+        }
+    }
+}
+
+/*записывает команды f в одноразовый command buffer, отправляет в очередь и ждет завершения,
+используется для загрузки ресурсов на GPU (копирование из staging буффера, смена layout изображений)*/
+pub fn submit_once<F: FnOnce(vk::CommandBuffer)>(
+    device: &Device,
+    command_pool: vk::CommandPool,
+    queue: vk::Queue,
+    f: F,
+) -> VkResult<()> {
+    let allocate_info = vk::CommandBufferAllocateInfo::default()
+        .command_pool(command_pool)
+        .level(vk::CommandBufferLevel::PRIMARY)
+        .command_buffer_count(1);
+    let command_buffer = unsafe { device.allocate_command_buffers(&allocate_info)?[0] };
+
+    let result = unsafe {
+        device
+            .begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo::default().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )
+            .and_then(|_| {
+                f(command_buffer);
+                device.end_command_buffer(command_buffer)
+            })
+            .and_then(|_| device.create_fence(&vk::FenceCreateInfo::default(), None))
+            .and_then(|fence| {
+                let command_buffers = [command_buffer];
+                let submit_info = vk::SubmitInfo::default().command_buffers(&command_buffers);
+                let result = device
+                    .queue_submit(queue, &[submit_info], fence)
+                    .and_then(|_| device.wait_for_fences(&[fence], true, u64::MAX)); //ждем пока GPU закончит, загрузка синхронная
+                device.destroy_fence(fence, None);
+                result
+            })
+    };
+
+    unsafe { device.free_command_buffers(command_pool, &[command_buffer]) };
+    result
+}
@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+pub const MAX_INDEX_LABELS: usize = 512; //номера треугольников дороже самих треугольников, больше не подписываем
+
+/*отладочное отображение геометрии Batcher`а для проверки тесселяции (triangulate, stroke):
+каркас вместо заливки, точки в вершинах и номера треугольников в их центрах*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DebugView {
+    pub wireframe: bool, //PolygonMode::Line если есть fillModeNonSolid, иначе ребра строятся на CPU
+    pub points: bool,
+    pub triangle_indices: bool, //нужен шрифт, без него не рисуется
+}
+
+impl DebugView {
+    //нужен ли проход по уже записанным треугольникам после отрисовки кадра
+    pub fn needs_overlay(&self, cpu_wireframe: bool) -> bool {
+        (self.wireframe && cpu_wireframe) || self.points || self.triangle_indices
+    }
+
+    pub fn describe(&self) -> String {
+        let enabled: Vec<&str> = [
+            (self.wireframe, "wireframe"),
+            (self.points, "points"),
+            (self.triangle_indices, "triangle indices"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+        if enabled.is_empty() { "off".to_string() } else { enabled.join(", ") }
+    }
+}
+
+/*ребра треугольного списка без повторов: общее ребро соседних треугольников рисуется один раз,
+направление не важно, ребро хранится как (меньший индекс, больший)*/
+pub fn unique_edges(indices: &[u32]) -> Vec<[u32; 2]> {
+    let mut seen = HashSet::new();
+    let mut edges = Vec::new();
+    for triangle in indices.chunks_exact(3) {
+        for (a, b) in [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])] {
+            let edge = [a.min(b), a.max(b)];
+            if edge[0] != edge[1] && seen.insert(edge) {
+                edges.push(edge);
+            }
+        }
+    }
+    edges
+}
+
+//центр масс треугольника, туда ставится его номер
+pub fn centroid([a, b, c]: [[f32; 2]; 3]) -> [f32; 2] {
+    [(a[0] + b[0] + c[0]) / 3.0, (a[1] + b[1] + c[1]) / 3.0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_edges_are_drawn_once() {
+        //квадрат из двух треугольников: 5 ребер, диагональ общая
+        let edges = unique_edges(&[0, 1, 2, 2, 3, 0]);
+        assert_eq!(edges, vec![[0, 1], [1, 2], [0, 2], [2, 3], [0, 3]]);
+        //вырожденный треугольник не дает ребер нулевой длины, хвост без полного треугольника игнорируется
+        assert_eq!(unique_edges(&[4, 4, 5, 7]), vec![[4, 5]]);
+        assert_eq!(centroid([[0.0, 0.0], [3.0, 0.0], [0.0, 3.0]]), [1.0, 1.0]);
+    }
+}
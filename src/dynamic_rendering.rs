@@ -0,0 +1,31 @@
+use ash::Device;
+use ash::vk;
+
+/*динамический рендер: проход начинается cmd_begin_rendering со списком ImageView прямо в command buffer,
+без vk::RenderPass и vk::Framebuffer, поэтому при изменении размера окна пересоздавать нечего кроме самих изображений.
+В Vulkan 1.3 это часть ядра, на 1.2 доступно через расширение VK_KHR_dynamic_rendering со своими указателями функций*/
+#[derive(Clone)]
+pub enum DynamicRendering {
+    Core, //функции из ash::Device
+    Khr(ash::khr::dynamic_rendering::Device),
+}
+
+impl DynamicRendering {
+    pub unsafe fn begin(&self, device: &Device, command_buffer: vk::CommandBuffer, rendering_info: &vk::RenderingInfo) {
+        unsafe {
+            match self {
+                DynamicRendering::Core => device.cmd_begin_rendering(command_buffer, rendering_info),
+                DynamicRendering::Khr(loader) => loader.cmd_begin_rendering(command_buffer, rendering_info),
+            }
+        }
+    }
+
+    pub unsafe fn end(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            match self {
+                DynamicRendering::Core => device.cmd_end_rendering(command_buffer),
+                DynamicRendering::Khr(loader) => loader.cmd_end_rendering(command_buffer),
+            }
+        }
+    }
+}
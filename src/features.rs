@@ -0,0 +1,196 @@
+use ash::vk;
+use ash::{Instance, khr};
+use std::ffi::{CStr, c_char};
+
+/*откуда взялась возможность: из ядра версии устройства или из расширения на более старой версии,
+от этого зависит какая структура включает ее при создании device и чьи указатели функций вызывать*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeatureSource {
+    Core,
+    Extension,
+}
+
+/*возможности которые включены при создании логического устройства,
+подсистемы проверяют их здесь вместо того чтобы заново опрашивать физическое устройство*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceFeatures {
+    pub api_version: u32, //меньшая из версий instance и устройства, функции выше нее вызывать нельзя
+    pub timeline_semaphore: Option<FeatureSource>, //ядро 1.2 или VK_KHR_timeline_semaphore
+    pub synchronization2: Option<FeatureSource>, //ядро 1.3 или VK_KHR_synchronization2
+    pub dynamic_rendering: Option<FeatureSource>, //ядро 1.3 или VK_KHR_dynamic_rendering
+    pub pipeline_statistics_query: bool, //ядро 1.0, запросы vk::QueryType::PIPELINE_STATISTICS
+    pub occlusion_query_precise: bool, //ядро 1.0, точное число прошедших сэмплов вместо "ноль или не ноль"
+    pub fill_mode_non_solid: bool, //ядро 1.0, PolygonMode::LINE и POINT для каркасного режима
+}
+
+impl DeviceFeatures {
+    /*опрос цепочкой p_next: vkGetPhysicalDeviceFeatures2 заполняет все структуры цепочки разом,
+    Vulkan12Features/Vulkan13Features можно передавать только если версия устройства их знает,
+    на младших версиях те же возможности описывают структуры расширений*/
+    pub fn query(instance: &Instance, physical_device: vk::PhysicalDevice, instance_version: u32) -> Self {
+        let api_version = unsafe { instance.get_physical_device_properties(physical_device) }
+            .api_version
+            .min(instance_version);
+        //возможности ядра 1.0 есть на любой версии
+        let core_features = unsafe { instance.get_physical_device_features(physical_device) };
+        let pipeline_statistics_query = core_features.pipeline_statistics_query == vk::TRUE;
+        let occlusion_query_precise = core_features.occlusion_query_precise == vk::TRUE;
+        let fill_mode_non_solid = core_features.fill_mode_non_solid == vk::TRUE;
+        if api_version < vk::API_VERSION_1_1 {
+            //vkGetPhysicalDeviceFeatures2 появилась в 1.1, на 1.0 без расширения instance опросить нечем
+            return Self {
+                api_version,
+                timeline_semaphore: None,
+                synchronization2: None,
+                dynamic_rendering: None,
+                pipeline_statistics_query,
+                occlusion_query_precise,
+                fill_mode_non_solid,
+            };
+        }
+
+        let extensions = unsafe { instance.enumerate_device_extension_properties(physical_device).unwrap_or_default() };
+        let has_extension =
+            |name: &CStr| extensions.iter().any(|extension| extension.extension_name_as_c_str() == Ok(name));
+
+        let mut features12 = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features13 = vk::PhysicalDeviceVulkan13Features::default();
+        let mut timeline_khr = vk::PhysicalDeviceTimelineSemaphoreFeatures::default();
+        let mut synchronization2_khr = vk::PhysicalDeviceSynchronization2Features::default();
+        let mut dynamic_rendering_khr = vk::PhysicalDeviceDynamicRenderingFeatures::default();
+        let mut features = vk::PhysicalDeviceFeatures2::default();
+        if api_version >= vk::API_VERSION_1_2 {
+            features = features.push_next(&mut features12);
+        } else if has_extension(khr::timeline_semaphore::NAME) {
+            features = features.push_next(&mut timeline_khr);
+        }
+        if api_version >= vk::API_VERSION_1_3 {
+            features = features.push_next(&mut features13);
+        } else {
+            if has_extension(khr::synchronization2::NAME) {
+                features = features.push_next(&mut synchronization2_khr);
+            }
+            //зависимости расширения (create_renderpass2, depth_stencil_resolve) в ядре только с 1.2
+            if api_version >= vk::API_VERSION_1_2 && has_extension(khr::dynamic_rendering::NAME) {
+                features = features.push_next(&mut dynamic_rendering_khr);
+            }
+        }
+        unsafe { instance.get_physical_device_features2(physical_device, &mut features) };
+
+        Self {
+            api_version,
+            timeline_semaphore: feature_source(features12.timeline_semaphore, timeline_khr.timeline_semaphore),
+            synchronization2: feature_source(features13.synchronization2, synchronization2_khr.synchronization2),
+            dynamic_rendering: feature_source(features13.dynamic_rendering, dynamic_rendering_khr.dynamic_rendering),
+            pipeline_statistics_query,
+            occlusion_query_precise,
+            fill_mode_non_solid,
+        }
+    }
+
+    //расширения устройства для возможностей из FeatureSource::Extension
+    pub fn extension_names(&self) -> Vec<*const c_char> {
+        [
+            (self.timeline_semaphore, khr::timeline_semaphore::NAME),
+            (self.synchronization2, khr::synchronization2::NAME),
+            (self.dynamic_rendering, khr::dynamic_rendering::NAME),
+        ]
+        .into_iter()
+        .filter(|&(source, _)| source == Some(FeatureSource::Extension))
+        .map(|(_, name)| name.as_ptr())
+        .collect()
+    }
+
+    pub fn version_string(&self) -> String {
+        format!(
+            "{}.{}.{}",
+            vk::api_version_major(self.api_version),
+            vk::api_version_minor(self.api_version),
+            vk::api_version_patch(self.api_version)
+        )
+    }
+}
+
+//структура ядра и структура расширения не заполняются одновременно, поэтому достаточно проверить обе
+fn feature_source(core: vk::Bool32, extension: vk::Bool32) -> Option<FeatureSource> {
+    if core == vk::TRUE {
+        Some(FeatureSource::Core)
+    } else if extension == vk::TRUE {
+        Some(FeatureSource::Extension)
+    } else {
+        None
+    }
+}
+
+/*структуры включения возможностей для vk::DeviceCreateInfo, живут пока создается device,
+в цепочку попадают только структуры нужные для features*/
+#[derive(Default)]
+pub struct FeatureChain {
+    core: vk::PhysicalDeviceFeatures,
+    features12: vk::PhysicalDeviceVulkan12Features<'static>,
+    features13: vk::PhysicalDeviceVulkan13Features<'static>,
+    timeline_khr: vk::PhysicalDeviceTimelineSemaphoreFeatures<'static>,
+    synchronization2_khr: vk::PhysicalDeviceSynchronization2Features<'static>,
+    dynamic_rendering_khr: vk::PhysicalDeviceDynamicRenderingFeatures<'static>,
+}
+
+impl FeatureChain {
+    pub fn push<'a>(&'a mut self, features: &DeviceFeatures, mut create_info: vk::DeviceCreateInfo<'a>) -> vk::DeviceCreateInfo<'a> {
+        let core = |source: Option<FeatureSource>| (source == Some(FeatureSource::Core)).into();
+        let extension = |source: Option<FeatureSource>| source == Some(FeatureSource::Extension);
+
+        self.core.pipeline_statistics_query = features.pipeline_statistics_query.into();
+        self.core.occlusion_query_precise = features.occlusion_query_precise.into();
+        self.core.fill_mode_non_solid = features.fill_mode_non_solid.into();
+        create_info = create_info.enabled_features(&self.core);
+
+        if features.api_version >= vk::API_VERSION_1_2 {
+            self.features12.timeline_semaphore = core(features.timeline_semaphore);
+            create_info = create_info.push_next(&mut self.features12);
+        } else if extension(features.timeline_semaphore) {
+            self.timeline_khr.timeline_semaphore = vk::TRUE;
+            create_info = create_info.push_next(&mut self.timeline_khr);
+        }
+        if features.api_version >= vk::API_VERSION_1_3 {
+            self.features13.synchronization2 = core(features.synchronization2);
+            self.features13.dynamic_rendering = core(features.dynamic_rendering);
+            create_info = create_info.push_next(&mut self.features13);
+        }
+        if extension(features.synchronization2) {
+            self.synchronization2_khr.synchronization2 = vk::TRUE;
+            create_info = create_info.push_next(&mut self.synchronization2_khr);
+        }
+        if extension(features.dynamic_rendering) {
+            self.dynamic_rendering_khr.dynamic_rendering = vk::TRUE;
+            create_info = create_info.push_next(&mut self.dynamic_rendering_khr);
+        }
+        create_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_feature_wins_over_extension() {
+        assert_eq!(feature_source(vk::TRUE, vk::TRUE), Some(FeatureSource::Core));
+        assert_eq!(feature_source(vk::FALSE, vk::TRUE), Some(FeatureSource::Extension));
+        assert_eq!(feature_source(vk::FALSE, vk::FALSE), None);
+    }
+
+    #[test]
+    fn only_extension_features_need_extension_names() {
+        let features = DeviceFeatures {
+            api_version: vk::API_VERSION_1_2,
+            timeline_semaphore: Some(FeatureSource::Core),
+            synchronization2: Some(FeatureSource::Extension),
+            dynamic_rendering: None,
+            pipeline_statistics_query: true,
+            occlusion_query_precise: false,
+            fill_mode_non_solid: true,
+        };
+        assert_eq!(features.extension_names(), vec![khr::synchronization2::NAME.as_ptr()]);
+        assert_eq!(features.version_string(), "1.2.0");
+    }
+}
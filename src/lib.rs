@@ -0,0 +1,1497 @@
+//! Vulkan рендер 2D на ash и winit: окно и устройство (AppBase), swapchain (FramesBase), проходы (RenderBase),
+//! pipeline`ы Batcher`а (PipelineSet) и кадры (CommandBase), дополнительные окна (WindowBase),
+//! фигуры, спрайты, текст, частицы и эффекты в модулях. Демо лежат в examples/.
+
+/*у всех unsafe fn destroy/begin/end один и тот же контракт: GPU закончил работу с ресурсом
+(fence кадра пройден или device_wait_idle), ресурс больше не используется и уничтожается один раз*/
+#![allow(clippy::missing_safety_doc)]
+
+pub mod atlas;
+pub mod batch;
+pub mod blend;
+pub mod buffer;
+pub mod debug_view;
+pub mod dynamic_rendering;
+pub mod features;
+pub mod particles;
+pub mod pipeline_cache;
+pub mod pipeline_desc;
+pub mod post;
+pub mod queries;
+pub mod reflect;
+pub mod stroke;
+pub mod target;
+pub mod text;
+pub mod texture;
+pub mod timing;
+pub mod triangulate;
+pub mod vertex;
+pub mod view;
+
+//версии ash и winit в API библиотеки, пользователю не нужно подбирать совместимые самому
+pub use ash;
+pub use winit;
+
+use ash::Device;
+use ash::ext::debug_utils;
+use ash::khr::surface;
+use ash::prelude::VkResult;
+use ash::{Entry, Instance, vk};
+use batch::Batcher;
+use blend::BlendMode;
+use buffer::find_memorytype_index;
+use dynamic_rendering::DynamicRendering;
+use features::{DeviceFeatures, FeatureChain, FeatureSource};
+use particles::ParticlePipeline;
+use pipeline_desc::{PipelineConfig, PipelineDesc, PipelineError, PipelineRegistry, RegisteredPipeline};
+use post::{PostChain, PostEffects, PostProcessBase};
+use queries::GpuStatistics;
+use target::RenderTarget;
+use timing::{FrameTimings, GpuTimer};
+use std::cell::RefCell;
+use std::ffi::c_char;
+use std::time::Instant;
+use texture::image_barrier;
+use vk::Queue;
+use winit::platform::run_on_demand::EventLoopExtRunOnDemand;
+use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use winit::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+    window::{Window, WindowBuilder, WindowId},
+};
+
+pub const MAX_FRAMES_IN_FLIGHT: usize = 2; //сколько кадров CPU может записывать пока GPU еще рисует предыдущие
+
+pub struct FramesBase {
+    pub loader: ash::khr::swapchain::Device,
+    pub surface_format: vk::SurfaceFormatKHR,
+    pub extent: vk::Extent2D,
+    pub swapchain: vk::SwapchainKHR, /*swapchain это структура которая используется технология организации/буфферизации отображения кадров и способ общения с оконным менеджером вашей системы */
+    pub images: Vec<vk::Image>,
+    pub image_views: Vec<vk::ImageView>,
+    pub format: vk::Format,
+    pub present_semaphores: Vec<vk::Semaphore>, //по семафору на каждый кадр swapchain, сигналится когда рендер кадра закончен и его можно показывать
+} //vulkan swapchain resources 
+
+impl FramesBase {
+    pub fn new(
+        instance: &ash::Instance,
+        device: &ash::Device,
+        surface: vk::SurfaceKHR,
+        surface_loader: &ash::khr::surface::Instance,
+        physical_device: vk::PhysicalDevice,
+        queue_family_index: u32,
+        window: &Window,
+    ) -> VkResult<Self> {
+        let surface_capabilities = unsafe {
+            surface_loader
+                .get_physical_device_surface_capabilities(physical_device, surface)
+                .unwrap()
+        };
+
+        log::debug!(
+            "Surface capabilities: min_images = {}, max_images = {}, extent = {:?}",
+            surface_capabilities.min_image_count, //минимальное количество кадров в очереди для данного GPU
+            surface_capabilities.max_image_count, //максимальное количество указано 0 то есть без ограничений на данный GPU
+            surface_capabilities.current_extent //размер поверхности рендера, привязанного к размеру окна
+        );
+
+        let surface_formats = unsafe {
+            surface_loader
+                .get_physical_device_surface_formats(physical_device, surface)
+                .unwrap()
+        }; //форматы отображения изо в различный RGB форматах,
+        //с разной цветокоррекцией, гаммой, прозрачностью, размером канала на один цвет или альфа канал
+        let surface_format = surface_formats[0];
+        let format = surface_format.format;
+        log::debug!("Available surface formats: {:?}", surface_formats);
+
+        let present_modes = unsafe {
+            //получаем список режимов представления изображения, IMMEDIATE, MAILBOX, FIFO, FIFO_RELAXED
+            surface_loader.get_physical_device_surface_present_modes(physical_device, surface)?
+        };
+        /*
+        1.IMMEDIATE отображает изображение сразу без ожидания синхронизации с частотой обновления экрана
+
+        2. MAILBOX тройная буфферизация, одно изо отображается, второе рендериться,
+        третье рендерится, если второй и третий отрендерились у возмет самый свежий рендер и отобразит его, то есть пропустить второй и покажет третий
+
+        3. FIFO двойная или тройная буфферизация с ожиданием обноевления частосты экрана v-sync (вертикальная синхронизация),
+        создается очередь на отображение привязанная к частоте обновления экрана, второй и третий кадр даже если они прошли этап рендера в любом случае
+        отобразяться в строгой последовательности второй потом третий
+        */
+
+        log::debug!("Present modes: {:?}", present_modes);
+        //MAILBOX есть не везде, FIFO спецификация гарантирует на любой поверхности
+        let present_mode = if present_modes.contains(&vk::PresentModeKHR::MAILBOX) {
+            vk::PresentModeKHR::MAILBOX
+        } else {
+            vk::PresentModeKHR::FIFO
+        };
+        log::debug!("Min image extent: {:?}", surface_capabilities.min_image_extent);
+
+        let extent = if surface_capabilities.current_extent.width != u32::MAX {
+            //проверка на неопределенное состояние размеров окна,
+            //по спецификации Vulkan неопределенное состояние поверхности или окна всегда будет u32::MAX
+            //если состояние определенное верни реальные размеры окна в переменную extent
+            surface_capabilities.current_extent
+        } else {
+            //в противном случае если размер u32::MAX состояние не определенно создай новую поверхность
+            let size = window.inner_size(); //достает данные для размеров поверхности surface из window
+            vk::Extent2D {
+                //создаем и возврашаем новый экземляр поверхности
+                width: size.width.clamp(
+                    //если меньше диапазона верни минимум или если больше верни максимум, если в диапазоне верни значение по факту
+                    surface_capabilities.min_image_extent.width,
+                    surface_capabilities.max_image_extent.width,
+                ),
+                height: size.height.clamp(
+                    //тоже самое для высоты
+                    surface_capabilities.min_image_extent.height,
+                    surface_capabilities.max_image_extent.height,
+                ),
+            }
+        };
+
+        let image_count = if surface_capabilities.max_image_count > 0 {
+            //защита от дурака, если указал максимум меньше минимума,
+            // на разные GPU дефолтные значения могут меняться,
+            //max_image_count = 0 это значит что ограничений по максимум нет, если бы указали 1 или больше, то все должно совпадать, и берем всегда минимум
+            surface_capabilities
+                .min_image_count
+                .min(surface_capabilities.max_image_count)
+        } else {
+            surface_capabilities.min_image_count + 1 //даем запас по буфферу + 1, для MAILBOX например
+        };
+
+        log::debug!("Swapchain image count: {}", image_count);
+
+        let swapchain_loader = ash::khr::swapchain::Device::new(instance, device);
+        let queue_family_indices = &[queue_family_index];
+
+        let create_info = vk::SwapchainCreateInfoKHR::default()
+            .surface(surface)
+            .min_image_count(image_count)
+            .image_format(surface_formats[0].format)
+            .image_color_space(surface_formats[0].color_space)
+            .image_extent(extent)
+            .image_array_layers(1)
+            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT) //изображения используются для цветного отображения
+            .pre_transform(surface_capabilities.current_transform) //преобразование изображений, по дефолту без поворотов
+            .image_sharing_mode(vk::SharingMode::EXCLUSIVE) //эксклюзивный доступ к семейству очередей, экслюзивный значит для одного семейства
+            .queue_family_indices(queue_family_indices) //передаем массив индексов семейства очередей, массив для задела в случае если мы будет передавать больше семейств COMPUTE, GRAPHICS ETC
+            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE) //непрозрачный режим для окна
+            .present_mode(present_mode)
+            .clipped(true); //обрезка невидимых пикселей 
+
+        let swapchain = unsafe {
+            swapchain_loader
+                .create_swapchain(&create_info, None)
+                .unwrap()
+        };
+
+        let images = unsafe { swapchain_loader.get_swapchain_images(swapchain).unwrap() }; //получаем сами кадры, вектор из 4 кадров
+
+        log::debug!(
+            "Count of swapchain images: {:?}",
+            images.len() //такое же количество как и в image_count четыре кадра.
+        );
+
+        let image_views: Vec<vk::ImageView> = images //ImageView это инструкция как работать с памятью кадра
+            .iter()
+            .map(|&image| {
+                let create_info = vk::ImageViewCreateInfo::default()
+                    .view_type(vk::ImageViewType::TYPE_2D)
+                    .format(surface_formats[0].format)
+                    .components(vk::ComponentMapping {
+                        r: vk::ComponentSwizzle::IDENTITY,
+                        g: vk::ComponentSwizzle::IDENTITY,
+                        b: vk::ComponentSwizzle::IDENTITY,
+                        a: vk::ComponentSwizzle::IDENTITY,
+                    })
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    })
+                    .image(image);
+                unsafe { device.create_image_view(&create_info, None).unwrap() }
+            })
+            .collect();
+
+        let present_semaphores = images
+            .iter()
+            .map(|_| unsafe {
+                device
+                    .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)
+                    .unwrap()
+            })
+            .collect();
+
+        Ok(Self {
+            loader: swapchain_loader,
+            surface_format,
+            extent,
+            swapchain,
+            images,
+            image_views,
+            format,
+            present_semaphores,
+        })
+    }
+
+    /*swapchain пересоздается целиком при изменении размеров окна, перед этим GPU должен закончить работу с кадрами*/
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            for &semaphore in &self.present_semaphores {
+                device.destroy_semaphore(semaphore, None);
+            }
+            for &image_view in &self.image_views {
+                device.destroy_image_view(image_view, None);
+            }
+            self.loader.destroy_swapchain(self.swapchain, None); //сами images принадлежат swapchain и удаляются вместе с ним
+        }
+    }
+}
+
+/*что render pass делает с содержимым изображения кадра: CLEAR заливает clear_color, LOAD сохраняет результат
+предыдущего прохода (для оверлеев поверх сцены), DONT_CARE когда все пиксели все равно будут перезаписаны,
+store_op STORE оставляет результат для показа или следующего прохода*/
+#[derive(Clone, Copy, Debug)]
+pub struct RenderPassDesc {
+    pub load_op: vk::AttachmentLoadOp,
+    pub store_op: vk::AttachmentStoreOp,
+    pub clear_color: [f32; 4], //читается при каждом begin render pass, поэтому его можно менять без пересоздания RenderBase
+    pub post_process: Option<PostEffects>, //Some добавляет subpass эффектов, параметры тоже можно менять на лету
+}
+
+impl Default for RenderPassDesc {
+    fn default() -> Self {
+        Self {
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::STORE,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            post_process: None,
+        }
+    }
+}
+
+impl RenderPassDesc {
+    //проход поверх результата предыдущего прохода в том же кадре
+    pub fn overlay() -> Self {
+        Self {
+            load_op: vk::AttachmentLoadOp::LOAD,
+            ..Default::default()
+        }
+    }
+}
+
+/*проход, под который создаются pipeline`ы, берется из RenderBase::pass_info или RenderTarget::pass_info*/
+#[derive(Clone, Copy, Debug)]
+pub struct PassInfo {
+    pub render_pass: vk::RenderPass, //null при динамическом рендере
+    pub format: vk::Format,
+    pub samples: vk::SampleCountFlags,
+    pub desc: RenderPassDesc,
+}
+
+impl PassInfo {
+    pub fn dynamic_rendering(&self) -> bool {
+        self.render_pass == vk::RenderPass::null()
+    }
+
+    /*pipeline`ы одного прохода годятся для другого, если совпадают способ рендера, формат, сэмплы
+    и структура прохода: load/store вложения и наличие subpass`а эффектов.
+    clear_color и параметры эффектов меняются на лету и не важны*/
+    pub fn compatible(&self, other: &PassInfo) -> bool {
+        self.dynamic_rendering() == other.dynamic_rendering()
+            && self.format == other.format
+            && self.samples == other.samples
+            && self.desc.load_op == other.desc.load_op
+            && self.desc.store_op == other.desc.store_op
+            && self.desc.post_process.is_some() == other.desc.post_process.is_some()
+    }
+}
+
+/*RenderBase нужен для определения порядка отображения теней, сглаживания, геометрии, освещения и так далее,
+в нем можно определить порядок рендера применяемый к одному или нескольким кадрам ImageView, с помощью механизма subpass`ов
+renderpassы это про организацию рендера, а не про сам рендер*/
+pub struct RenderBase {
+    pub render_pass: vk::RenderPass, //null при динамическом рендере
+    pub frame_buffers: Vec<vk::Framebuffer>, //пусто при динамическом рендере
+    pub format: vk::Format, //формат кадров swapchain, нужен pipeline`ам при динамическом рендере вместо render pass
+    pub samples: vk::SampleCountFlags, //количество сэмплов MSAA, pipeline`ы должны создаваться с таким же
+    pub desc: RenderPassDesc,
+    pub post: Option<PostProcessBase>, //второй subpass, есть если desc.post_process задан
+    msaa_images: Vec<(vk::Image, vk::DeviceMemory, vk::ImageView)>, //пусто без MSAA
+    scene_images: Vec<(vk::Image, vk::DeviceMemory, vk::ImageView)>, //промежуточные изображения сцены, пусто без post_process
+    dynamic_rendering: Option<DynamicRendering>, //Some выбирает cmd_begin_rendering вместо render pass
+    images: Vec<vk::Image>, //кадры swapchain для барьеров динамического рендера, ими владеет FramesBase
+    image_views: Vec<vk::ImageView>,
+}
+
+impl RenderBase {
+    /*samples больше TYPE_1 включает MSAA: рендер идет в промежуточное многосэмпловое изображение,
+    а в конце subpass оно усредняется (resolve) в изображение swapchain,
+    проход с LoadOp::LOAD всегда без MSAA: загрузить можно только изображение swapchain, а не сэмплы предыдущего прохода.
+    С desc.post_process сцена (subpass 0) рисуется в промежуточное изображение, а subpass 1 читает его
+    как input attachment и пишет результат эффектов в изображение swapchain, вместе с LoadOp::LOAD это ошибка.
+    С dynamic_rendering render pass и framebuffer`ы не создаются, тот же результат дают begin и end*/
+    pub fn new(
+        device: &Device,
+        pipeline_cache: vk::PipelineCache,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        frames_base: &FramesBase,
+        samples: vk::SampleCountFlags,
+        desc: &RenderPassDesc,
+        dynamic_rendering: Option<&DynamicRendering>,
+    ) -> VkResult<Self> {
+        let (format, extent, image_views) = (frames_base.format, frames_base.extent, &frames_base.image_views);
+        let load = desc.load_op == vk::AttachmentLoadOp::LOAD;
+        let post = desc.post_process.is_some();
+        if load && post {
+            return Err(vk::Result::ERROR_INITIALIZATION_FAILED); //изображение сцены временное, загружать из него нечего
+        }
+        let samples = if load { vk::SampleCountFlags::TYPE_1 } else { samples };
+        let msaa = samples != vk::SampleCountFlags::TYPE_1;
+
+        //по многосэмпловому и промежуточному изображению на каждый кадр swapchain
+        let msaa_images = if msaa {
+            image_views
+                .iter()
+                .map(|_| {
+                    Self::create_attachment_image(
+                        device,
+                        memory_properties,
+                        format,
+                        extent,
+                        samples,
+                        vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+                    )
+                })
+                .collect::<VkResult<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+        let scene_images = if post {
+            image_views
+                .iter()
+                .map(|_| {
+                    Self::create_attachment_image(
+                        device,
+                        memory_properties,
+                        format,
+                        extent,
+                        vk::SampleCountFlags::TYPE_1,
+                        if dynamic_rendering.is_some() {
+                            //между отдельными динамическими проходами изображение читается sampler`ом, transient оно быть не может
+                            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED
+                        } else {
+                            vk::ImageUsageFlags::COLOR_ATTACHMENT
+                                | vk::ImageUsageFlags::INPUT_ATTACHMENT
+                                | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT //живет только внутри render pass
+                        },
+                    )
+                })
+                .collect::<VkResult<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        let (render_pass, frame_buffers) = if dynamic_rendering.is_some() {
+            (vk::RenderPass::null(), Vec::new()) //вложения перечисляются прямо при записи команд в begin
+        } else {
+            let render_pass = Self::create_render_pass(device, format, samples, desc)?;
+            let frame_buffers: Vec<vk::Framebuffer> = unsafe { //framebuffers это механизм привязки того как и
+                // в каком порядке будет рендерится кадр ImageView с помощью RenderPass
+                image_views.iter().enumerate().map(|(i, &image_view)| {
+                    let attachments: Vec<vk::ImageView> = msaa_images
+                        .get(i)
+                        .into_iter()
+                        .chain(scene_images.get(i))
+                        .map(|&(_, _, view)| view)
+                        .chain([image_view])
+                        .collect(); //порядок как у описаний вложений
+                    let framebuffer_info = vk::FramebufferCreateInfo::default()
+                        .render_pass(render_pass)
+                        .attachments(&attachments)
+                        .width(extent.width)
+                        .height(extent.height)
+                        .layers(1);
+                    device.create_framebuffer(&framebuffer_info, None).unwrap()
+                })
+            }
+            .collect();
+            (render_pass, frame_buffers)
+        };
+
+        let post = if post {
+            let input_views: Vec<vk::ImageView> = scene_images.iter().map(|&(_, _, view)| view).collect();
+            let subpass = if dynamic_rendering.is_some() { 0 } else { 1 }; //без render pass номер subpass всегда 0
+            Some(PostProcessBase::new(device, pipeline_cache, render_pass, subpass, format, extent, &input_views)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            render_pass,
+            frame_buffers,
+            format,
+            samples,
+            desc: *desc,
+            post,
+            msaa_images,
+            scene_images,
+            dynamic_rendering: dynamic_rendering.cloned(),
+            images: frames_base.images.clone(),
+            image_views: image_views.clone(),
+        })
+    }
+
+    /*порядок вложений: [MSAA изображение] [промежуточное изображение сцены] изображение swapchain,
+    сцена рисуется в первое из них, resolve идет во второе*/
+    fn create_render_pass(
+        device: &Device,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+        desc: &RenderPassDesc,
+    ) -> VkResult<vk::RenderPass> {
+        let load = desc.load_op == vk::AttachmentLoadOp::LOAD;
+        let post = desc.post_process.is_some();
+        let msaa = samples != vk::SampleCountFlags::TYPE_1;
+        //после предыдущего прохода изображение swapchain уже в PRESENT_SRC_KHR, при LOAD этот layout нужно указать честно
+        let initial_layout = if load { vk::ImageLayout::PRESENT_SRC_KHR } else { vk::ImageLayout::UNDEFINED };
+
+        let attachment = |samples: vk::SampleCountFlags| {
+            vk::AttachmentDescription::default() //описание свойства буффера которые будут применять к ImageView
+                .format(format)
+                .samples(samples) /*флаг сглаживания TYPE_1 (x1) без сглаживания, TYPE_2 это MSAA x2 и так далее */
+                .load_op(vk::AttachmentLoadOp::DONT_CARE) /*определяет что будет с буффером кадра перед рендером*/
+                .store_op(vk::AttachmentStoreOp::DONT_CARE) /*определяет состояние буффера после рендера, STORE сохраняет результат*/
+                .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE) /*указание для трафаретного буффера перед рендером, игнорировать, нужен для 3D*/
+                .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE) /*указания для трафаретного буффера после рендера, в данном случае тоже игнорировать*/
+                .initial_layout(vk::ImageLayout::UNDEFINED) /*начальное состояние буффера перед рендером, UNDEFINED начальное состояние не важно*/
+                .final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        };
+        let mut attachments = Vec::new();
+        if msaa {
+            //сэмплы после resolve не нужны, transient изображение может вообще не попасть в память
+            attachments.push(attachment(samples));
+        }
+        if post {
+            attachments.push(attachment(vk::SampleCountFlags::TYPE_1).final_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL));
+        }
+        attachments.push(
+            attachment(vk::SampleCountFlags::TYPE_1)
+                .store_op(desc.store_op)
+                .initial_layout(initial_layout)
+                .final_layout(vk::ImageLayout::PRESENT_SRC_KHR), /*конечное состояние буффера после рендера, PRESENT_SRC_KHR - отобразить*/
+        );
+        attachments[0] = attachments[0].load_op(desc.load_op); //CLEAR очищает цветом clear_color то вложение куда рисуется сцена
+        let swapchain_attachment = attachments.len() as u32 - 1;
+
+        let color_attachment_refs = [vk::AttachmentReference::default() //указывает как субпасс будет использоваться в renderpass
+            .attachment(0) /*сцена рисуется в первое вложение*/
+            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)]; /*буффер оптимизирован для работы со цветом*/
+        let resolve_attachment_refs = [vk::AttachmentReference::default()
+            .attachment(1)
+            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)];
+        let scene_attachment = if msaa { 1 } else { 0 }; //куда попадает готовая сцена без сэмплов
+        let input_attachment_refs = [vk::AttachmentReference::default()
+            .attachment(scene_attachment)
+            .layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)];
+        let post_color_refs = [vk::AttachmentReference::default()
+            .attachment(swapchain_attachment)
+            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)];
+
+        let mut scene_subpass = vk::SubpassDescription::default() //этап рендера,
+            // можно добавлять их больше, каждый субпасс может отвечать за разное,
+            //  будь то цвет или глубина, трафарет и так далее
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS) /*subpass использует графический pipeline
+            если выбрано другое семейство очереди рендер не будет работать, например если семейство очереди помечено как COMPUTE для вычислений,
+            то графический флаг пайплайна не будет работать */
+            .color_attachments(&color_attachment_refs);
+        if msaa {
+            scene_subpass = scene_subpass.resolve_attachments(&resolve_attachment_refs); //resolve color_attachments[i] в resolve_attachments[i] в конце subpass
+        }
+        let post_subpass = vk::SubpassDescription::default()
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+            .input_attachments(&input_attachment_refs)
+            .color_attachments(&post_color_refs);
+        let subpasses = [scene_subpass, post_subpass];
+
+        /*зависимость от внешних команд до render pass, переход layout кадра должен дождаться момента
+        когда swapchain отдаст изображение, семафор acquire ожидается на стадии COLOR_ATTACHMENT_OUTPUT,
+        resolve тоже выполняется на этой стадии, запись предыдущего прохода в том же кадре должна быть видна для LOAD*/
+        let external_dependency = |dst_subpass: u32| {
+            vk::SubpassDependency::default()
+                .src_subpass(vk::SUBPASS_EXTERNAL)
+                .dst_subpass(dst_subpass)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(if load {
+                    vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                } else {
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                })
+        };
+        let dependencies = [
+            external_dependency(0),
+            external_dependency(1), //изображение swapchain с post_process впервые используется во втором subpass
+            /*эффекты читают сцену только после того как subpass 0 (и resolve) ее записал,
+            BY_REGION: фрагменту нужен только свой пиксель, поэтому тайловые GPU не сбрасывают изображение в память*/
+            vk::SubpassDependency::default()
+                .src_subpass(0)
+                .dst_subpass(1)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::INPUT_ATTACHMENT_READ)
+                .dependency_flags(vk::DependencyFlags::BY_REGION),
+        ];
+        let subpass_count = if post { 2 } else { 1 };
+        let dependency_count = if post { 3 } else { 1 };
+
+        let render_pass_info = vk::RenderPassCreateInfo::default()
+            .attachments(&attachments)
+            .subpasses(&subpasses[..subpass_count])
+            .dependencies(&dependencies[..dependency_count]);
+        let render_pass = unsafe { device.create_render_pass(&render_pass_info, None)? };
+
+        Ok(render_pass)
+    }
+
+    //проход для PipelineSet::new, recreate и compatible
+    pub fn pass_info(&self) -> PassInfo {
+        PassInfo {
+            render_pass: self.render_pass,
+            format: self.format,
+            samples: self.samples,
+            desc: self.desc,
+        }
+    }
+
+    //начинает проход в кадр image_index: render pass с framebuffer`ом или cmd_begin_rendering с явными переходами layout
+    pub fn begin(&self, device: &Device, command_buffer: vk::CommandBuffer, image_index: usize, extent: vk::Extent2D) {
+        let clear_value = vk::ClearValue {
+            color: vk::ClearColorValue { float32: self.desc.clear_color }, //используется только при AttachmentLoadOp::CLEAR
+        };
+        let Some(dynamic_rendering) = &self.dynamic_rendering else {
+            let clear_values = [clear_value];
+            let render_pass_begin_info = vk::RenderPassBeginInfo::default()
+                .render_pass(self.render_pass)
+                .framebuffer(self.frame_buffers[image_index])
+                .render_area(extent.into())
+                .clear_values(&clear_values);
+            unsafe { device.cmd_begin_render_pass(command_buffer, &render_pass_begin_info, vk::SubpassContents::INLINE) };
+            return;
+        };
+
+        /*render pass менял layout сам по описаниям вложений, здесь те же переходы делают барьеры,
+        кадр swapchain после предыдущего прохода в PRESENT_SRC_KHR, при CLEAR его содержимое не нужно*/
+        let load = self.desc.load_op == vk::AttachmentLoadOp::LOAD;
+        image_barrier(
+            device,
+            command_buffer,
+            self.images[image_index],
+            0,
+            1,
+            (
+                if load { vk::ImageLayout::PRESENT_SRC_KHR } else { vk::ImageLayout::UNDEFINED },
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, //та же стадия что ждет семафор acquire
+            ),
+            (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            ),
+        );
+        let msaa_image = self.msaa_images.get(image_index);
+        let scene_image = self.scene_images.get(image_index);
+        for &(image, _, _) in msaa_image.into_iter().chain(scene_image) {
+            image_barrier(
+                device,
+                command_buffer,
+                image,
+                0,
+                1,
+                (vk::ImageLayout::UNDEFINED, vk::AccessFlags::empty(), vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT),
+                (
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                ),
+            );
+        }
+
+        //те же роли изображений что у вложений render pass: сцена рисуется в первое, resolve идет во второе
+        let mut views = msaa_image
+            .into_iter()
+            .chain(scene_image)
+            .map(|&(_, _, view)| view)
+            .chain([self.image_views[image_index]]);
+        let color_view = views.next().unwrap();
+        let store_op = if msaa_image.is_some() {
+            vk::AttachmentStoreOp::DONT_CARE //сэмплы после resolve не нужны
+        } else if scene_image.is_some() {
+            vk::AttachmentStoreOp::STORE //сцену прочитает проход эффектов
+        } else {
+            self.desc.store_op
+        };
+        let mut color_attachment = vk::RenderingAttachmentInfo::default()
+            .image_view(color_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(self.desc.load_op)
+            .store_op(store_op)
+            .clear_value(clear_value);
+        if msaa_image.is_some() {
+            color_attachment = color_attachment
+                .resolve_mode(vk::ResolveModeFlags::AVERAGE)
+                .resolve_image_view(views.next().unwrap())
+                .resolve_image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+        }
+        let color_attachments = [color_attachment];
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(extent.into())
+            .layer_count(1)
+            .color_attachments(&color_attachments);
+        unsafe { dynamic_rendering.begin(device, command_buffer, &rendering_info) };
+    }
+
+    //заканчивает проход, с post_process перед этим рисует эффекты: следующим subpass или отдельным динамическим проходом
+    pub fn end(&self, device: &Device, command_buffer: vk::CommandBuffer, image_index: usize, extent: vk::Extent2D) {
+        let effects = self.desc.post_process.unwrap_or_default(); //эффекты по умолчанию просто копируют сцену
+        let Some(dynamic_rendering) = &self.dynamic_rendering else {
+            if let Some(post) = &self.post {
+                unsafe { device.cmd_next_subpass(command_buffer, vk::SubpassContents::INLINE) };
+                post.draw(device, command_buffer, image_index, extent, &effects);
+            }
+            unsafe { device.cmd_end_render_pass(command_buffer) };
+            return;
+        };
+
+        unsafe { dynamic_rendering.end(device, command_buffer) };
+        if let Some(post) = &self.post {
+            //то же что зависимость subpass 0 -> 1: запись сцены и resolve видны фрагментному шейдеру эффектов
+            image_barrier(
+                device,
+                command_buffer,
+                self.scene_images[image_index].0,
+                0,
+                1,
+                (
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                ),
+                (vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::AccessFlags::SHADER_READ, vk::PipelineStageFlags::FRAGMENT_SHADER),
+            );
+            let color_attachments = [vk::RenderingAttachmentInfo::default()
+                .image_view(self.image_views[image_index])
+                .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                .load_op(vk::AttachmentLoadOp::DONT_CARE) //полноэкранный треугольник перезапишет каждый пиксель
+                .store_op(self.desc.store_op)];
+            let rendering_info = vk::RenderingInfo::default()
+                .render_area(extent.into())
+                .layer_count(1)
+                .color_attachments(&color_attachments);
+            unsafe { dynamic_rendering.begin(device, command_buffer, &rendering_info) };
+            post.draw(device, command_buffer, image_index, extent, &effects);
+            unsafe { dynamic_rendering.end(device, command_buffer) };
+        }
+
+        /*как final_layout у вложения swapchain, стадия назначения COLOR_ATTACHMENT_OUTPUT,
+        чтобы барьер следующего прохода с LOAD дождался этого перехода*/
+        image_barrier(
+            device,
+            command_buffer,
+            self.images[image_index],
+            0,
+            1,
+            (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            ),
+            (vk::ImageLayout::PRESENT_SRC_KHR, vk::AccessFlags::empty(), vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT),
+        );
+    }
+
+    /*transient изображение живет только внутри render pass, поэтому драйвер может держать его в памяти тайлов
+    (LAZILY_ALLOCATED на мобильных GPU), на остальных берется обычная видеопамять*/
+    fn create_attachment_image(
+        device: &Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        format: vk::Format,
+        extent: vk::Extent2D,
+        samples: vk::SampleCountFlags,
+        usage: vk::ImageUsageFlags,
+    ) -> VkResult<(vk::Image, vk::DeviceMemory, vk::ImageView)> {
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(extent.into())
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(samples)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+        let image = unsafe { device.create_image(&image_info, None)? };
+
+        let memory_req = unsafe { device.get_image_memory_requirements(image) };
+        let memory_index = find_memorytype_index(
+            &memory_req,
+            memory_properties,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::LAZILY_ALLOCATED,
+        )
+        .or_else(|| find_memorytype_index(&memory_req, memory_properties, vk::MemoryPropertyFlags::DEVICE_LOCAL))
+        .ok_or(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)?;
+        let memory = unsafe {
+            device.allocate_memory(
+                &vk::MemoryAllocateInfo::default()
+                    .allocation_size(memory_req.size)
+                    .memory_type_index(memory_index),
+                None,
+            )?
+        };
+        unsafe { device.bind_image_memory(image, memory, 0)? };
+
+        let view_info = vk::ImageViewCreateInfo::default()
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(format)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            });
+        let view = unsafe { device.create_image_view(&view_info, None)? };
+
+        Ok((image, memory, view))
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            for &frame_buffer in &self.frame_buffers {
+                device.destroy_framebuffer(frame_buffer, None);
+            }
+            if let Some(post) = &self.post {
+                post.destroy(device);
+            }
+            for &(image, memory, view) in self.msaa_images.iter().chain(&self.scene_images) {
+                device.destroy_image_view(view, None);
+                device.destroy_image(image, None);
+                device.free_memory(memory, None);
+            }
+            device.destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
+
+/*варианты одного pipeline по всем BlendMode, сами pipeline`ы и их раскладка принадлежат PipelineRegistry*/
+pub struct AppearanceBase {
+    pub pipelines: [vk::Pipeline; BlendMode::COUNT], //индекс BlendMode::index
+    pub pipeline_layout: vk::PipelineLayout,
+}
+
+impl AppearanceBase {
+    //desc.blend не важен, запрашивается вариант на каждый режим, одинаковые описания реестр создает один раз
+    pub fn new(device: &Device, registry: &mut PipelineRegistry, desc: &PipelineDesc) -> Result<Self, PipelineError> {
+        let mut pipelines = [vk::Pipeline::null(); BlendMode::COUNT];
+        let mut pipeline_layout = vk::PipelineLayout::null();
+        for mode in BlendMode::ALL {
+            let registered = registry.get(device, &desc.with_blend(mode))?;
+            pipelines[mode.index()] = registered.pipeline;
+            pipeline_layout = registered.pipeline_layout; //у вариантов одни шейдеры, значит и раскладка одна
+        }
+        Ok(Self { pipelines, pipeline_layout })
+    }
+}
+
+/*pipeline`ы для всех видов вершин Batcher`а под один render pass, пересоздаются вместе с ним*/
+pub struct PipelineSet {
+    pub shapes: AppearanceBase,
+    pub sprites: AppearanceBase,
+    pub sdf_text: AppearanceBase,
+    pub particles: ParticlePipeline,
+    pub wireframe: Option<RegisteredPipeline>, //None без fillModeNonSolid, тогда каркас строит Batcher на CPU
+    registry: PipelineRegistry,
+}
+
+impl PipelineSet {
+    //pass берется из RenderBase::pass_info или RenderTarget::pass_info, для которого создается набор
+    pub fn new(
+        device: &Device,
+        pipeline_cache: vk::PipelineCache,
+        pass: PassInfo,
+        extent: vk::Extent2D,
+        config: &PipelineConfig,
+        features: &DeviceFeatures,
+    ) -> Result<Self, PipelineError> {
+        let mut registry =
+            PipelineRegistry::new(pipeline_cache, pass, extent, features.fill_mode_non_solid, config.shader_dir.clone());
+        let wireframe = if features.fill_mode_non_solid {
+            Some(registry.get(device, &config.wireframe)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            shapes: AppearanceBase::new(device, &mut registry, &config.shapes)?,
+            sprites: AppearanceBase::new(device, &mut registry, &config.sprites)?,
+            sdf_text: AppearanceBase::new(device, &mut registry, &config.sdf_text)?,
+            particles: ParticlePipeline::new(device, pipeline_cache, pass.render_pass, pass.format, pass.samples)?,
+            wireframe,
+            registry,
+        })
+    }
+
+    pub fn bind(&self, batcher: &mut Batcher) {
+        batcher.set_pipeline(self.shapes.pipelines, self.shapes.pipeline_layout);
+        batcher.set_sprite_pipeline(self.sprites.pipelines, self.sprites.pipeline_layout);
+        batcher.set_sdf_pipeline(self.sdf_text.pipelines, self.sdf_text.pipeline_layout);
+        match self.wireframe {
+            Some(wireframe) => batcher.set_wireframe_pipeline(wireframe.pipeline, wireframe.pipeline_layout),
+            None => batcher.set_wireframe_pipeline(vk::Pipeline::null(), vk::PipelineLayout::null()),
+        }
+    }
+
+    /*набор для пересозданного прохода (изменился размер окна): с динамическими viewport/scissor
+    и совместимым проходом старые pipeline`ы годятся и остаются, иначе набор создается заново*/
+    pub fn recreate(
+        &mut self,
+        device: &Device,
+        pipeline_cache: vk::PipelineCache,
+        pass: PassInfo,
+        extent: vk::Extent2D,
+        config: &PipelineConfig,
+        features: &DeviceFeatures,
+    ) -> Result<(), PipelineError> {
+        if self.registry.retarget(pass, extent) {
+            return Ok(());
+        }
+        unsafe { self.destroy(device) };
+        *self = Self::new(device, pipeline_cache, pass, extent, config, features)?;
+        Ok(())
+    }
+
+    //набор можно использовать в проходе pass, см. PipelineRegistry::compatible
+    pub fn compatible(&self, pass: &PassInfo) -> bool {
+        self.registry.compatible(pass)
+    }
+
+    //число разных pipeline`ов Batcher`а в наборе
+    pub fn pipeline_count(&self) -> usize {
+        self.registry.len()
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            self.registry.destroy(device);
+            self.particles.destroy(device);
+        }
+    }
+}
+
+/*CommandBase хранит пул команд и по одному command buffer на каждый кадр в полете,
+а также примитивы синхронизации: семафор acquire (swapchain отдал кадр) и fence (GPU закончил кадр)*/
+pub struct CommandBase {
+    pub pool: vk::CommandPool,
+    pub command_buffers: Vec<vk::CommandBuffer>,
+    pub acquire_semaphores: Vec<vk::Semaphore>,
+    pub in_flight_fences: Vec<vk::Fence>,
+    pub current_frame: usize,
+    pub gpu_timer: Option<GpuTimer>, //если задан, render_frame замеряет на GPU каждый проход кадра
+    pub frame_timings: FrameTimings, //время CPU на этапы последнего render_frame
+    pub gpu_statistics: Option<GpuStatistics>, //если задан, render_frame считает вершины и фрагменты каждого прохода
+} //vulkan command and frame synchronization resources
+
+impl CommandBase {
+    pub fn new(device: &Device, queue_family_index: u32, frames_in_flight: usize) -> VkResult<Self> {
+        let pool_info = vk::CommandPoolCreateInfo::default()
+            .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER) //разрешаем перезаписывать отдельные command buffer каждый кадр
+            .queue_family_index(queue_family_index);
+        let pool = unsafe { device.create_command_pool(&pool_info, None)? };
+
+        let allocate_info = vk::CommandBufferAllocateInfo::default()
+            .command_pool(pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(frames_in_flight as u32);
+        let command_buffers = unsafe { device.allocate_command_buffers(&allocate_info)? };
+
+        let mut acquire_semaphores = Vec::with_capacity(frames_in_flight);
+        let mut in_flight_fences = Vec::with_capacity(frames_in_flight);
+        for _ in 0..frames_in_flight {
+            unsafe {
+                acquire_semaphores.push(device.create_semaphore(&vk::SemaphoreCreateInfo::default(), None)?);
+                in_flight_fences.push(device.create_fence(
+                    &vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED), //первый кадр не должен ждать
+                    None,
+                )?);
+            }
+        }
+
+        Ok(Self {
+            pool,
+            command_buffers,
+            acquire_semaphores,
+            in_flight_fences,
+            current_frame: 0,
+            gpu_timer: None,
+            frame_timings: FrameTimings::default(),
+            gpu_statistics: None,
+        })
+    }
+
+    /*один кадр целиком: ждем fence, берем изображение из swapchain, сначала рисуем в offscreen цели targets,
+    потом проходы цепочки эффектов post_chain (ее source должен быть среди targets), затем по очереди выполняем render pass`ы passes, record(command_buffer, frame, pass) записывает команды отрисовки прохода,
+    pass от 0 до targets.len() это targets[pass], дальше passes[pass - targets.len()], затем отправка в очередь и показ,
+    возвращает true если swapchain устарел (suboptimal) и его стоит пересоздать*/
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_frame<P: FnMut(vk::CommandBuffer, usize), F: FnMut(vk::CommandBuffer, usize, usize)>(
+        &mut self,
+        device: &Device,
+        queue: Queue,
+        frames_base: &FramesBase,
+        targets: &[&RenderTarget],
+        post_chain: Option<&PostChain>,
+        passes: &[&RenderBase],
+        mut prepare: P,
+        mut record: F,
+    ) -> VkResult<bool> {
+        let frame = self.current_frame;
+        let command_buffer = self.command_buffers[frame];
+        let fence = self.in_flight_fences[frame];
+
+        let acquire_start = Instant::now();
+        unsafe { device.wait_for_fences(&[fence], true, u64::MAX)? }; //GPU закончил предыдущее использование ресурсов этого кадра
+
+        let (image_index, acquire_suboptimal) = unsafe {
+            frames_base.loader.acquire_next_image(
+                frames_base.swapchain,
+                u64::MAX,
+                self.acquire_semaphores[frame],
+                vk::Fence::null(),
+            )?
+        };
+
+        let submit_start = Instant::now();
+        self.frame_timings.acquire = submit_start - acquire_start;
+
+        unsafe {
+            device.reset_fences(&[fence])?; //сбрасываем только после успешного acquire, иначе следующий wait зависнет
+            device.reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())?;
+            device.begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo::default().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )?;
+        }
+
+        /*метки времени ставятся вне render pass`ов, участок называется по индексу pass из record,
+        чтобы отчет GpuTimer::report совпадал с тем, что вызывающий рисует в каждом проходе*/
+        let mut timer = self.gpu_timer.as_mut();
+        if let Some(timer) = timer.as_deref_mut() {
+            timer.begin_frame(device, command_buffer, frame);
+        }
+        let mut statistics = self.gpu_statistics.as_mut();
+        if let Some(statistics) = statistics.as_deref_mut() {
+            statistics.begin_frame(device, command_buffer, frame);
+        }
+        //запросы статистики обязаны начинаться и заканчиваться внутри одного subpass, поэтому оборачивают только record
+        let mut record = |command_buffer, frame, pass| {
+            GpuStatistics::scoped(statistics.as_deref_mut(), device, command_buffer, frame, || format!("pass {pass}"), || {
+                record(command_buffer, frame, pass)
+            })
+        };
+
+        //работа вне render pass`ов (compute dispatch, копирования) до первого прохода кадра
+        GpuTimer::scoped(timer.as_deref_mut(), device, command_buffer, frame, || "prepare".to_string(), || {
+            prepare(command_buffer, frame)
+        });
+
+        //цели рисуются раньше, чтобы проходы swapchain уже могли читать их как текстуры
+        for (pass, target) in targets.iter().enumerate() {
+            GpuTimer::scoped(timer.as_deref_mut(), device, command_buffer, frame, || format!("pass {pass}"), || {
+                target.begin(device, command_buffer);
+                record(command_buffer, frame, pass);
+                target.end(device, command_buffer);
+            });
+        }
+        if let Some(post_chain) = post_chain {
+            GpuTimer::scoped(timer.as_deref_mut(), device, command_buffer, frame, || "post chain".to_string(), || {
+                post_chain.record(device, command_buffer)
+            });
+        }
+
+        for (pass, render_base) in passes.iter().enumerate() {
+            let pass = targets.len() + pass;
+            GpuTimer::scoped(timer.as_deref_mut(), device, command_buffer, frame, || format!("pass {pass}"), || {
+                render_base.begin(device, command_buffer, image_index as usize, frames_base.extent);
+                record(command_buffer, frame, pass);
+                render_base.end(device, command_buffer, image_index as usize, frames_base.extent);
+            });
+        }
+        unsafe { device.end_command_buffer(command_buffer)? };
+
+        let wait_semaphores = [self.acquire_semaphores[frame]];
+        let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let command_buffers = [command_buffer];
+        let signal_semaphores = [frames_base.present_semaphores[image_index as usize]];
+        let submit_info = vk::SubmitInfo::default()
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .command_buffers(&command_buffers)
+            .signal_semaphores(&signal_semaphores);
+
+        unsafe { device.queue_submit(queue, &[submit_info], fence)? };
+        let present_start = Instant::now();
+        self.frame_timings.submit = present_start - submit_start;
+
+        let swapchains = [frames_base.swapchain];
+        let image_indices = [image_index];
+        let present_info = vk::PresentInfoKHR::default()
+            .wait_semaphores(&signal_semaphores)
+            .swapchains(&swapchains)
+            .image_indices(&image_indices);
+
+        let present_suboptimal = unsafe { frames_base.loader.queue_present(queue, &present_info)? };
+        self.frame_timings.present = present_start.elapsed();
+
+        self.current_frame = (frame + 1) % self.command_buffers.len();
+
+        Ok(acquire_suboptimal || present_suboptimal)
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            for &semaphore in &self.acquire_semaphores {
+                device.destroy_semaphore(semaphore, None);
+            }
+            for &fence in &self.in_flight_fences {
+                device.destroy_fence(fence, None);
+            }
+            device.destroy_command_pool(self.pool, None); //command buffer`ы освобождаются вместе с пулом
+            if let Some(gpu_timer) = &self.gpu_timer {
+                gpu_timer.destroy(device);
+            }
+            if let Some(gpu_statistics) = &self.gpu_statistics {
+                gpu_statistics.destroy(device);
+            }
+        }
+    }
+}
+
+pub struct AppBase {
+    pub entry: Entry,
+    pub instance: Instance,
+    pub event_loop: RefCell<EventLoop<()>>, //RefCell потому что run_on_demand требует &mut, а остальные поля нужны внутри цикла
+    pub window: Window,
+    pub surface: vk::SurfaceKHR,
+    pub surface_loader: surface::Instance,
+    pub physical_device: vk::PhysicalDevice,
+    pub device_memory_properties: vk::PhysicalDeviceMemoryProperties,
+    pub queue_family_index: u32,
+    pub device: Device,
+    pub present_queue: Queue,
+    pub api_version: u32, //версия Vulkan instance, не выше 1.3, у устройства она может быть ниже (features.api_version)
+    pub features: DeviceFeatures, //включенные возможности устройства
+    pub dynamic_rendering: Option<DynamicRendering>, //None если устройство не умеет cmd_begin_rendering
+} //basic init vulkan resources
+
+impl AppBase {
+    pub fn new(width: u32, height: u32) -> VkResult<Self> {
+        let entry = Entry::linked(); //базовый ресурс Vulkan
+        let app_name = c"vulkan_2d_triangle";
+
+        /*наибольшая версия которую знает загрузчик, но не выше 1.3 под которую собран ash,
+        загрузчик Vulkan 1.0 не знает vkEnumerateInstanceVersion и поддерживает только 1.0*/
+        let api_version = match unsafe { entry.try_enumerate_instance_version() } {
+            Ok(Some(version)) => version.min(vk::API_VERSION_1_3),
+            _ => vk::API_VERSION_1_0,
+        };
+
+        let app_info = vk::ApplicationInfo::default()
+            .application_name(app_name)
+            .application_version(0)
+            .engine_name(app_name)
+            .engine_version(0)
+            .api_version(api_version);
+
+        let layer_name = [c"VK_LAYER_KHRONOS_validation"]; //слой валидации для проверок ошибок Vulkan на этапе компиляции, можно добавить еще дополнительные слои например для подсчета FPS
+        let layers_names: Vec<*const c_char> = layer_name //интепретация запись слоя в массив c_char, так как ash vk работает только с C
+            .iter()
+            .map(|raw_name| raw_name.as_ptr())
+            .collect();
+
+        let event_loop = EventLoop::new().unwrap(); //создаем цикл событий
+
+        let window = WindowBuilder::new() //создаем окно
+            .with_title("vulkan_2d_triangle")
+            .with_inner_size(winit::dpi::LogicalSize::new(
+                //логический размер окна с учетом DPI, автоматическое масштабирование
+                f64::from(width),
+                f64::from(height),
+            ))
+            .build(&event_loop) //помещаем цикл событий в окно
+            .unwrap();
+
+        let mut extension_names =
+            ash_window::enumerate_required_extensions(window.display_handle().unwrap().as_raw()) //возвращаем дескриптор оконного менеджера в список расширений
+                .unwrap()
+                .to_vec();
+        extension_names.push(debug_utils::NAME.as_ptr());
+
+        let create_info = vk::InstanceCreateInfo::default() //передаем данные о расширениях и слоях в Instance
+            .application_info(&app_info)
+            .flags(vk::InstanceCreateFlags::empty())
+            .enabled_layer_names(&layers_names)
+            .enabled_extension_names(&extension_names);
+
+        let instance: Instance =
+            unsafe { entry.create_instance(&create_info, None) }.expect("Instance Create Error"); //Instance создается с помощью entry 
+
+        let surface = unsafe {
+            //создаем поверхность рендера
+            ash_window::create_surface(
+                &entry,
+                &instance,
+                window.display_handle().unwrap().as_raw(), //передаем дескриптор оконного менеджера wayland, x11 etc
+                window.window_handle().unwrap().as_raw(), //передаем дескриптор нашего конкретного окна, экземпляр window
+                None,
+            )
+        }
+        .unwrap();
+
+        let surface_loader = surface::Instance::new(&entry, &instance); //загрузчик поверхности рендера
+
+        let physical_devices = unsafe { instance.enumerate_physical_devices().unwrap() }; //возвращается массив физических устройств 
+
+        let (physical_device, queue_family_index) = physical_devices //получаем активное физическое устройство
+            .into_iter() //перечисляем без сохранения всех физический устройств
+            .find_map(|pdevice| unsafe {
+                //получаем физическое устройство из списка
+                {
+                    instance
+                        .get_physical_device_queue_family_properties(pdevice) //получаем свойства очереди активного устройста
+                        .iter() //перечисляем их
+                        .enumerate() //достаем индексы свойств
+                        .find_map(|(index, info)| {
+                            let supports_graphics =
+                                info.queue_flags.contains(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE); //смотрим поддерживает ли свойство активного устройства данные флаги, COMPUTE нужен симуляции частиц в той же очереди
+                            let supports_surface = surface_loader
+                                .get_physical_device_surface_support(pdevice, index as u32, surface) //смотрим поддерживает ли устройство данную поверхность рендера
+                                .unwrap_or(false); //если нет верни false
+                            if supports_graphics && supports_surface {
+                                Some((pdevice, index as u32)) //если поддерживает верни устройство и индекс свойств семейства очереди 
+                            } else {
+                                None
+                            }
+                        })
+                }
+            })
+            .expect("No physical device found");
+
+        let device_memory_properties = unsafe {
+            //типы и кучи памяти устройства, нужны для выбора памяти под буфферы и изображения
+            instance.get_physical_device_memory_properties(physical_device)
+        };
+
+        let mut device_extension_names_raw: Vec<*const c_char> =
+            vec![ash::khr::swapchain::NAME.as_ptr()]; //расширения устройства
+
+        /*возможности новее 1.0 (timeline семафоры, synchronization2, динамический рендер) берутся из ядра версии устройства
+        или из расширений, если устройство старше, включается все что поддерживается*/
+        let features = DeviceFeatures::query(&instance, physical_device, api_version);
+        device_extension_names_raw.extend(features.extension_names());
+        let mut feature_chain = FeatureChain::default();
+
+        let priorities = [1.0_f32]; //приоритет очереди, первый
+
+        let queue_info = vk::DeviceQueueCreateInfo::default() //информация для создания очереди устройства
+            .queue_family_index(queue_family_index) //индекс очереди
+            .queue_priorities(&priorities); //приоритет первый
+        let queue_infos = [queue_info]; //вектор очередей 
+        let device_create_info = vk::DeviceCreateInfo::default()
+            .queue_create_infos(&queue_infos)
+            .enabled_extension_names(&device_extension_names_raw);
+        //возможности включаются структурами в цепочке p_next, без этого их функции вызывать нельзя
+        let device_create_info = feature_chain.push(&features, device_create_info);
+
+        let device = unsafe {
+            //создаем логическое устройство с помощью экземпляра instance
+            instance
+                .create_device(physical_device, &device_create_info, None) //передаем физическое устройство
+                .unwrap()
+        };
+
+        let dynamic_rendering = features.dynamic_rendering.map(|source| match source {
+            FeatureSource::Core => DynamicRendering::Core,
+            FeatureSource::Extension => DynamicRendering::Khr(ash::khr::dynamic_rendering::Device::new(&instance, &device)),
+        });
+
+        let present_queue = unsafe { device.get_device_queue(queue_family_index, 0) }; //возвращаем очередь логического устройства,
+        // очереди принимают SubmitInfo а SubmitInfo принимает массив CommandBuffer, массив комманд на выполнение на  GPU
+        // первый параметр это индекс семейства очередей GRAPHICS или COMPUTE, PRESENT, TRANSFER  etc, второй параметр это индекс очереди, очередей в одном семействе может быть заданое количество
+
+        Ok(Self {
+            entry,
+            instance,
+            event_loop: RefCell::new(event_loop),
+            window,
+            surface,
+            surface_loader,
+            physical_device,
+            device_memory_properties,
+            queue_family_index,
+            device,
+            present_queue,
+            api_version,
+            features,
+            dynamic_rendering,
+        })
+    }
+
+    /*наибольшее поддерживаемое количество сэмплов цвета не больше requested,
+    framebufferColorSampleCounts это маска всех количеств сэмплов которые устройство умеет для цветных вложений*/
+    pub fn sample_count(&self, requested: u32) -> vk::SampleCountFlags {
+        let limits = unsafe { self.instance.get_physical_device_properties(self.physical_device).limits };
+        [64, 32, 16, 8, 4, 2]
+            .into_iter()
+            .map(vk::SampleCountFlags::from_raw)
+            .find(|&count| count.as_raw() <= requested && limits.framebuffer_color_sample_counts.contains(count))
+            .unwrap_or(vk::SampleCountFlags::TYPE_1)
+    }
+
+    /*поверхность рендера для еще одного окна на том же instance, семейство очередей устройства
+    выбрано под основное окно, поэтому проверяем что оно умеет показывать и в новую поверхность*/
+    pub fn create_surface(&self, window: &Window) -> VkResult<vk::SurfaceKHR> {
+        let surface = unsafe {
+            ash_window::create_surface(
+                &self.entry,
+                &self.instance,
+                window.display_handle().unwrap().as_raw(),
+                window.window_handle().unwrap().as_raw(),
+                None,
+            )?
+        };
+        let supported = unsafe {
+            self.surface_loader
+                .get_physical_device_surface_support(self.physical_device, self.queue_family_index, surface)
+                .unwrap_or(false)
+        };
+        if !supported {
+            unsafe { self.surface_loader.destroy_surface(surface, None) };
+            return Err(vk::Result::ERROR_INCOMPATIBLE_DISPLAY_KHR);
+        }
+        Ok(surface)
+    }
+
+    /*запускает цикл событий, f получает каждое событие и цель цикла, через которую можно открывать новые окна,
+    Event::AboutToWait приходит когда очередь событий разобрана и пора рисовать кадр,
+    когда завершать цикл решает f: вызывает exit у цели цикла, например на WindowEvent::CloseRequested основного окна*/
+    pub fn render_loop<F: FnMut(&Event<()>, &EventLoopWindowTarget<()>)>(
+        &self,
+        mut f: F,
+    ) -> std::result::Result<(), winit::error::EventLoopError> {
+        self.event_loop.borrow_mut().run_on_demand(|event, elwp| {
+            elwp.set_control_flow(ControlFlow::Poll); //не ждем новых событий, рисуем кадры непрерывно
+            f(&event, elwp)
+        })
+    }
+}
+
+impl Drop for AppBase {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.device_wait_idle().unwrap();
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+/*дополнительное окно на общих instance и device: своя поверхность, swapchain, framebuffer`ы, command buffer`ы и Batcher,
+pipeline`ы берутся у основного окна, если проход окна с ними совместим по PassInfo::compatible
+(тот же способ рендера, формат, сэмплы и структура RenderPassDesc), иначе у окна свой PipelineSet*/
+pub struct WindowBase {
+    pub window: Window,
+    pub surface: vk::SurfaceKHR,
+    pub frames_base: FramesBase,
+    pub render_base: RenderBase,
+    pub command_base: CommandBase,
+    pub pipeline_set: Option<PipelineSet>, //None пока подходят pipeline`ы основного окна
+    pub batcher: Batcher,
+    pub swapchain_dirty: bool,
+}
+
+impl WindowBase {
+    //desc и dynamic_rendering как у прохода, под который создан shared, иначе pipeline`ы не будут общими
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        app_base: &AppBase,
+        window: Window,
+        pipeline_cache: vk::PipelineCache,
+        samples: vk::SampleCountFlags,
+        desc: &RenderPassDesc,
+        dynamic_rendering: Option<&DynamicRendering>,
+        shared: &PipelineSet,
+        config: &PipelineConfig,
+    ) -> Result<Self, PipelineError> {
+        let device = &app_base.device;
+        let surface = app_base.create_surface(&window)?;
+        let frames_base = FramesBase::new(
+            &app_base.instance,
+            device,
+            surface,
+            &app_base.surface_loader,
+            app_base.physical_device,
+            app_base.queue_family_index,
+            &window,
+        )?;
+        let render_base = RenderBase::new(
+            device,
+            pipeline_cache,
+            &app_base.device_memory_properties,
+            &frames_base,
+            samples,
+            desc,
+            dynamic_rendering,
+        )?;
+        let pipeline_set = if shared.compatible(&render_base.pass_info()) {
+            None
+        } else {
+            Some(PipelineSet::new(device, pipeline_cache, render_base.pass_info(), frames_base.extent, config, &app_base.features)?)
+        };
+        let command_base = CommandBase::new(device, app_base.queue_family_index, MAX_FRAMES_IN_FLIGHT)?;
+        let batcher = Batcher::new(device, &app_base.device_memory_properties, MAX_FRAMES_IN_FLIGHT)?;
+
+        Ok(Self {
+            window,
+            surface,
+            frames_base,
+            render_base,
+            command_base,
+            pipeline_set,
+            batcher,
+            swapchain_dirty: false,
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /*кадр окна: draw записывает фигуры в Batcher в пикселях окна, свернутое окно пропускается,
+    после изменения размера swapchain и framebuffer`ы пересоздаются, общие pipeline`ы остаются пока совместимы*/
+    pub fn render(
+        &mut self,
+        app_base: &AppBase,
+        pipeline_cache: vk::PipelineCache,
+        shared: &PipelineSet,
+        config: &PipelineConfig,
+        draw: impl FnOnce(&mut Batcher, vk::Extent2D),
+    ) -> Result<(), PipelineError> {
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+        let device = &app_base.device;
+        if self.swapchain_dirty {
+            unsafe {
+                device.device_wait_idle()?;
+                self.render_base.destroy(device);
+                self.frames_base.destroy(device);
+            }
+            self.frames_base = FramesBase::new(
+                &app_base.instance,
+                device,
+                self.surface,
+                &app_base.surface_loader,
+                app_base.physical_device,
+                app_base.queue_family_index,
+                &self.window,
+            )?;
+            self.render_base = RenderBase::new(
+                device,
+                pipeline_cache,
+                &app_base.device_memory_properties,
+                &self.frames_base,
+                self.render_base.samples,
+                &self.render_base.desc,
+                self.render_base.dynamic_rendering.as_ref(),
+            )?;
+            if let Some(pipeline_set) = &mut self.pipeline_set {
+                pipeline_set.recreate(
+                    device,
+                    pipeline_cache,
+                    self.render_base.pass_info(),
+                    self.frames_base.extent,
+                    config,
+                    &app_base.features,
+                )?;
+            }
+            self.swapchain_dirty = false;
+        }
+        /*новый swapchain может получить другой формат, а основное окно могло пересоздать shared под свой проход,
+        поэтому общие pipeline`ы проверяются каждый кадр*/
+        if self.pipeline_set.is_none() && !shared.compatible(&self.render_base.pass_info()) {
+            self.pipeline_set = Some(PipelineSet::new(
+                device,
+                pipeline_cache,
+                self.render_base.pass_info(),
+                self.frames_base.extent,
+                config,
+                &app_base.features,
+            )?);
+        }
+
+        self.batcher.begin(self.frames_base.extent);
+        self.pipeline_set.as_ref().unwrap_or(shared).bind(&mut self.batcher);
+        draw(&mut self.batcher, self.frames_base.extent);
+
+        let batcher = &mut self.batcher;
+        let mut flushed = Ok(());
+        let result = self.command_base.render_frame(
+            device,
+            app_base.present_queue,
+            &self.frames_base,
+            &[],
+            None,
+            &[&self.render_base],
+            |_, _| {},
+            |command_buffer, frame, _| {
+                flushed = batcher.flush(device, &app_base.device_memory_properties, command_buffer, frame);
+            },
+        );
+        match result {
+            Ok(suboptimal) => self.swapchain_dirty |= suboptimal,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.swapchain_dirty = true,
+            Err(err) => return Err(err.into()),
+        }
+        flushed?;
+        Ok(())
+    }
+
+    pub unsafe fn destroy(&self, app_base: &AppBase) {
+        let device = &app_base.device;
+        unsafe {
+            self.batcher.destroy(device);
+            self.command_base.destroy(device);
+            if let Some(pipeline_set) = &self.pipeline_set {
+                pipeline_set.destroy(device);
+            }
+            self.render_base.destroy(device);
+            self.frames_base.destroy(device);
+            app_base.surface_loader.destroy_surface(self.surface, None);
+        }
+    }
+}
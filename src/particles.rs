@@ -0,0 +1,454 @@
+use crate::AppBase;
+use crate::blend::BlendMode;
+use crate::buffer::{BufferBase, find_memorytype_index, submit_once};
+use crate::post::{embedded_error, embedded_interface, embedded_reflection, shader_module};
+use crate::view::viewport;
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+
+const WORKGROUP_SIZE: u32 = 64; //local_size_x в particles.comp
+const SIMULATION_SHADER: &[u8] = include_bytes!("../shader/particles.comp.spv");
+const PARTICLE_VERT: &[u8] = include_bytes!("../shader/particle.vert.spv");
+const PARTICLE_FRAG: &[u8] = include_bytes!("../shader/particle.frag.spv");
+
+/*частица в storage буффере, раскладка std430 совпадает с Particle в particles.comp,
+тот же буффер читается как вершинный (по экземпляру на частицу) в particle.vert*/
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Particle {
+    pub position: [f32; 2], //в пикселях окна
+    pub velocity: [f32; 2], //пикселей в секунду
+    pub color: [f32; 4],
+    pub life: [f32; 2], //оставшееся и полное время жизни в секундах
+    padding: [f32; 2], //выравнивание структуры std430 до 16 байт
+}
+
+impl Particle {
+    //поля частицы, которые particle.vert читает как атрибуты экземпляра, по location входов
+    pub fn attributes() -> [vk::VertexInputAttributeDescription; 2] {
+        let field = |location, format, offset: usize| vk::VertexInputAttributeDescription {
+            location,
+            binding: 0,
+            format,
+            offset: offset as u32,
+        };
+        [
+            field(0, vk::Format::R32G32_SFLOAT, std::mem::offset_of!(Particle, position)),
+            field(1, vk::Format::R32G32B32A32_SFLOAT, std::mem::offset_of!(Particle, color)),
+        ]
+    }
+}
+
+/*параметры эмиттера, передаются в compute шейдер push constant`ами каждый кадр*/
+#[derive(Clone, Copy, Debug)]
+pub struct EmitterParams {
+    pub position: [f32; 2],
+    pub gravity: [f32; 2], //пикселей в секунду за секунду, ось Y вниз
+    pub lifetime: f32, //наибольшее время жизни, каждая частица живет от половины до полного
+    pub speed: f32, //наибольшая начальная скорость
+}
+
+impl Default for EmitterParams {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0],
+            gravity: [0.0, 300.0],
+            lifetime: 2.5,
+            speed: 350.0,
+        }
+    }
+}
+
+//совпадает с layout(push_constant) в particles.comp
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SimulationConstants {
+    emitter: [f32; 2],
+    gravity: [f32; 2],
+    dt: f32,
+    time: f32,
+    lifetime: f32,
+    speed: f32,
+    count: u32,
+}
+
+/*симуляция частиц на GPU: compute pipeline обновляет storage буффер в видеопамяти каждый кадр,
+затем барьер делает записи видимыми для чтения вершинных атрибутов и graphics pipeline рисует тот же буффер без копирования.
+Очередь одна (семейство из AppBase::new поддерживает GRAPHICS и COMPUTE), поэтому хватает pipeline барьеров*/
+pub struct ParticleSystem {
+    pub buffer: vk::Buffer,
+    pub memory: vk::DeviceMemory,
+    pub count: u32,
+    pub emitter: EmitterParams,
+    set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    shader_module: vk::ShaderModule,
+    constants: SimulationConstants,
+} //vulkan compute particle resources
+
+impl ParticleSystem {
+    //count больше нуля, буффер нулевого размера в Vulkan создать нельзя
+    pub fn new(
+        app_base: &AppBase,
+        pipeline_cache: vk::PipelineCache,
+        command_pool: vk::CommandPool,
+        count: u32,
+        emitter: EmitterParams,
+    ) -> VkResult<Self> {
+        if count == 0 {
+            return Err(vk::Result::ERROR_INITIALIZATION_FAILED);
+        }
+        let device = &app_base.device;
+        let size = (count as usize * size_of::<Particle>()) as vk::DeviceSize;
+
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(
+                vk::BufferUsageFlags::STORAGE_BUFFER //запись из compute шейдера
+                    | vk::BufferUsageFlags::VERTEX_BUFFER //чтение атрибутов экземпляров
+                    | vk::BufferUsageFlags::TRANSFER_DST, //начальное состояние
+            )
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = unsafe { device.create_buffer(&buffer_info, None)? };
+        let memory_req = unsafe { device.get_buffer_memory_requirements(buffer) };
+        let memory_index = find_memorytype_index(
+            &memory_req,
+            &app_base.device_memory_properties,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL, //CPU к частицам после загрузки не обращается
+        )
+        .ok_or(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)?;
+        let memory = unsafe {
+            device.allocate_memory(
+                &vk::MemoryAllocateInfo::default()
+                    .allocation_size(memory_req.size)
+                    .memory_type_index(memory_index),
+                None,
+            )?
+        };
+        unsafe { device.bind_buffer_memory(buffer, memory, 0)? };
+
+        /*все частицы сначала невидимы и умирают в разное время, поэтому эмиттер выпускает их равномерно,
+        а не одной вспышкой в первом кадре*/
+        let particles: Vec<Particle> = (0..count)
+            .map(|i| {
+                let life = emitter.lifetime * i as f32 / count as f32;
+                Particle {
+                    position: emitter.position,
+                    color: [0.0; 4],
+                    life: [life, emitter.lifetime],
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let staging = BufferBase::new(
+            device,
+            &app_base.device_memory_properties,
+            size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+        )?;
+        staging.write(0, &particles);
+        let upload = submit_once(device, command_pool, app_base.present_queue, |command_buffer| unsafe {
+            device.cmd_copy_buffer(command_buffer, staging.buffer, buffer, &[vk::BufferCopy::default().size(size)]);
+        });
+        unsafe { staging.destroy(device) };
+        upload?;
+
+        //storage буффер частиц и push constant`ы описаны в самом particles.comp
+        let interface = embedded_interface(&[SIMULATION_SHADER])?;
+        let set_layout = interface.create_set_layouts(device).map_err(embedded_error)?[0];
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: 1,
+        }];
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::default().max_sets(1).pool_sizes(&pool_sizes),
+                None,
+            )?
+        };
+        let descriptor_set = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::default()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&[set_layout]),
+            )?[0]
+        };
+        let buffer_infos = [vk::DescriptorBufferInfo::default().buffer(buffer).range(vk::WHOLE_SIZE)];
+        let write = vk::WriteDescriptorSet::default()
+            .dst_set(descriptor_set)
+            .dst_binding(0)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .buffer_info(&buffer_infos);
+        unsafe { device.update_descriptor_sets(&[write], &[]) };
+
+        let pipeline_layout = interface.create_pipeline_layout(device, &[set_layout])?;
+
+        let shader_module = shader_module(device, SIMULATION_SHADER)?;
+        let stage = vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .module(shader_module)
+            .name(c"main");
+        let pipeline_info = vk::ComputePipelineCreateInfo::default().stage(stage).layout(pipeline_layout);
+        let pipeline = unsafe {
+            device
+                .create_compute_pipelines(pipeline_cache, &[pipeline_info], None)
+                .map_err(|(_, err)| err)?[0]
+        };
+
+        Ok(Self {
+            buffer,
+            memory,
+            count,
+            emitter,
+            set_layout,
+            descriptor_pool,
+            descriptor_set,
+            pipeline_layout,
+            pipeline,
+            shader_module,
+            constants: SimulationConstants {
+                emitter: emitter.position,
+                gravity: emitter.gravity,
+                dt: 0.0,
+                time: 0.0,
+                lifetime: emitter.lifetime,
+                speed: emitter.speed,
+                count,
+            },
+        })
+    }
+
+    //шаг симуляции для следующего record, dt в секундах с прошлого кадра
+    pub fn update(&mut self, dt: f32, time: f32) {
+        self.constants = SimulationConstants {
+            emitter: self.emitter.position,
+            gravity: self.emitter.gravity,
+            dt: dt.min(0.1), //после паузы (свернутое окно) частицы не улетают за один шаг
+            time,
+            lifetime: self.emitter.lifetime,
+            speed: self.emitter.speed,
+            count: self.count,
+        };
+    }
+
+    /*записывает шаг симуляции, вызывается вне render pass до прохода в котором рисуются частицы,
+    буффер один на все кадры в полете, поэтому барьеры с обеих сторон dispatch*/
+    pub fn record(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &self.constants as *const SimulationConstants as *const u8,
+                size_of::<SimulationConstants>(),
+            )
+        };
+        unsafe {
+            //прошлый кадр мог еще читать вершины из буффера, и его запись из compute должна быть видна этому шагу
+            self.buffer_barrier(
+                device,
+                command_buffer,
+                (vk::AccessFlags::SHADER_WRITE, vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::VERTEX_INPUT),
+                (vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE, vk::PipelineStageFlags::COMPUTE_SHADER),
+            );
+            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::COMPUTE, self.pipeline);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipeline_layout,
+                0,
+                &[self.descriptor_set],
+                &[],
+            );
+            device.cmd_push_constants(command_buffer, self.pipeline_layout, vk::ShaderStageFlags::COMPUTE, 0, bytes);
+            device.cmd_dispatch(command_buffer, self.count.div_ceil(WORKGROUP_SIZE), 1, 1);
+            //новые позиции должны быть видны при чтении вершинных атрибутов
+            self.buffer_barrier(
+                device,
+                command_buffer,
+                (vk::AccessFlags::SHADER_WRITE, vk::PipelineStageFlags::COMPUTE_SHADER),
+                (vk::AccessFlags::VERTEX_ATTRIBUTE_READ, vk::PipelineStageFlags::VERTEX_INPUT),
+            );
+        }
+    }
+
+    unsafe fn buffer_barrier(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        (src_access_mask, src_stage_mask): (vk::AccessFlags, vk::PipelineStageFlags),
+        (dst_access_mask, dst_stage_mask): (vk::AccessFlags, vk::PipelineStageFlags),
+    ) {
+        let barrier = vk::BufferMemoryBarrier::default()
+            .src_access_mask(src_access_mask)
+            .dst_access_mask(dst_access_mask)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.buffer)
+            .size(vk::WHOLE_SIZE);
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                src_stage_mask,
+                dst_stage_mask,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[barrier],
+                &[],
+            );
+        }
+    }
+
+    //рисует все частицы одним instanced draw call, вызывается внутри прохода под который создан pipeline
+    pub fn draw(&self, device: &Device, command_buffer: vk::CommandBuffer, pipeline: &ParticlePipeline, extent: vk::Extent2D, size: f32) {
+        let constants = [extent.width as f32, extent.height as f32, size];
+        let bytes = unsafe { std::slice::from_raw_parts(constants.as_ptr() as *const u8, size_of_val(&constants)) };
+        unsafe {
+            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline.pipeline);
+            let rect = vk::Rect2D::from(extent);
+            device.cmd_set_viewport(command_buffer, 0, &[viewport(rect)]);
+            device.cmd_set_scissor(command_buffer, 0, &[rect]);
+            device.cmd_push_constants(command_buffer, pipeline.pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, bytes);
+            device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.buffer], &[0]);
+            device.cmd_draw(command_buffer, 6, self.count, 0, 0); //6 вершин квадрата на экземпляр
+        }
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            device.destroy_pipeline(self.pipeline, None);
+            device.destroy_pipeline_layout(self.pipeline_layout, None);
+            device.destroy_shader_module(self.shader_module, None);
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+            device.destroy_descriptor_set_layout(self.set_layout, None);
+            device.destroy_buffer(self.buffer, None);
+            device.free_memory(self.memory, None);
+        }
+    }
+}
+
+/*graphics pipeline для частиц под один проход, как и PipelineSet переживает изменение размера окна,
+вершинный буффер это буффер ParticleSystem с шагом по экземплярам, смешивание аддитивное*/
+pub struct ParticlePipeline {
+    pub pipeline: vk::Pipeline,
+    pub pipeline_layout: vk::PipelineLayout,
+    shader_modules: Vec<vk::ShaderModule>,
+}
+
+impl ParticlePipeline {
+    //render_pass null значит динамический рендер, тогда формат вложения берется из format
+    pub fn new(
+        device: &Device,
+        pipeline_cache: vk::PipelineCache,
+        render_pass: vk::RenderPass,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+    ) -> VkResult<Self> {
+        //push constant`ы (resolution и size в particle.vert) и атрибуты экземпляра берутся из шейдеров
+        let interface = embedded_interface(&[PARTICLE_VERT, PARTICLE_FRAG])?;
+        let pipeline_layout = interface.create_pipeline_layout(device, &[])?;
+        let attribute_descriptions = embedded_reflection(PARTICLE_VERT)?
+            .vertex_attributes(&Particle::attributes())
+            .map_err(embedded_error)?;
+
+        let vert_module = shader_module(device, PARTICLE_VERT)?;
+        let frag_module = shader_module(device, PARTICLE_FRAG)?;
+        let shader_stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert_module)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag_module)
+                .name(c"main"),
+        ];
+
+        let binding_descriptions = [vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: size_of::<Particle>() as u32,
+            input_rate: vk::VertexInputRate::INSTANCE, //атрибуты меняются раз на частицу, а не на вершину
+        }];
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&binding_descriptions)
+            .vertex_attribute_descriptions(&attribute_descriptions);
+        let input_assembly_state =
+            vk::PipelineInputAssemblyStateCreateInfo::default().topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+        //viewport и scissor задает draw, поэтому pipeline не зависит от размера окна
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state = vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(vk::CullModeFlags::NONE)
+            .line_width(1.0);
+        let multisample_state = vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(samples);
+        let color_blend_attachments = [BlendMode::Additive.attachment_state()]; //перекрывающиеся частицы светятся ярче
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default().attachments(&color_blend_attachments);
+
+        let color_formats = [format];
+        let mut rendering_info = vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let mut pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&shader_stages)
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .color_blend_state(&color_blend_state)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .render_pass(render_pass)
+            .subpass(0);
+        if render_pass == vk::RenderPass::null() {
+            pipeline_info = pipeline_info.push_next(&mut rendering_info);
+        }
+        let pipeline = unsafe {
+            device
+                .create_graphics_pipelines(pipeline_cache, &[pipeline_info], None)
+                .map_err(|(_, err)| err)?[0]
+        };
+
+        Ok(Self {
+            pipeline,
+            pipeline_layout,
+            shader_modules: vec![vert_module, frag_module],
+        })
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            device.destroy_pipeline(self.pipeline, None);
+            device.destroy_pipeline_layout(self.pipeline_layout, None);
+            for &shader_module in &self.shader_modules {
+                device.destroy_shader_module(shader_module, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particle_matches_std430_layout() {
+        //vec2, vec2, vec4, vec2 и выравнивание массива структур до 16 байт
+        assert_eq!(size_of::<Particle>(), 48);
+        assert_eq!(std::mem::offset_of!(Particle, color), 16);
+        assert_eq!(std::mem::offset_of!(Particle, life), 32);
+        assert_eq!(size_of::<SimulationConstants>(), 36);
+    }
+
+    #[test]
+    fn shaders_match_particle_structs() {
+        let simulation = embedded_interface(&[SIMULATION_SHADER]).unwrap();
+        assert_eq!(simulation.push_constant_range.map(|range| range.size), Some(size_of::<SimulationConstants>() as u32));
+        let draw = embedded_interface(&[PARTICLE_VERT, PARTICLE_FRAG]).unwrap();
+        assert_eq!(draw.push_constant_range.map(|range| range.size), Some(3 * size_of::<f32>() as u32));
+        assert_eq!(embedded_reflection(PARTICLE_VERT).unwrap().vertex_attributes(&Particle::attributes()).unwrap().len(), 2);
+    }
+}
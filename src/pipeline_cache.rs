@@ -0,0 +1,118 @@
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::path::{Path, PathBuf};
+
+const CACHE_FILE: &str = "pipeline_cache.bin";
+const HEADER_SIZE: usize = 32; //длина заголовка vk::PipelineCacheHeaderVersionOne
+
+/*pipeline cache: драйвер кладет в него скомпилированные шейдеры и при следующем create_*_pipelines с тем же состоянием
+берет их оттуда. Содержимое сохраняется в файл при выходе и читается при запуске, поэтому pipeline`ы AppearanceBase,
+PostProcessBase и остальных не компилируются заново при каждом старте.
+Данные годятся только для того же драйвера и устройства, это проверяется по заголовку до передачи в Vulkan*/
+pub struct PipelineCacheBase {
+    pub cache: vk::PipelineCache, //передается во все конструкторы pipeline`ов
+    path: PathBuf,
+} //vulkan pipeline cache resources
+
+impl PipelineCacheBase {
+    /*читает dir/pipeline_cache.bin если он есть и подходит устройству, иначе начинает с пустого кеша,
+    испорченный или чужой файл не ошибка, он просто будет перезаписан в save*/
+    pub fn load(device: &Device, properties: &vk::PhysicalDeviceProperties, dir: &Path) -> VkResult<Self> {
+        let path = dir.join(CACHE_FILE);
+        let initial_data = match std::fs::read(&path) {
+            Ok(data) if header_matches(&data, properties) => data,
+            Ok(_) => {
+                log::warn!("Pipeline cache {} belongs to another device or driver, ignoring it", path.display());
+                Vec::new()
+            }
+            Err(_) => Vec::new(), //первый запуск
+        };
+        log::info!("Pipeline cache: {} ({} bytes loaded)", path.display(), initial_data.len());
+
+        let create_info = vk::PipelineCacheCreateInfo::default().initial_data(&initial_data);
+        let cache = unsafe { device.create_pipeline_cache(&create_info, None)? };
+        Ok(Self { cache, path })
+    }
+
+    /*пишет содержимое кеша во временный файл и переименовывает его, чтобы прерванная запись не оставила обрезанный кеш*/
+    pub fn save(&self, device: &Device) -> std::io::Result<()> {
+        let data = unsafe { device.get_pipeline_cache_data(self.cache) }.map_err(std::io::Error::other)?;
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp_path = self.path.with_extension("tmp");
+        std::fs::write(&temp_path, &data)?;
+        std::fs::rename(&temp_path, &self.path)
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe { device.destroy_pipeline_cache(self.cache, None) };
+    }
+}
+
+/*каталог кеша: PIPELINE_CACHE_DIR, иначе $XDG_CACHE_HOME или ~/.cache, в крайнем случае временный каталог системы*/
+pub fn cache_dir() -> PathBuf {
+    let base = std::env::var_os("PIPELINE_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("XDG_CACHE_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+                .map(|cache| cache.join("vulkan_2d_triangle"))
+        });
+    base.unwrap_or_else(|| std::env::temp_dir().join("vulkan_2d_triangle"))
+}
+
+/*заголовок версии ONE: длина заголовка, версия, vendorID, deviceID и pipelineCacheUUID, все числа little endian.
+Драйвер сам проверяет то же самое, но некоторые падают на чужих данных, поэтому проверяем до create_pipeline_cache*/
+fn header_matches(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+    if data.len() < HEADER_SIZE {
+        return false;
+    }
+    let word = |index: usize| u32::from_le_bytes(data[index * 4..index * 4 + 4].try_into().unwrap());
+    word(0) as usize >= HEADER_SIZE
+        && word(1) == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+        && word(2) == properties.vendor_id
+        && word(3) == properties.device_id
+        && data[16..32] == properties.pipeline_cache_uuid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(properties: &vk::PhysicalDeviceProperties) -> Vec<u8> {
+        let mut data = Vec::new();
+        for word in [HEADER_SIZE as u32, 1, properties.vendor_id, properties.device_id] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        data.extend_from_slice(&properties.pipeline_cache_uuid);
+        data.extend_from_slice(&[0xAB; 8]); //данные драйвера после заголовка
+        data
+    }
+
+    #[test]
+    fn header_must_match_device() {
+        let properties = vk::PhysicalDeviceProperties {
+            vendor_id: 0x10DE,
+            device_id: 0x2484,
+            pipeline_cache_uuid: [7; vk::UUID_SIZE],
+            ..Default::default()
+        };
+        let data = header(&properties);
+        assert!(header_matches(&data, &properties));
+        assert!(!header_matches(&data[..16], &properties));
+
+        let other_driver = vk::PhysicalDeviceProperties {
+            pipeline_cache_uuid: [8; vk::UUID_SIZE],
+            ..properties
+        };
+        assert!(!header_matches(&data, &other_driver));
+        let other_device = vk::PhysicalDeviceProperties {
+            device_id: 0x2485,
+            ..properties
+        };
+        assert!(!header_matches(&data, &other_device));
+    }
+}
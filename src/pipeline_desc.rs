@@ -0,0 +1,565 @@
+use crate::PassInfo;
+use crate::blend::BlendMode;
+use crate::reflect::{PipelineInterface, ShaderError, ShaderReflection};
+use crate::vertex::Vertex;
+use ash::Device;
+use ash::util::read_spv;
+use ash::vk;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum PipelineError {
+    Shader(ShaderError),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnknownShader(String), //нет ни файла в каталоге шейдеров, ни встроенного шейдера с таким именем
+    Samples { requested: u32, pass: u32 }, //PipelineDesc::samples не совпадает с числом сэмплов прохода
+    MissingFeature(&'static str), //описание требует возможность, которой у устройства нет
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::Shader(err) => write!(f, "pipeline shader error: {}", err),
+            PipelineError::Io(err) => write!(f, "pipeline config io error: {}", err),
+            PipelineError::Json(err) => write!(f, "pipeline config error: {}", err),
+            PipelineError::UnknownShader(name) => write!(f, "unknown shader '{}'", name),
+            PipelineError::Samples { requested, pass } => {
+                write!(f, "pipeline wants {} samples, but the pass has {}", requested, pass)
+            }
+            PipelineError::MissingFeature(feature) => write!(f, "pipeline needs the device feature {}", feature),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+impl From<ShaderError> for PipelineError {
+    fn from(err: ShaderError) -> Self {
+        PipelineError::Shader(err)
+    }
+}
+
+impl From<vk::Result> for PipelineError {
+    fn from(err: vk::Result) -> Self {
+        PipelineError::Shader(ShaderError::Vulkan(err))
+    }
+}
+
+impl From<std::io::Error> for PipelineError {
+    fn from(err: std::io::Error) -> Self {
+        PipelineError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for PipelineError {
+    fn from(err: serde_json::Error) -> Self {
+        PipelineError::Json(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    #[default]
+    TriangleList,
+    TriangleStrip,
+    LineList,
+    LineStrip,
+    PointList,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    Line, //нужна возможность устройства fillModeNonSolid
+    Point,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CullMode {
+    #[default]
+    None, //в 2D рисуем обе стороны, порядок обхода вершин не важен
+    Front,
+    Back,
+}
+
+//состояния которые задаются командами в command buffer вместо того чтобы запекаться в pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DynamicState {
+    Viewport,
+    Scissor,
+    LineWidth,
+}
+
+impl Topology {
+    fn vk(self) -> vk::PrimitiveTopology {
+        match self {
+            Topology::TriangleList => vk::PrimitiveTopology::TRIANGLE_LIST,
+            Topology::TriangleStrip => vk::PrimitiveTopology::TRIANGLE_STRIP,
+            Topology::LineList => vk::PrimitiveTopology::LINE_LIST,
+            Topology::LineStrip => vk::PrimitiveTopology::LINE_STRIP,
+            Topology::PointList => vk::PrimitiveTopology::POINT_LIST,
+        }
+    }
+}
+
+impl PolygonMode {
+    fn vk(self) -> vk::PolygonMode {
+        match self {
+            PolygonMode::Fill => vk::PolygonMode::FILL,
+            PolygonMode::Line => vk::PolygonMode::LINE,
+            PolygonMode::Point => vk::PolygonMode::POINT,
+        }
+    }
+}
+
+impl CullMode {
+    fn vk(self) -> vk::CullModeFlags {
+        match self {
+            CullMode::None => vk::CullModeFlags::NONE,
+            CullMode::Front => vk::CullModeFlags::FRONT,
+            CullMode::Back => vk::CullModeFlags::BACK,
+        }
+    }
+}
+
+impl DynamicState {
+    fn vk(self) -> vk::DynamicState {
+        match self {
+            DynamicState::Viewport => vk::DynamicState::VIEWPORT,
+            DynamicState::Scissor => vk::DynamicState::SCISSOR,
+            DynamicState::LineWidth => vk::DynamicState::LINE_WIDTH,
+        }
+    }
+}
+
+/*полное описание graphics pipeline для вершин Vertex, кроме того что задает проход (render pass, формат, размер).
+Одинаковые описания дают одинаковый хеш и в PipelineRegistry превращаются в один и тот же vk::Pipeline.
+Шейдеры задаются именами вроде "sprite.frag": файл <каталог шейдеров>/sprite.frag.spv или встроенный в программу шейдер*/
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipelineDesc {
+    pub vertex_shader: String,
+    pub fragment_shader: String,
+    pub topology: Topology,
+    pub polygon_mode: PolygonMode,
+    pub cull_mode: CullMode,
+    pub blend: BlendMode,
+    pub samples: Option<u32>, //None берет число сэмплов прохода, иначе должно с ним совпадать
+    pub dynamic_states: BTreeSet<DynamicState>, //множество, порядок и повторы в конфигурации не дают разных pipeline`ов
+}
+
+impl Default for PipelineDesc {
+    fn default() -> Self {
+        Self {
+            vertex_shader: "triangle.vert".to_string(),
+            fragment_shader: "triangle.frag".to_string(),
+            topology: Topology::default(),
+            polygon_mode: PolygonMode::default(),
+            cull_mode: CullMode::default(),
+            blend: BlendMode::default(),
+            samples: None,
+            dynamic_states: BTreeSet::from([DynamicState::Viewport, DynamicState::Scissor]), //Batcher задает их для каждого вида
+        }
+    }
+}
+
+impl PipelineDesc {
+    pub fn new(vertex_shader: &str, fragment_shader: &str) -> Self {
+        Self {
+            vertex_shader: vertex_shader.to_string(),
+            fragment_shader: fragment_shader.to_string(),
+            ..Default::default()
+        }
+    }
+
+    //pipeline не зависит от размера прохода, его можно не пересоздавать при изменении размера окна
+    pub fn extent_independent(&self) -> bool {
+        self.dynamic_states.contains(&DynamicState::Viewport) && self.dynamic_states.contains(&DynamicState::Scissor)
+    }
+
+    //тот же pipeline с другим смешиванием, Batcher держит по варианту на каждый BlendMode
+    pub fn with_blend(&self, blend: BlendMode) -> Self {
+        Self { blend, ..self.clone() }
+    }
+
+    /*проверка описания против прохода с pass_samples сэмплами и возможностей устройства до создания pipeline,
+    возвращает число сэмплов pipeline`а*/
+    pub fn validate(&self, pass_samples: vk::SampleCountFlags, fill_mode_non_solid: bool) -> Result<vk::SampleCountFlags, PipelineError> {
+        match self.samples {
+            Some(requested) if requested != pass_samples.as_raw() => {
+                return Err(PipelineError::Samples {
+                    requested,
+                    pass: pass_samples.as_raw(),
+                });
+            }
+            _ => {}
+        }
+        if self.polygon_mode != PolygonMode::Fill && !fill_mode_non_solid {
+            return Err(PipelineError::MissingFeature("fillModeNonSolid"));
+        }
+        Ok(pass_samples)
+    }
+}
+
+/*описания pipeline`ов Batcher`а из конфигурационного файла JSON, отсутствующие поля берутся из встроенных значений*/
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipelineConfig {
+    pub shapes: PipelineDesc,
+    pub sprites: PipelineDesc,
+    pub sdf_text: PipelineDesc,
+    pub wireframe: PipelineDesc, //отладочный каркас для всех фигур, создается только при fillModeNonSolid
+    #[serde(skip)]
+    pub shader_dir: Option<PathBuf>, //каталог файла конфигурации, в нем ищутся файлы шейдеров
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            shapes: PipelineDesc::new("triangle.vert", "triangle.frag"),
+            sprites: PipelineDesc::new("sprite.vert", "sprite.frag"),
+            sdf_text: PipelineDesc::new("sprite.vert", "text_sdf.frag"),
+            wireframe: PipelineDesc {
+                polygon_mode: PolygonMode::Line,
+                ..PipelineDesc::new("triangle.vert", "triangle.frag")
+            },
+            shader_dir: None,
+        }
+    }
+}
+
+impl PipelineConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PipelineError> {
+        let path = path.as_ref();
+        let mut config = Self::from_json(&std::fs::read_to_string(path)?)?;
+        config.shader_dir = path.parent().map(Path::to_path_buf);
+        Ok(config)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PipelineError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+//шейдеры проекта доступны и без каталога shader рядом с программой
+fn embedded_shader(name: &str) -> Option<&'static [u8]> {
+    Some(match name {
+        "triangle.vert" => include_bytes!("../shader/triangle.vert.spv"),
+        "triangle.frag" => include_bytes!("../shader/triangle.frag.spv"),
+        "sprite.vert" => include_bytes!("../shader/sprite.vert.spv"),
+        "sprite.frag" => include_bytes!("../shader/sprite.frag.spv"),
+        "text_sdf.frag" => include_bytes!("../shader/text_sdf.frag.spv"),
+        _ => return None,
+    })
+}
+
+struct LoadedShader {
+    module: vk::ShaderModule,
+    reflection: ShaderReflection,
+}
+
+struct LoadedLayout {
+    pipeline_layout: vk::PipelineLayout,
+    set_layouts: Vec<vk::DescriptorSetLayout>, //из reflection, совместимы с TextureDescriptors::layout
+}
+
+//pipeline из реестра, принадлежит реестру и уничтожается вместе с ним
+#[derive(Clone, Copy, Debug)]
+pub struct RegisteredPipeline {
+    pub pipeline: vk::Pipeline,
+    pub pipeline_layout: vk::PipelineLayout,
+}
+
+/*pipeline`ы под один проход (render pass или формат динамического рендера), ключ это PipelineDesc:
+повторный запрос того же описания возвращает уже созданный pipeline, шейдерные модули и раскладки
+общие для всех pipeline`ов с теми же шейдерами. При пересоздании прохода см. retarget*/
+pub struct PipelineRegistry {
+    pipeline_cache: vk::PipelineCache,
+    pass: PassInfo, //render pass или формат динамического рендера, сэмплы и RenderPassDesc
+    extent: vk::Extent2D,
+    fill_mode_non_solid: bool, //можно ли PolygonMode::Line и Point
+    shader_dir: Option<PathBuf>,
+    shaders: HashMap<String, LoadedShader>,
+    layouts: HashMap<(String, String), LoadedLayout>,
+    pipelines: HashMap<PipelineDesc, RegisteredPipeline>,
+} //vulkan pipeline resources
+
+impl PipelineRegistry {
+    pub fn new(
+        pipeline_cache: vk::PipelineCache,
+        pass: PassInfo,
+        extent: vk::Extent2D,
+        fill_mode_non_solid: bool,
+        shader_dir: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            pipeline_cache,
+            pass,
+            extent,
+            fill_mode_non_solid,
+            shader_dir,
+            shaders: HashMap::new(),
+            layouts: HashMap::new(),
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /*pipeline`ы годятся для другого прохода, если проходы совместимы по PassInfo::compatible
+    (тот же способ рендера, формат, сэмплы и load/store/subpass`ы RenderPassDesc) и ни один pipeline не запек размер прохода*/
+    pub fn compatible(&self, pass: &PassInfo) -> bool {
+        self.pass.compatible(pass) && self.pipelines.keys().all(PipelineDesc::extent_independent)
+    }
+
+    /*переход на пересозданный проход (render pass из которого pipeline создан можно уничтожить),
+    false если pipeline`ы для него не годятся и реестр нужно пересоздать*/
+    pub fn retarget(&mut self, pass: PassInfo, extent: vk::Extent2D) -> bool {
+        let compatible = self.compatible(&pass);
+        if compatible {
+            self.pass = pass;
+            self.extent = extent;
+        }
+        compatible
+    }
+
+    //число разных pipeline`ов, меньше числа запросов если описания повторялись
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    pub fn get(&mut self, device: &Device, desc: &PipelineDesc) -> Result<RegisteredPipeline, PipelineError> {
+        if let Some(&registered) = self.pipelines.get(desc) {
+            return Ok(registered);
+        }
+        let registered = self.create(device, desc)?;
+        self.pipelines.insert(desc.clone(), registered);
+        Ok(registered)
+    }
+
+    fn load_shader(&mut self, device: &Device, name: &str) -> Result<(), PipelineError> {
+        if self.shaders.contains_key(name) {
+            return Ok(());
+        }
+        let file = self.shader_dir.as_ref().map(|dir| dir.join(format!("{}.spv", name))).filter(|path| path.exists());
+        let code = match file {
+            Some(path) => std::fs::read(path)?,
+            None => embedded_shader(name).ok_or_else(|| PipelineError::UnknownShader(name.to_string()))?.to_vec(),
+        };
+        //read_spv проверяет magic number и копирует байты в выровненный по u32 вектор
+        let words = read_spv(&mut Cursor::new(&code))?;
+        let reflection = ShaderReflection::parse(&words)?;
+        let module = unsafe { device.create_shader_module(&vk::ShaderModuleCreateInfo::default().code(&words), None)? };
+        self.shaders.insert(name.to_string(), LoadedShader { module, reflection });
+        Ok(())
+    }
+
+    fn create(&mut self, device: &Device, desc: &PipelineDesc) -> Result<RegisteredPipeline, PipelineError> {
+        let samples = desc.validate(self.pass.samples, self.fill_mode_non_solid)?;
+        self.load_shader(device, &desc.vertex_shader)?;
+        self.load_shader(device, &desc.fragment_shader)?;
+        let vertex = &self.shaders[&desc.vertex_shader];
+        let fragment = &self.shaders[&desc.fragment_shader];
+
+        /*атрибуты вершин, раскладки дескрипторов и push constant`ы берутся из самих шейдеров,
+        несовпадение выходов вершинного шейдера со входами фрагментного это ошибка до создания pipeline*/
+        let layout_key = (desc.vertex_shader.clone(), desc.fragment_shader.clone());
+        if !self.layouts.contains_key(&layout_key) {
+            let interface = PipelineInterface::new(&[&vertex.reflection, &fragment.reflection])?;
+            let set_layouts = interface.create_set_layouts(device)?;
+            let pipeline_layout = interface.create_pipeline_layout(device, &set_layouts)?;
+            self.layouts.insert(layout_key.clone(), LoadedLayout { pipeline_layout, set_layouts });
+        }
+        let pipeline_layout = self.layouts[&layout_key].pipeline_layout;
+
+        let shader_stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vertex.module)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(fragment.module)
+                .name(c"main"),
+        ];
+
+        let binding_descriptions = Vertex::binding_descriptions();
+        let attribute_descriptions = vertex.reflection.vertex_attributes(&Vertex::attributes())?;
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::default() //как читать вершинный буффер, выведено из layout(location) вершинного шейдера
+            .vertex_binding_descriptions(&binding_descriptions)
+            .vertex_attribute_descriptions(&attribute_descriptions);
+        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::default().topology(desc.topology.vk());
+
+        //при динамических viewport/scissor эти значения игнорируются, но их число все равно задается здесь
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: self.extent.width as f32,
+            height: self.extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+        let scissors = [self.extent.into()]; //пиксели вне scissor отбрасываются
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewports(&viewports)
+            .scissors(&scissors);
+
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(desc.polygon_mode.vk())
+            .cull_mode(desc.cull_mode.vk())
+            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+            .line_width(1.0);
+        let multisample_state = vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(samples);
+        let color_blend_attachments = [desc.blend.attachment_state()];
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default().attachments(&color_blend_attachments);
+        let dynamic_states: Vec<vk::DynamicState> = desc.dynamic_states.iter().map(|state| state.vk()).collect();
+        let dynamic_state = vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let color_formats = [self.pass.format];
+        let mut rendering_info = vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let mut pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&shader_stages)
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .color_blend_state(&color_blend_state)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .render_pass(self.pass.render_pass)
+            .subpass(0);
+        if self.pass.dynamic_rendering() {
+            pipeline_info = pipeline_info.push_next(&mut rendering_info); //форматы вложений вместо render pass
+        }
+        let pipeline = unsafe {
+            device
+                .create_graphics_pipelines(self.pipeline_cache, &[pipeline_info], None)
+                .map_err(|(_, err)| err)?[0]
+        };
+
+        Ok(RegisteredPipeline { pipeline, pipeline_layout })
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            for registered in self.pipelines.values() {
+                device.destroy_pipeline(registered.pipeline, None);
+            }
+            for layout in self.layouts.values() {
+                device.destroy_pipeline_layout(layout.pipeline_layout, None);
+                for &set_layout in &layout.set_layouts {
+                    device.destroy_descriptor_set_layout(set_layout, None);
+                }
+            }
+            for shader in self.shaders.values() {
+                device.destroy_shader_module(shader.module, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let config = PipelineConfig::from_json(
+            r#"{ "shapes": { "polygon_mode": "line", "dynamic_states": ["viewport", "scissor"] } }"#,
+        )
+        .unwrap();
+        assert_eq!(config.shapes.vertex_shader, "triangle.vert");
+        assert_eq!(config.shapes.polygon_mode, PolygonMode::Line);
+        assert_eq!(config.shapes.dynamic_states, BTreeSet::from([DynamicState::Viewport, DynamicState::Scissor]));
+        assert_eq!(config.sprites, PipelineConfig::default().sprites);
+        assert_eq!(PipelineConfig::from_json(&serde_json::to_string(&config).unwrap()).unwrap(), config);
+    }
+
+    #[test]
+    fn identical_descriptions_share_a_key() {
+        let sprites = PipelineDesc::new("sprite.vert", "sprite.frag");
+        let mut keys = HashSet::new();
+        for mode in BlendMode::ALL {
+            keys.insert(sprites.with_blend(mode));
+            keys.insert(sprites.with_blend(mode)); //повтор не добавляет новый pipeline
+        }
+        assert_eq!(keys.len(), BlendMode::COUNT);
+        assert!(keys.contains(&PipelineConfig::default().sprites));
+
+        //порядок и повторы динамических состояний в конфигурации не меняют ключ
+        let config = PipelineConfig::from_json(
+            r#"{ "sprites": { "vertex_shader": "sprite.vert", "fragment_shader": "sprite.frag",
+                "dynamic_states": ["scissor", "viewport", "scissor"] } }"#,
+        )
+        .unwrap();
+        assert!(keys.contains(&config.sprites));
+    }
+
+    #[test]
+    fn validates_samples_and_features() {
+        let four = vk::SampleCountFlags::TYPE_4;
+        let desc = PipelineDesc::default();
+        assert_eq!(desc.validate(four, false).unwrap(), four);
+        let matching = PipelineDesc { samples: Some(4), ..desc.clone() };
+        assert_eq!(matching.validate(four, false).unwrap(), four);
+        for requested in [3, 8] {
+            let wrong = PipelineDesc { samples: Some(requested), ..desc.clone() };
+            assert!(matches!(wrong.validate(four, true), Err(PipelineError::Samples { pass: 4, .. })));
+        }
+
+        let wireframe = PipelineConfig::default().wireframe;
+        assert!(wireframe.validate(four, true).is_ok());
+        assert!(matches!(wireframe.validate(four, false), Err(PipelineError::MissingFeature("fillModeNonSolid"))));
+    }
+
+    #[test]
+    fn registry_rejects_passes_with_other_structure() {
+        use crate::RenderPassDesc;
+        use crate::post::PostEffects;
+        use ash::vk::Handle;
+
+        let pass = PassInfo {
+            render_pass: vk::RenderPass::from_raw(1),
+            format: vk::Format::B8G8R8A8_SRGB,
+            samples: vk::SampleCountFlags::TYPE_4,
+            desc: RenderPassDesc::default(),
+        };
+        let extent = vk::Extent2D { width: 640, height: 480 };
+        let mut registry = PipelineRegistry::new(vk::PipelineCache::null(), pass, extent, false, None);
+
+        //пересозданный проход с другим цветом очистки и размером подходит
+        let recreated = PassInfo {
+            render_pass: vk::RenderPass::from_raw(2),
+            desc: RenderPassDesc { clear_color: [1.0; 4], ..pass.desc },
+            ..pass
+        };
+        assert!(registry.retarget(recreated, vk::Extent2D { width: 800, height: 600 }));
+
+        let others = [
+            PassInfo { render_pass: vk::RenderPass::null(), ..pass },
+            PassInfo { format: vk::Format::R8G8B8A8_UNORM, ..pass },
+            PassInfo { samples: vk::SampleCountFlags::TYPE_1, ..pass },
+            PassInfo { desc: RenderPassDesc::overlay(), ..pass },
+            PassInfo { desc: RenderPassDesc { store_op: vk::AttachmentStoreOp::DONT_CARE, ..pass.desc }, ..pass },
+            PassInfo { desc: RenderPassDesc { post_process: Some(PostEffects::default()), ..pass.desc }, ..pass },
+        ];
+        for other in others {
+            assert!(!registry.compatible(&other), "{:?}", other);
+            assert!(!registry.retarget(other, extent));
+        }
+    }
+}
@@ -0,0 +1,595 @@
+use crate::reflect::{PipelineInterface, ShaderError, ShaderReflection};
+use crate::target::RenderTarget;
+use crate::texture::{TextureDescriptors, TextureOptions};
+use crate::{AppBase, RenderPassDesc};
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::util::read_spv;
+use ash::vk;
+use std::io::Cursor;
+
+/*параметры эффектов второго subpass, передаются через push constant каждый кадр,
+поэтому их можно менять во время работы без пересоздания pipeline*/
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PostEffects {
+    pub tint: [f32; 4],       //цвет умножается на tint.rgb, tint.a не используется
+    pub vignette: f32,        //сила затемнения к краям, 0 выключает
+    pub vignette_radius: f32, //расстояние от центра (в долях половины диагонали) где начинается затемнение
+    pub grayscale: f32,       //0 исходный цвет, 1 полностью черно-белый
+    pub brightness: f32,      //прибавляется к цвету
+    pub contrast: f32,        //растяжение относительно 0.5, 1 без изменений
+    pub saturation: f32,      //1 без изменений, 0 тоже что grayscale
+}
+
+impl Default for PostEffects {
+    fn default() -> Self {
+        Self {
+            tint: [1.0, 1.0, 1.0, 1.0],
+            vignette: 0.0,
+            vignette_radius: 0.75,
+            grayscale: 0.0,
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+//раскладка совпадает с layout(push_constant) в post.frag
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PostConstants {
+    effects: PostEffects,
+    resolution: [f32; 2],
+}
+
+//вершинный шейдер всех полноэкранных проходов
+const FULLSCREEN_VERT: &[u8] = include_bytes!("../shader/fullscreen.vert.spv");
+
+/*второй subpass render pass`а: полноэкранный треугольник читает результат первого subpass как input attachment
+(только пиксель под фрагментом, без выборки соседей) и записывает в изображение swapchain.
+При динамическом рендере (render_pass null) subpass`ов нет, сцена читается тем же пикселем через sampler2D*/
+pub struct PostProcessBase {
+    pub pipeline: vk::Pipeline,
+    pub pipeline_layout: vk::PipelineLayout,
+    pub set_layout: vk::DescriptorSetLayout,
+    pub descriptor_pool: vk::DescriptorPool,
+    pub descriptor_sets: Vec<vk::DescriptorSet>, //по набору на каждый кадр swapchain, у каждого свое промежуточное изображение
+    sampler: vk::Sampler, //null для input attachment
+    shader_modules: Vec<vk::ShaderModule>,
+}
+
+impl PostProcessBase {
+    pub fn new(
+        device: &Device,
+        pipeline_cache: vk::PipelineCache,
+        render_pass: vk::RenderPass,
+        subpass: u32,
+        format: vk::Format,
+        extent: vk::Extent2D,
+        input_views: &[vk::ImageView],
+    ) -> VkResult<Self> {
+        let dynamic = render_pass == vk::RenderPass::null();
+        let frag_code: &[u8] = if dynamic {
+            include_bytes!("../shader/post_sampled.frag.spv")
+        } else {
+            include_bytes!("../shader/post.frag.spv")
+        };
+        //input attachment или sampler2D, раскладка и push constant`ы берутся из шейдера
+        let interface = embedded_interface(&[FULLSCREEN_VERT, frag_code])?;
+        let descriptor_type = match interface.bindings.first() {
+            Some((binding, _)) => binding.descriptor_type,
+            None => return Err(embedded_error(ShaderError::Interface("post shader reads no scene image".to_string()))),
+        };
+        let sampler = if dynamic {
+            //texelFetch не фильтрует, но combined image sampler без sampler`а не бывает
+            let sampler_info = vk::SamplerCreateInfo::default()
+                .mag_filter(vk::Filter::NEAREST)
+                .min_filter(vk::Filter::NEAREST)
+                .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE);
+            unsafe { device.create_sampler(&sampler_info, None)? }
+        } else {
+            vk::Sampler::null()
+        };
+
+        let set_layouts = interface.create_set_layouts(device).map_err(embedded_error)?;
+        let set_layout = set_layouts[0];
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: descriptor_type,
+            descriptor_count: input_views.len() as u32,
+        }];
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::default()
+                    .max_sets(input_views.len() as u32)
+                    .pool_sizes(&pool_sizes),
+                None,
+            )?
+        };
+        let set_layouts = vec![set_layout; input_views.len()];
+        let descriptor_sets = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::default()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&set_layouts),
+            )?
+        };
+        for (&set, &view) in descriptor_sets.iter().zip(input_views) {
+            let image_info = [vk::DescriptorImageInfo::default()
+                .sampler(sampler) //input attachment читается без sampler
+                .image_view(view)
+                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)];
+            let write = vk::WriteDescriptorSet::default()
+                .dst_set(set)
+                .dst_binding(0)
+                .descriptor_type(descriptor_type)
+                .image_info(&image_info);
+            unsafe { device.update_descriptor_sets(&[write], &[]) };
+        }
+
+        let pipeline_layout = interface.create_pipeline_layout(device, &set_layouts)?;
+
+        let vert_module = shader_module(device, FULLSCREEN_VERT)?;
+        let frag_module = shader_module(device, frag_code)?;
+        let pipeline = fullscreen_pipeline(
+            device,
+            pipeline_cache,
+            pipeline_layout,
+            render_pass,
+            subpass,
+            format,
+            extent,
+            vk::SampleCountFlags::TYPE_1, //MSAA уже сведен resolve`ом в первом subpass
+            vert_module,
+            frag_module,
+        )?;
+
+        Ok(Self {
+            pipeline,
+            pipeline_layout,
+            set_layout,
+            descriptor_pool,
+            descriptor_sets,
+            sampler,
+            shader_modules: vec![vert_module, frag_module],
+        })
+    }
+
+    //записывает полноэкранный проход, command buffer уже должен быть в subpass эффектов
+    pub fn draw(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        image_index: usize,
+        extent: vk::Extent2D,
+        effects: &PostEffects,
+    ) {
+        let constants = PostConstants {
+            effects: *effects,
+            resolution: [extent.width as f32, extent.height as f32],
+        };
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&constants as *const PostConstants as *const u8, size_of::<PostConstants>())
+        };
+        unsafe {
+            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[self.descriptor_sets[image_index]],
+                &[],
+            );
+            device.cmd_push_constants(command_buffer, self.pipeline_layout, vk::ShaderStageFlags::FRAGMENT, 0, bytes);
+            device.cmd_draw(command_buffer, 3, 1, 0, 0);
+        }
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            device.destroy_pipeline(self.pipeline, None);
+            device.destroy_pipeline_layout(self.pipeline_layout, None);
+            device.destroy_descriptor_pool(self.descriptor_pool, None); //наборы освобождаются вместе с пулом
+            device.destroy_descriptor_set_layout(self.set_layout, None);
+            device.destroy_sampler(self.sampler, None); //null handle игнорируется
+            for &shader_module in &self.shader_modules {
+                device.destroy_shader_module(shader_module, None);
+            }
+        }
+    }
+}
+
+pub(crate) fn shader_module(device: &Device, spv: &[u8]) -> VkResult<vk::ShaderModule> {
+    let words = read_spv(&mut Cursor::new(spv)).map_err(|_| vk::Result::ERROR_INITIALIZATION_FAILED)?;
+    unsafe { device.create_shader_module(&vk::ShaderModuleCreateInfo::default().code(&words), None) }
+}
+
+/*интерфейс встроенных шейдеров для pipeline`ов вне PipelineRegistry, раскладки берутся из SPIR-V так же как в реестре.
+Встроенный SPIR-V проверяется тестами, поэтому ошибка разбора тут это ошибка сборки*/
+pub(crate) fn embedded_interface(stages: &[&[u8]]) -> VkResult<PipelineInterface> {
+    let reflections = stages.iter().map(|spv| embedded_reflection(spv)).collect::<VkResult<Vec<_>>>()?;
+    let stages: Vec<&ShaderReflection> = reflections.iter().collect();
+    PipelineInterface::new(&stages).map_err(embedded_error)
+}
+
+pub(crate) fn embedded_reflection(spv: &[u8]) -> VkResult<ShaderReflection> {
+    let words = read_spv(&mut Cursor::new(spv)).map_err(|_| vk::Result::ERROR_INITIALIZATION_FAILED)?;
+    ShaderReflection::parse(&words).map_err(embedded_error)
+}
+
+//ошибка встроенного шейдера пишется в лог, конструкторы эффектов и частиц возвращают VkResult
+pub(crate) fn embedded_error(err: ShaderError) -> vk::Result {
+    match err {
+        ShaderError::Vulkan(err) => err,
+        other => {
+            log::error!("embedded shader: {}", other);
+            vk::Result::ERROR_INITIALIZATION_FAILED
+        }
+    }
+}
+
+/*pipeline полноэкранного треугольника из fullscreen.vert: без вершинного буффера, без смешивания,
+фрагмент пишет итоговый цвет пикселя, format используется только для динамического рендера (render_pass null)*/
+#[allow(clippy::too_many_arguments)]
+fn fullscreen_pipeline(
+    device: &Device,
+    pipeline_cache: vk::PipelineCache,
+    pipeline_layout: vk::PipelineLayout,
+    render_pass: vk::RenderPass,
+    subpass: u32,
+    format: vk::Format,
+    extent: vk::Extent2D,
+    samples: vk::SampleCountFlags,
+    vert_module: vk::ShaderModule,
+    frag_module: vk::ShaderModule,
+) -> VkResult<vk::Pipeline> {
+    let shader_stages = [
+        vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::VERTEX)
+            .module(vert_module)
+            .name(c"main"),
+        vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::FRAGMENT)
+            .module(frag_module)
+            .name(c"main"),
+    ];
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::default(); //вершины генерируются из gl_VertexIndex
+    let input_assembly_state =
+        vk::PipelineInputAssemblyStateCreateInfo::default().topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+    let viewports = [vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: extent.width as f32,
+        height: extent.height as f32,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    }];
+    let scissors = [extent.into()];
+    let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+        .viewports(&viewports)
+        .scissors(&scissors);
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::default()
+        .polygon_mode(vk::PolygonMode::FILL)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .line_width(1.0);
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(samples);
+    let color_blend_attachments = [vk::PipelineColorBlendAttachmentState::default()
+        .blend_enable(false)
+        .color_write_mask(vk::ColorComponentFlags::RGBA)];
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default().attachments(&color_blend_attachments);
+
+    let color_formats = [format];
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+    let mut pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+        .stages(&shader_stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .color_blend_state(&color_blend_state)
+        .layout(pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(subpass);
+    if render_pass == vk::RenderPass::null() {
+        pipeline_info = pipeline_info.push_next(&mut rendering_info);
+    }
+    unsafe {
+        device
+            .create_graphics_pipelines(pipeline_cache, &[pipeline_info], None)
+            .map_err(|(_, err)| err)
+            .map(|pipelines| pipelines[0])
+    }
+}
+
+//фрагментные шейдеры эффектов, порядок как у ChainEffect::kind
+const CHAIN_SHADERS: [&[u8]; ChainEffect::KINDS] = [
+    include_bytes!("../shader/blur.frag.spv"),
+    include_bytes!("../shader/bloom.frag.spv"),
+    include_bytes!("../shader/crt.frag.spv"),
+    include_bytes!("../shader/pixelate.frag.spv"),
+    include_bytes!("../shader/tonemap.frag.spv"),
+];
+
+/*формат промежуточных изображений цепочки, половинные float`ы хранят цвет больше 1 после свечения
+и аддитивного смешивания, до Tonemap он не обрезается, формат обязателен для COLOR_ATTACHMENT и SAMPLED*/
+pub const CHAIN_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+/*один эффект цепочки, каждый это один или несколько полноэкранных проходов,
+параметры задаются в пикселях экрана, чтобы эффект не зависел от размера окна*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChainEffect {
+    Blur { radius: f32 }, //гауссово размытие, два прохода: по горизонтали и по вертикали
+    Bloom { threshold: f32, intensity: f32, radius: f32 }, //свечение пикселей ярче threshold
+    Crt { curvature: f32, scanlines: f32, aberration: f32 }, //кинескоп: изгиб, строки развертки, сдвиг каналов
+    Pixelate { size: f32 }, //крупные пиксели размером size
+    Tonemap { exposure: f32, aces: bool }, //HDR в 0..1, Reinhard или ACES
+}
+
+impl ChainEffect {
+    const KINDS: usize = 5; //количество pipeline`ов, по одному на вариант
+
+    fn kind(self) -> usize {
+        match self {
+            ChainEffect::Blur { .. } => 0,
+            ChainEffect::Bloom { .. } => 1,
+            ChainEffect::Crt { .. } => 2,
+            ChainEffect::Pixelate { .. } => 3,
+            ChainEffect::Tonemap { .. } => 4,
+        }
+    }
+
+    //проходы эффекта: индекс pipeline и vec4 params шейдера
+    fn passes(self) -> impl Iterator<Item = (usize, [f32; 4])> {
+        let kind = self.kind();
+        let (first, second) = match self {
+            ChainEffect::Blur { radius } => ([radius, 0.0, 0.0, 0.0], Some([0.0, radius, 0.0, 0.0])),
+            ChainEffect::Bloom { threshold, intensity, radius } => ([threshold, intensity, radius, 0.0], None),
+            ChainEffect::Crt { curvature, scanlines, aberration } => ([curvature, scanlines, aberration, 0.0], None),
+            ChainEffect::Pixelate { size } => ([size, 0.0, 0.0, 0.0], None),
+            ChainEffect::Tonemap { exposure, aces } => ([exposure, if aces { 1.0 } else { 0.0 }, 0.0, 0.0], None),
+        };
+        std::iter::once((kind, first)).chain(second.map(|params| (kind, params)))
+    }
+}
+
+/*куда пишет проход pass (от 0): цели чередуются 0, 1, 0, ..., поэтому каждый проход читает результат предыдущего,
+None это исходное изображение сцены, из него читает первый проход и blit при пустой цепочке*/
+fn chain_output(pass_count: usize) -> Option<usize> {
+    pass_count.checked_sub(1).map(|last| last % 2)
+}
+
+//раскладка совпадает с layout(push_constant) в шейдерах цепочки
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ChainConstants {
+    params: [f32; 4],
+    resolution: [f32; 2],
+}
+
+/*цепочка полноэкранных эффектов: сцена рисуется в source, затем каждый проход читает предыдущее изображение
+как sampler2D и пишет в одну из двух промежуточных целей по очереди (ping-pong), в конце blit копирует результат
+в кадр swapchain внутри обычного RenderBase прохода. Порядок и параметры effects можно менять между кадрами*/
+pub struct PostChain {
+    pub source: RenderTarget, //сюда рисуется сцена, pipeline`ы сцены создаются для source.render_pass
+    pub effects: Vec<ChainEffect>,
+    targets: [RenderTarget; 2],
+    descriptors: TextureDescriptors,
+    source_set: vk::DescriptorSet,
+    target_sets: [vk::DescriptorSet; 2],
+    set_layouts: Vec<vk::DescriptorSetLayout>, //из шейдеров, наборы из descriptors с ними совместимы
+    pipeline_layout: vk::PipelineLayout,
+    pipelines: [vk::Pipeline; ChainEffect::KINDS], //для render pass промежуточных целей, у обеих он совместим
+    blit_pipeline: vk::Pipeline, //для render pass кадра swapchain
+    shader_modules: Vec<vk::ShaderModule>,
+} //vulkan post-processing chain resources
+
+impl PostChain {
+    /*extent обычно размер swapchain, output_render_pass, output_format и output_samples это RenderBase в который идет blit,
+    цепочку нужно пересоздавать вместе с ним*/
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        app_base: &AppBase,
+        pipeline_cache: vk::PipelineCache,
+        command_pool: vk::CommandPool,
+        extent: vk::Extent2D,
+        source_desc: &RenderPassDesc,
+        output_render_pass: vk::RenderPass,
+        output_format: vk::Format,
+        output_samples: vk::SampleCountFlags,
+        effects: Vec<ChainEffect>,
+    ) -> VkResult<Self> {
+        let device = &app_base.device;
+        let options = TextureOptions::default(); //линейная фильтрация и CLAMP_TO_EDGE, размытие не заворачивается через край
+        let source = RenderTarget::new(app_base, command_pool, extent, CHAIN_FORMAT, source_desc, &options)?;
+        //промежуточные цели всегда целиком перезаписываются, старое содержимое не нужно
+        let target_desc = RenderPassDesc {
+            load_op: vk::AttachmentLoadOp::DONT_CARE,
+            ..Default::default()
+        };
+        let targets = [
+            RenderTarget::new(app_base, command_pool, extent, CHAIN_FORMAT, &target_desc, &options)?,
+            RenderTarget::new(app_base, command_pool, extent, CHAIN_FORMAT, &target_desc, &options)?,
+        ];
+
+        let descriptors = TextureDescriptors::new(device, 3)?;
+        let source_set = descriptors.allocate(device, &source.texture)?;
+        let target_sets = [
+            descriptors.allocate(device, &targets[0].texture)?,
+            descriptors.allocate(device, &targets[1].texture)?,
+        ];
+
+        /*раскладка одна на все проходы цепочки, чтобы draw привязывал наборы и push constant`ы одинаково,
+        берется из первого эффекта, остальные эффекты и blit (без push constant`ов) должны в нее помещаться*/
+        let blit_code: &[u8] = include_bytes!("../shader/blit.frag.spv");
+        let interface = embedded_interface(&[FULLSCREEN_VERT, CHAIN_SHADERS[0]])?;
+        for frag_code in CHAIN_SHADERS.iter().chain([&blit_code]) {
+            if !embedded_interface(&[FULLSCREEN_VERT, frag_code])?.fits_layout(&interface) {
+                let message = "chain shaders do not share one pipeline layout".to_string();
+                return Err(embedded_error(ShaderError::Interface(message)));
+            }
+        }
+        let set_layouts = interface.create_set_layouts(device).map_err(embedded_error)?;
+        let pipeline_layout = interface.create_pipeline_layout(device, &set_layouts)?;
+
+        let vert_module = shader_module(device, FULLSCREEN_VERT)?;
+        let mut frag_modules = [vk::ShaderModule::null(); ChainEffect::KINDS];
+        for (module, frag_code) in frag_modules.iter_mut().zip(CHAIN_SHADERS) {
+            *module = shader_module(device, frag_code)?;
+        }
+        let blit_module = shader_module(device, blit_code)?;
+
+        let mut pipelines = [vk::Pipeline::null(); ChainEffect::KINDS];
+        for (pipeline, &frag_module) in pipelines.iter_mut().zip(&frag_modules) {
+            *pipeline = fullscreen_pipeline(
+                device,
+                pipeline_cache,
+                pipeline_layout,
+                targets[0].render_pass,
+                0,
+                CHAIN_FORMAT,
+                extent,
+                targets[0].samples,
+                vert_module,
+                frag_module,
+            )?;
+        }
+        let blit_pipeline = fullscreen_pipeline(
+            device,
+            pipeline_cache,
+            pipeline_layout,
+            output_render_pass,
+            0,
+            output_format,
+            extent,
+            output_samples,
+            vert_module,
+            blit_module,
+        )?;
+
+        let mut shader_modules = vec![vert_module, blit_module];
+        shader_modules.extend(frag_modules);
+
+        Ok(Self {
+            source,
+            effects,
+            targets,
+            descriptors,
+            source_set,
+            target_sets,
+            set_layouts,
+            pipeline_layout,
+            pipelines,
+            blit_pipeline,
+            shader_modules,
+        })
+    }
+
+    fn draw(&self, device: &Device, command_buffer: vk::CommandBuffer, pipeline: vk::Pipeline, input: vk::DescriptorSet, params: [f32; 4]) {
+        let extent = self.source.extent();
+        let constants = ChainConstants {
+            params,
+            resolution: [extent.width as f32, extent.height as f32],
+        };
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&constants as *const ChainConstants as *const u8, size_of::<ChainConstants>())
+        };
+        unsafe {
+            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[input],
+                &[],
+            );
+            device.cmd_push_constants(command_buffer, self.pipeline_layout, vk::ShaderStageFlags::FRAGMENT, 0, bytes);
+            device.cmd_draw(command_buffer, 3, 1, 0, 0);
+        }
+    }
+
+    /*записывает все проходы эффектов, вызывается вне render pass после прохода в source и до прохода с blit,
+    переходы layout между проходами делают render pass`ы RenderTarget*/
+    pub fn record(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        let mut input = self.source_set;
+        for (pass, (kind, params)) in self.effects.iter().flat_map(|effect| effect.passes()).enumerate() {
+            let output = pass % 2;
+            let target = &self.targets[output];
+            target.begin(device, command_buffer);
+            self.draw(device, command_buffer, self.pipelines[kind], input, params);
+            target.end(device, command_buffer);
+            input = self.target_sets[output];
+        }
+    }
+
+    //копирует результат цепочки в текущий render pass, он должен быть начат с output_render_pass
+    pub fn blit(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        let pass_count = self.effects.iter().map(|effect| effect.passes().count()).sum();
+        let input = match chain_output(pass_count) {
+            Some(output) => self.target_sets[output],
+            None => self.source_set,
+        };
+        self.draw(device, command_buffer, self.blit_pipeline, input, [0.0; 4]);
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            for &pipeline in self.pipelines.iter().chain([&self.blit_pipeline]) {
+                device.destroy_pipeline(pipeline, None);
+            }
+            device.destroy_pipeline_layout(self.pipeline_layout, None);
+            for &set_layout in &self.set_layouts {
+                device.destroy_descriptor_set_layout(set_layout, None);
+            }
+            for &shader_module in &self.shader_modules {
+                device.destroy_shader_module(shader_module, None);
+            }
+            self.descriptors.destroy(device);
+            for target in self.targets.iter().chain([&self.source]) {
+                target.destroy(device);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_is_separable_into_two_passes() {
+        let passes: Vec<_> = ChainEffect::Blur { radius: 3.0 }.passes().collect();
+        assert_eq!(passes, vec![(0, [3.0, 0.0, 0.0, 0.0]), (0, [0.0, 3.0, 0.0, 0.0])]);
+        assert_eq!(ChainEffect::Tonemap { exposure: 1.0, aces: true }.passes().count(), 1);
+    }
+
+    #[test]
+    fn chain_output_alternates_targets() {
+        assert_eq!(chain_output(0), None); //пустая цепочка показывает source
+        assert_eq!(chain_output(1), Some(0));
+        assert_eq!(chain_output(2), Some(1));
+        assert_eq!(chain_output(5), Some(0));
+    }
+
+    #[test]
+    fn shader_layouts_match_constants() {
+        for frag_code in [include_bytes!("../shader/post.frag.spv").as_slice(), include_bytes!("../shader/post_sampled.frag.spv")] {
+            let post = embedded_interface(&[FULLSCREEN_VERT, frag_code]).unwrap();
+            assert_eq!(post.push_constant_range.map(|range| range.size), Some(size_of::<PostConstants>() as u32));
+        }
+        let chain = embedded_interface(&[FULLSCREEN_VERT, CHAIN_SHADERS[0]]).unwrap();
+        assert_eq!(chain.push_constant_range.map(|range| range.size), Some(size_of::<ChainConstants>() as u32));
+        for frag_code in CHAIN_SHADERS.iter().chain([&include_bytes!("../shader/blit.frag.spv").as_slice()]) {
+            assert!(embedded_interface(&[FULLSCREEN_VERT, frag_code]).unwrap().fits_layout(&chain));
+        }
+        //post.frag читает input attachment, а не sampler2D цепочки
+        let post = embedded_interface(&[FULLSCREEN_VERT, include_bytes!("../shader/post.frag.spv")]).unwrap();
+        assert!(!post.fits_layout(&chain));
+    }
+}
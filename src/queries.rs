@@ -0,0 +1,197 @@
+use crate::AppBase;
+use crate::timing::create_query_pools;
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+
+const MAX_SCOPES: u32 = 16; //участков на кадр, лишние не замеряются
+
+/*счетчики в порядке возрастания битов флагов, в таком порядке их возвращает get_query_pool_results*/
+const STATISTICS: vk::QueryPipelineStatisticFlags = vk::QueryPipelineStatisticFlags::from_raw(
+    vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES.as_raw()
+        | vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS.as_raw()
+        | vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES.as_raw()
+        | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS.as_raw(),
+);
+
+/*счетчики одного участка за кадр*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PassCounters {
+    pub input_vertices: u64, //вершин прочитано input assembly
+    pub vertex_invocations: u64, //запусков вершинного шейдера
+    pub clipping_primitives: u64, //примитивов дошедших до отсечения
+    pub fragment_invocations: u64, //запусков фрагментного шейдера
+    pub samples_passed: Option<u64>, //occlusion query, None если устройство не дает точного числа
+}
+
+impl PassCounters {
+    fn from_results(statistics: [u64; 4], samples_passed: u64, precise: bool) -> Self {
+        let [input_vertices, vertex_invocations, clipping_primitives, fragment_invocations] = statistics;
+        Self {
+            input_vertices,
+            vertex_invocations,
+            clipping_primitives,
+            fragment_invocations,
+            samples_passed: precise.then_some(samples_passed),
+        }
+    }
+
+    /*среднее число запусков фрагментного шейдера на пиксель области pixels, 1.0 значит каждый пиксель закрашен один раз,
+    в 2D сцене с прозрачными спрайтами это основная мера лишней работы*/
+    pub fn overdraw(&self, pixels: u64) -> f64 {
+        if pixels == 0 { 0.0 } else { self.fragment_invocations as f64 / pixels as f64 }
+    }
+}
+
+/*статистика конвейера и occlusion запросы вокруг отрисовки проходов, пулы по одному на кадр в полете как у GpuTimer,
+результаты кадра забираются когда его fence уже пройден. Запросы начинаются и заканчиваются внутри одного subpass,
+поэтому участок оборачивает только команды отрисовки, а не begin/end прохода*/
+pub struct GpuStatistics {
+    statistics_pools: Vec<vk::QueryPool>,
+    occlusion_pools: Vec<vk::QueryPool>,
+    scopes: Vec<Vec<String>>, //записанные в command buffer кадра участки, индекс участка = индекс запроса
+    precise: bool, //occlusion_query_precise включен, иначе результат только "ноль или не ноль"
+    latest: Vec<(String, PassCounters)>, //последний кадр для которого готовы результаты
+}
+
+impl GpuStatistics {
+    //None если pipeline_statistics_query не включен при создании устройства
+    pub fn new(app_base: &AppBase, frames_in_flight: usize) -> VkResult<Option<Self>> {
+        if !app_base.features.pipeline_statistics_query {
+            return Ok(None);
+        }
+        let device = &app_base.device;
+        let statistics_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::PIPELINE_STATISTICS)
+            .query_count(MAX_SCOPES)
+            .pipeline_statistics(STATISTICS);
+        let occlusion_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::OCCLUSION)
+            .query_count(MAX_SCOPES);
+        let statistics_pools = create_query_pools(device, &statistics_info, frames_in_flight)?;
+        let occlusion_pools = match create_query_pools(device, &occlusion_info, frames_in_flight) {
+            Ok(occlusion_pools) => occlusion_pools,
+            Err(err) => {
+                for &query_pool in &statistics_pools {
+                    unsafe { device.destroy_query_pool(query_pool, None) };
+                }
+                return Err(err);
+            }
+        };
+
+        Ok(Some(Self {
+            statistics_pools,
+            occlusion_pools,
+            scopes: vec![Vec::new(); frames_in_flight],
+            precise: app_base.features.occlusion_query_precise,
+            latest: Vec::new(),
+        }))
+    }
+
+    /*вызывается в начале записи command buffer кадра вне render pass, после ожидания его fence:
+    забирает результаты прошлого использования пулов и сбрасывает их*/
+    pub fn begin_frame(&mut self, device: &Device, command_buffer: vk::CommandBuffer, frame: usize) {
+        let scopes = std::mem::take(&mut self.scopes[frame]);
+        if !scopes.is_empty() {
+            let mut statistics = vec![[0u64; 4]; scopes.len()];
+            let mut samples = vec![0u64; scopes.len()];
+            //без WAIT: fence уже сигнализирован, NOT_READY значит кадр не был отправлен
+            let result = unsafe {
+                device
+                    .get_query_pool_results(self.statistics_pools[frame], 0, &mut statistics, vk::QueryResultFlags::TYPE_64)
+                    .and_then(|_| {
+                        device.get_query_pool_results(self.occlusion_pools[frame], 0, &mut samples, vk::QueryResultFlags::TYPE_64)
+                    })
+            };
+            if result.is_ok() {
+                self.latest = scopes
+                    .into_iter()
+                    .zip(statistics.into_iter().zip(samples))
+                    .map(|(name, (statistics, samples))| (name, PassCounters::from_results(statistics, samples, self.precise)))
+                    .collect();
+            }
+        }
+        unsafe {
+            device.cmd_reset_query_pool(command_buffer, self.statistics_pools[frame], 0, MAX_SCOPES);
+            device.cmd_reset_query_pool(command_buffer, self.occlusion_pools[frame], 0, MAX_SCOPES);
+        }
+    }
+
+    /*f между началом и концом запросов участка name, без статистики просто вызывает f,
+    вызывается внутри прохода, f не должна переключать subpass*/
+    pub fn scoped<R>(
+        statistics: Option<&mut Self>,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        frame: usize,
+        name: impl FnOnce() -> String,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let Some(statistics) = statistics else { return f() };
+        let index = statistics.scopes[frame].len() as u32;
+        if index >= MAX_SCOPES {
+            return f();
+        }
+        statistics.scopes[frame].push(name());
+        let (statistics_pool, occlusion_pool) = (statistics.statistics_pools[frame], statistics.occlusion_pools[frame]);
+        let control = if statistics.precise { vk::QueryControlFlags::PRECISE } else { vk::QueryControlFlags::empty() };
+        unsafe {
+            device.cmd_begin_query(command_buffer, statistics_pool, index, vk::QueryControlFlags::empty());
+            device.cmd_begin_query(command_buffer, occlusion_pool, index, control);
+        }
+        let result = f();
+        unsafe {
+            device.cmd_end_query(command_buffer, occlusion_pool, index);
+            device.cmd_end_query(command_buffer, statistics_pool, index);
+        }
+        result
+    }
+
+    //счетчики участков последнего готового кадра, отстает на MAX_FRAMES_IN_FLIGHT кадров
+    pub fn latest(&self) -> &[(String, PassCounters)] {
+        &self.latest
+    }
+
+    //по строке на участок, overdraw считается относительно pixels (обычно площадь окна)
+    pub fn summary(&self, pixels: u64) -> String {
+        self.latest
+            .iter()
+            .map(|(name, counters)| {
+                let samples = counters.samples_passed.map_or("n/a".to_string(), |samples| samples.to_string());
+                format!(
+                    "{name}: vertices {}, vertex invocations {}, primitives {}, fragment invocations {}, samples passed {}, overdraw {:.2}",
+                    counters.input_vertices,
+                    counters.vertex_invocations,
+                    counters.clipping_primitives,
+                    counters.fragment_invocations,
+                    samples,
+                    counters.overdraw(pixels)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            for &query_pool in self.statistics_pools.iter().chain(&self.occlusion_pools) {
+                device.destroy_query_pool(query_pool, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_follow_flag_bit_order() {
+        let counters = PassCounters::from_results([6, 6, 2, 800], 640, false);
+        assert_eq!(counters.input_vertices, 6);
+        assert_eq!(counters.clipping_primitives, 2);
+        assert_eq!(counters.samples_passed, None);
+        assert_eq!(counters.overdraw(400), 2.0);
+        assert_eq!(PassCounters::from_results([0; 4], 640, true).samples_passed, Some(640));
+    }
+}
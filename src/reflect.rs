@@ -0,0 +1,611 @@
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+//номера из спецификации SPIR-V, используются только те что нужны для интерфейса шейдера
+const MAGIC: u32 = 0x0723_0203;
+const OP_NAME: u32 = 5;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_OUTPUT: u32 = 3;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+const DIM_SUBPASS_DATA: u32 = 6;
+
+/*ошибки разбора шейдера и несовпадения интерфейсов между стадиями*/
+#[derive(Debug)]
+pub enum ShaderError {
+    Spirv(String), //файл не SPIR-V или в нем конструкция, которую reflection не понимает
+    Interface(String), //стадии или вершинный формат не совпадают
+    Vulkan(vk::Result),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Spirv(message) => write!(f, "invalid SPIR-V: {}", message),
+            ShaderError::Interface(message) => write!(f, "shader interface mismatch: {}", message),
+            ShaderError::Vulkan(err) => write!(f, "shader vulkan error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+impl From<vk::Result> for ShaderError {
+    fn from(err: vk::Result) -> Self {
+        ShaderError::Vulkan(err)
+    }
+}
+
+/*вход или выход стадии с layout(location = ...), встроенные переменные (gl_Position, gl_VertexIndex) не попадают*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterfaceVariable {
+    pub location: u32,
+    pub format: vk::Format, //формат атрибута того же размера, vec4 это R32G32B32A32_SFLOAT
+    pub name: String,
+}
+
+/*ресурс из layout(set = ..., binding = ...)*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DescriptorBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: u32, //длина массива дескрипторов, 1 для одиночного
+    pub name: String,
+}
+
+//тип из секции объявлений, только то что влияет на форматы и размеры
+#[derive(Clone, Debug)]
+enum Type {
+    Scalar { float: bool, signed: bool, width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: Option<u32> }, //None для runtime array
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+/*интерфейс одной стадии, разобранный из SPIR-V: входы, выходы, дескрипторы и размер push constant блока*/
+#[derive(Clone, Debug)]
+pub struct ShaderReflection {
+    pub stage: vk::ShaderStageFlags,
+    pub inputs: Vec<InterfaceVariable>, //по возрастанию location
+    pub outputs: Vec<InterfaceVariable>,
+    pub bindings: Vec<DescriptorBinding>, //по возрастанию (set, binding)
+    pub push_constant_size: Option<u32>,
+}
+
+impl ShaderReflection {
+    /*words как из ash::util::read_spv. Используется первая точка входа модуля,
+    все шейдеры проекта содержат ровно одну main*/
+    pub fn parse(words: &[u32]) -> Result<Self, ShaderError> {
+        if words.len() < 5 || words[0] != MAGIC {
+            return Err(ShaderError::Spirv("missing SPIR-V header".to_string()));
+        }
+
+        let mut stage = None;
+        let mut names: HashMap<u32, String> = HashMap::new();
+        let mut types: HashMap<u32, Type> = HashMap::new();
+        let mut constants: HashMap<u32, u32> = HashMap::new();
+        let mut decorations: HashMap<(u32, u32), u32> = HashMap::new(); //(id, decoration) -> первый литерал
+        let mut member_decorations: HashMap<(u32, u32, u32), u32> = HashMap::new(); //(struct, member, decoration)
+        let mut variables: Vec<(u32, u32, u32)> = Vec::new(); //id, тип указателя, storage class
+
+        let mut offset = 5;
+        while offset < words.len() {
+            let word_count = (words[offset] >> 16) as usize;
+            let opcode = words[offset] & 0xFFFF;
+            if word_count == 0 || offset + word_count > words.len() {
+                return Err(ShaderError::Spirv(format!("truncated instruction at word {}", offset)));
+            }
+            let operands = &words[offset + 1..offset + word_count];
+            let operand = |index: usize| {
+                operands
+                    .get(index)
+                    .copied()
+                    .ok_or_else(|| ShaderError::Spirv(format!("opcode {} is missing operand {}", opcode, index)))
+            };
+            match opcode {
+                OP_NAME => {
+                    names.insert(operand(0)?, literal_string(&operands[1..]));
+                }
+                OP_ENTRY_POINT if stage.is_none() => {
+                    stage = Some(match operand(0)? {
+                        0 => vk::ShaderStageFlags::VERTEX,
+                        4 => vk::ShaderStageFlags::FRAGMENT,
+                        5 => vk::ShaderStageFlags::COMPUTE,
+                        model => return Err(ShaderError::Spirv(format!("unsupported execution model {}", model))),
+                    });
+                }
+                OP_TYPE_INT => {
+                    types.insert(operand(0)?, Type::Scalar { float: false, signed: operand(2)? == 1, width: operand(1)? });
+                }
+                OP_TYPE_FLOAT => {
+                    types.insert(operand(0)?, Type::Scalar { float: true, signed: true, width: operand(1)? });
+                }
+                OP_TYPE_VECTOR => {
+                    types.insert(operand(0)?, Type::Vector { component: operand(1)?, count: operand(2)? });
+                }
+                OP_TYPE_MATRIX => {
+                    types.insert(operand(0)?, Type::Matrix { column: operand(1)?, count: operand(2)? });
+                }
+                OP_TYPE_IMAGE => {
+                    types.insert(operand(0)?, Type::Image { dim: operand(2)?, sampled: operand(6)? });
+                }
+                OP_TYPE_SAMPLER => {
+                    types.insert(operand(0)?, Type::Sampler);
+                }
+                OP_TYPE_SAMPLED_IMAGE => {
+                    types.insert(operand(0)?, Type::SampledImage);
+                }
+                OP_TYPE_ARRAY => {
+                    //длина из OpSpecConstant станет известна только при создании pipeline
+                    let id = operand(0)?;
+                    let Some(&length) = constants.get(&operand(2)?) else {
+                        return Err(ShaderError::Spirv(format!("array type {} has no constant length", id)));
+                    };
+                    types.insert(id, Type::Array { element: operand(1)?, length: Some(length) });
+                }
+                OP_TYPE_RUNTIME_ARRAY => {
+                    types.insert(operand(0)?, Type::Array { element: operand(1)?, length: None });
+                }
+                OP_TYPE_STRUCT => {
+                    types.insert(operand(0)?, Type::Struct { members: operands[1..].to_vec() });
+                }
+                OP_TYPE_POINTER => {
+                    types.insert(operand(0)?, Type::Pointer { pointee: operand(2)? });
+                }
+                OP_CONSTANT => {
+                    //длины массивов это 32-битные целые константы, для остальных первого слова достаточно
+                    constants.insert(operand(1)?, operand(2)?);
+                }
+                OP_VARIABLE => variables.push((operand(1)?, operand(0)?, operand(2)?)),
+                OP_DECORATE => {
+                    decorations.insert((operand(0)?, operand(1)?), operands.get(2).copied().unwrap_or(0));
+                }
+                OP_MEMBER_DECORATE => {
+                    member_decorations.insert((operand(0)?, operand(1)?, operand(2)?), operands.get(3).copied().unwrap_or(0));
+                }
+                _ => {}
+            }
+            offset += word_count;
+        }
+
+        let stage = stage.ok_or_else(|| ShaderError::Spirv("no entry point".to_string()))?;
+        let module = Module {
+            types,
+            decorations,
+            member_decorations,
+        };
+        let mut reflection = Self {
+            stage,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            bindings: Vec::new(),
+            push_constant_size: None,
+        };
+
+        for (id, pointer_type, storage_class) in variables {
+            let name = names.get(&id).cloned().unwrap_or_default();
+            let Some(Type::Pointer { pointee }) = module.types.get(&pointer_type) else {
+                return Err(ShaderError::Spirv(format!("variable {} is not a pointer", name)));
+            };
+            let pointee = *pointee;
+            match storage_class {
+                STORAGE_INPUT | STORAGE_OUTPUT => {
+                    //gl_PerVertex и другие встроенные переменные без location не часть пользовательского интерфейса
+                    let Some(&location) = module.decorations.get(&(id, DECORATION_LOCATION)) else { continue };
+                    let variable = InterfaceVariable {
+                        location,
+                        format: module.format(pointee, &name)?,
+                        name,
+                    };
+                    if storage_class == STORAGE_INPUT {
+                        reflection.inputs.push(variable);
+                    } else {
+                        reflection.outputs.push(variable);
+                    }
+                }
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    let (element, count) = match module.types.get(&pointee) {
+                        Some(&Type::Array { element, length }) => (element, length.unwrap_or(1)),
+                        _ => (pointee, 1),
+                    };
+                    reflection.bindings.push(DescriptorBinding {
+                        set: module.decorations.get(&(id, DECORATION_DESCRIPTOR_SET)).copied().unwrap_or(0),
+                        binding: module.decorations.get(&(id, DECORATION_BINDING)).copied().unwrap_or(0),
+                        descriptor_type: module.descriptor_type(element, storage_class, &name)?,
+                        count,
+                        name,
+                    });
+                }
+                STORAGE_PUSH_CONSTANT => reflection.push_constant_size = Some(module.size(pointee, None)?),
+                _ => {} //Private, Function и Workgroup переменные не видны снаружи
+            }
+        }
+        reflection.inputs.sort_by_key(|variable| variable.location);
+        reflection.outputs.sort_by_key(|variable| variable.location);
+        reflection.bindings.sort_by_key(|binding| (binding.set, binding.binding));
+        Ok(reflection)
+    }
+
+    /*атрибуты вершинного буффера для входов вершинного шейдера: каждому входу нужно поле вершины fields (Vertex::attributes)
+    с тем же location и форматом, смещение берется из поля, поэтому шейдер может читать не все поля и пропускать location`ы*/
+    pub fn vertex_attributes(
+        &self,
+        fields: &[vk::VertexInputAttributeDescription],
+    ) -> Result<Vec<vk::VertexInputAttributeDescription>, ShaderError> {
+        self.inputs
+            .iter()
+            .map(|input| match fields.iter().find(|field| field.location == input.location) {
+                Some(field) if field.format == input.format => Ok(*field),
+                Some(field) => Err(ShaderError::Interface(format!(
+                    "vertex input '{}' at location {} is {:?}, but the vertex field there is {:?}",
+                    input.name, input.location, input.format, field.format
+                ))),
+                None => Err(ShaderError::Interface(format!(
+                    "vertex input '{}' at location {} has no vertex field",
+                    input.name, input.location
+                ))),
+            })
+            .collect()
+    }
+}
+
+/*интерфейс pipeline из всех стадий: проверяет что каждый вход следующей стадии записан предыдущей
+и объединяет дескрипторы и push constant`ы стадий для vk::PipelineLayout*/
+pub struct PipelineInterface {
+    pub bindings: Vec<(DescriptorBinding, vk::ShaderStageFlags)>,
+    pub push_constant_range: Option<vk::PushConstantRange>,
+}
+
+impl PipelineInterface {
+    //stages в порядке конвейера, например [вершинный, фрагментный]
+    pub fn new(stages: &[&ShaderReflection]) -> Result<Self, ShaderError> {
+        for pair in stages.windows(2) {
+            let (producer, consumer) = (pair[0], pair[1]);
+            for input in &consumer.inputs {
+                match producer.outputs.iter().find(|output| output.location == input.location) {
+                    Some(output) if output.format == input.format => {}
+                    Some(output) => {
+                        return Err(ShaderError::Interface(format!(
+                            "{:?} input '{}' at location {} is {:?}, but {:?} writes {:?}",
+                            consumer.stage, input.name, input.location, input.format, producer.stage, output.format
+                        )));
+                    }
+                    None => {
+                        return Err(ShaderError::Interface(format!(
+                            "{:?} input '{}' at location {} is not written by the {:?} stage",
+                            consumer.stage, input.name, input.location, producer.stage
+                        )));
+                    }
+                }
+            }
+        }
+
+        let mut merged: BTreeMap<(u32, u32), (DescriptorBinding, vk::ShaderStageFlags)> = BTreeMap::new();
+        for stage in stages {
+            for binding in &stage.bindings {
+                let key = (binding.set, binding.binding);
+                match merged.get_mut(&key) {
+                    Some((existing, stages)) => {
+                        if existing.descriptor_type != binding.descriptor_type || existing.count != binding.count {
+                            return Err(ShaderError::Interface(format!(
+                                "set {} binding {} is {:?}[{}] in one stage and {:?}[{}] in {:?}",
+                                binding.set,
+                                binding.binding,
+                                existing.descriptor_type,
+                                existing.count,
+                                binding.descriptor_type,
+                                binding.count,
+                                stage.stage
+                            )));
+                        }
+                        *stages |= stage.stage;
+                    }
+                    None => {
+                        merged.insert(key, (binding.clone(), stage.stage));
+                    }
+                }
+            }
+        }
+
+        //один диапазон на все стадии, размер по самому большому блоку
+        let push_constant_range = stages
+            .iter()
+            .filter_map(|stage| stage.push_constant_size.map(|size| (stage.stage, size)))
+            .reduce(|(flags, size), (stage, stage_size)| (flags | stage, size.max(stage_size)))
+            .map(|(stage_flags, size)| vk::PushConstantRange { stage_flags, offset: 0, size });
+
+        Ok(Self {
+            bindings: merged.into_values().collect(),
+            push_constant_range,
+        })
+    }
+
+    /*раскладки наборов 0..=максимальный set, пропущенные номера получают пустую раскладку.
+    Раскладка совпадает по содержимому с созданной вручную (например TextureDescriptors::layout),
+    поэтому наборы выделенные из той раскладки совместимы с этим pipeline*/
+    pub fn create_set_layouts(&self, device: &Device) -> Result<Vec<vk::DescriptorSetLayout>, ShaderError> {
+        let set_count = self.bindings.iter().map(|(binding, _)| binding.set + 1).max().unwrap_or(0);
+        let mut set_layouts = Vec::with_capacity(set_count as usize);
+        for set in 0..set_count {
+            let layout_bindings: Vec<vk::DescriptorSetLayoutBinding> = self
+                .bindings
+                .iter()
+                .filter(|(binding, _)| binding.set == set)
+                .map(|(binding, stages)| {
+                    vk::DescriptorSetLayoutBinding::default()
+                        .binding(binding.binding)
+                        .descriptor_type(binding.descriptor_type)
+                        .descriptor_count(binding.count)
+                        .stage_flags(*stages)
+                })
+                .collect();
+            let create_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&layout_bindings);
+            match unsafe { device.create_descriptor_set_layout(&create_info, None) } {
+                Ok(layout) => set_layouts.push(layout),
+                Err(err) => {
+                    for &layout in &set_layouts {
+                        unsafe { device.destroy_descriptor_set_layout(layout, None) };
+                    }
+                    return Err(err.into());
+                }
+            }
+        }
+        Ok(set_layouts)
+    }
+
+    //раскладка pipeline из наборов create_set_layouts и общего диапазона push constant`ов
+    pub fn create_pipeline_layout(&self, device: &Device, set_layouts: &[vk::DescriptorSetLayout]) -> VkResult<vk::PipelineLayout> {
+        let push_constant_ranges: Vec<vk::PushConstantRange> = self.push_constant_range.into_iter().collect();
+        unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::default()
+                    .set_layouts(set_layouts)
+                    .push_constant_ranges(&push_constant_ranges),
+                None,
+            )
+        }
+    }
+
+    /*pipeline с этим интерфейсом можно создать с раскладкой из layout: каждый его дескриптор есть там с тем же типом
+    и видим его стадиям, а push constant`ы помещаются в диапазон layout. Имена переменных в шейдерах не важны*/
+    pub fn fits_layout(&self, layout: &PipelineInterface) -> bool {
+        let bindings_fit = self.bindings.iter().all(|(binding, stages)| {
+            layout.bindings.iter().any(|(other, other_stages)| {
+                (other.set, other.binding, other.descriptor_type, other.count)
+                    == (binding.set, binding.binding, binding.descriptor_type, binding.count)
+                    && other_stages.contains(*stages)
+            })
+        });
+        let push_fits = match (self.push_constant_range, layout.push_constant_range) {
+            (None, _) => true,
+            (Some(range), Some(other)) => {
+                other.stage_flags.contains(range.stage_flags) && range.offset >= other.offset && range.offset + range.size <= other.offset + other.size
+            }
+            (Some(_), None) => false,
+        };
+        bindings_fit && push_fits
+    }
+}
+
+struct Module {
+    types: HashMap<u32, Type>,
+    decorations: HashMap<(u32, u32), u32>,
+    member_decorations: HashMap<(u32, u32, u32), u32>,
+}
+
+impl Module {
+    fn get(&self, id: u32) -> Result<&Type, ShaderError> {
+        self.types.get(&id).ok_or_else(|| ShaderError::Spirv(format!("unknown type {}", id)))
+    }
+
+    //формат атрибута для скаляра или вектора из 32-битных компонент
+    fn format(&self, id: u32, name: &str) -> Result<vk::Format, ShaderError> {
+        let (component, count) = match self.get(id)? {
+            Type::Vector { component, count } => (*component, *count),
+            Type::Scalar { .. } => (id, 1),
+            other => return Err(ShaderError::Spirv(format!("interface variable '{}' has unsupported type {:?}", name, other))),
+        };
+        let &Type::Scalar { float, signed, width: 32 } = self.get(component)? else {
+            return Err(ShaderError::Spirv(format!("interface variable '{}' is not 32-bit", name)));
+        };
+        use vk::Format as F;
+        let formats = match (float, signed) {
+            (true, _) => [F::R32_SFLOAT, F::R32G32_SFLOAT, F::R32G32B32_SFLOAT, F::R32G32B32A32_SFLOAT],
+            (false, true) => [F::R32_SINT, F::R32G32_SINT, F::R32G32B32_SINT, F::R32G32B32A32_SINT],
+            (false, false) => [F::R32_UINT, F::R32G32_UINT, F::R32G32B32_UINT, F::R32G32B32A32_UINT],
+        };
+        formats
+            .get(count as usize - 1)
+            .copied()
+            .ok_or_else(|| ShaderError::Spirv(format!("interface variable '{}' has {} components", name, count)))
+    }
+
+    fn descriptor_type(&self, id: u32, storage_class: u32, name: &str) -> Result<vk::DescriptorType, ShaderError> {
+        Ok(match (storage_class, self.get(id)?) {
+            (STORAGE_STORAGE_BUFFER, _) => vk::DescriptorType::STORAGE_BUFFER,
+            //до SPIR-V 1.3 storage буффер это Uniform со структурой BufferBlock
+            (STORAGE_UNIFORM, _) if self.decorations.contains_key(&(id, DECORATION_BUFFER_BLOCK)) => {
+                vk::DescriptorType::STORAGE_BUFFER
+            }
+            (STORAGE_UNIFORM, _) => vk::DescriptorType::UNIFORM_BUFFER,
+            (_, Type::SampledImage) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            (_, Type::Sampler) => vk::DescriptorType::SAMPLER,
+            (_, Type::Image { dim: DIM_SUBPASS_DATA, .. }) => vk::DescriptorType::INPUT_ATTACHMENT,
+            (_, Type::Image { sampled: 2, .. }) => vk::DescriptorType::STORAGE_IMAGE,
+            (_, Type::Image { .. }) => vk::DescriptorType::SAMPLED_IMAGE,
+            (_, other) => return Err(ShaderError::Spirv(format!("resource '{}' has unsupported type {:?}", name, other))),
+        })
+    }
+
+    /*размер в байтах по смещениям и шагам из декораций, как его видит push constant или буффер,
+    MatrixStride стоит на члене структуры, а не на типе матрицы, поэтому передается в matrix_stride*/
+    fn size(&self, id: u32, matrix_stride: Option<u32>) -> Result<u32, ShaderError> {
+        Ok(match self.get(id)? {
+            Type::Scalar { width, .. } => width / 8,
+            Type::Vector { component, count } => self.size(*component, None)? * count,
+            Type::Matrix { column, count } => {
+                let stride = match matrix_stride {
+                    Some(stride) => stride,
+                    None => self.size(*column, None)?,
+                };
+                stride * count
+            }
+            Type::Array { element, length } => {
+                let stride = match self.decorations.get(&(id, DECORATION_ARRAY_STRIDE)) {
+                    Some(&stride) => stride,
+                    None => self.size(*element, matrix_stride)?,
+                };
+                stride * length.unwrap_or(0)
+            }
+            Type::Struct { members } => {
+                let mut size = 0;
+                for (index, &member) in members.iter().enumerate() {
+                    let offset = self.member_decorations.get(&(id, index as u32, DECORATION_OFFSET)).copied().unwrap_or(size);
+                    if self.member_decorations.contains_key(&(id, index as u32, DECORATION_BUILT_IN)) {
+                        continue;
+                    }
+                    let matrix_stride = self.member_decorations.get(&(id, index as u32, DECORATION_MATRIX_STRIDE)).copied();
+                    size = size.max(offset + self.size(member, matrix_stride)?);
+                }
+                size
+            }
+            other => return Err(ShaderError::Spirv(format!("type {:?} has no size", other))),
+        })
+    }
+}
+
+//строка SPIR-V: UTF-8 упакованный по 4 байта в слово, завершается нулем
+fn literal_string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).take_while(|&byte| byte != 0).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vertex::Vertex;
+    use ash::util::read_spv;
+    use std::io::Cursor;
+    use std::mem::offset_of;
+
+    fn reflect(spv: &[u8]) -> ShaderReflection {
+        ShaderReflection::parse(&read_spv(&mut Cursor::new(spv)).unwrap()).unwrap()
+    }
+
+    fn layout(attributes: &[vk::VertexInputAttributeDescription]) -> Vec<(u32, vk::Format, u32)> {
+        attributes.iter().map(|attribute| (attribute.location, attribute.format, attribute.offset)).collect()
+    }
+
+    #[test]
+    fn vertex_inputs_match_vertex_struct() {
+        let sprite = reflect(include_bytes!("../shader/sprite.vert.spv"));
+        assert_eq!(sprite.stage, vk::ShaderStageFlags::VERTEX);
+        assert_eq!(
+            layout(&sprite.vertex_attributes(&Vertex::attributes()).unwrap()),
+            [
+                (0, vk::Format::R32G32_SFLOAT, offset_of!(Vertex, position) as u32),
+                (1, vk::Format::R32G32B32A32_SFLOAT, offset_of!(Vertex, color) as u32),
+                (2, vk::Format::R32G32_SFLOAT, offset_of!(Vertex, uv) as u32),
+            ]
+        );
+        //без поля uv входу location 2 не из чего читать
+        assert!(matches!(sprite.vertex_attributes(&Vertex::attributes()[..2]), Err(ShaderError::Interface(_))));
+    }
+
+    #[test]
+    fn skipped_location_keeps_field_offsets() {
+        //testdata/skip_location.vert читает position (location 0) и uv (location 2), без color
+        let shader = reflect(include_bytes!("../testdata/skip_location.vert.spv"));
+        assert_eq!(
+            layout(&shader.vertex_attributes(&Vertex::attributes()).unwrap()),
+            [
+                (0, vk::Format::R32G32_SFLOAT, offset_of!(Vertex, position) as u32),
+                (2, vk::Format::R32G32_SFLOAT, offset_of!(Vertex, uv) as u32),
+            ]
+        );
+
+        //тот же location с другим форматом
+        let mut fields = Vertex::attributes();
+        fields[2].format = vk::Format::R32G32B32A32_SFLOAT;
+        assert!(matches!(shader.vertex_attributes(&fields), Err(ShaderError::Interface(_))));
+    }
+
+    #[test]
+    fn reflects_bindings_and_push_constants() {
+        let sprite = reflect(include_bytes!("../shader/sprite.frag.spv"));
+        assert_eq!(sprite.bindings.len(), 1);
+        assert_eq!(sprite.bindings[0].descriptor_type, vk::DescriptorType::COMBINED_IMAGE_SAMPLER);
+        assert_eq!((sprite.bindings[0].set, sprite.bindings[0].binding), (0, 0));
+
+        let post = reflect(include_bytes!("../shader/post.frag.spv"));
+        assert_eq!(post.bindings[0].descriptor_type, vk::DescriptorType::INPUT_ATTACHMENT);
+        assert!(post.push_constant_size.is_some());
+
+        let particles = reflect(include_bytes!("../shader/particles.comp.spv"));
+        assert_eq!(particles.stage, vk::ShaderStageFlags::COMPUTE);
+        assert_eq!(particles.bindings[0].descriptor_type, vk::DescriptorType::STORAGE_BUFFER);
+        assert_eq!(particles.push_constant_size, Some(36));
+    }
+
+    #[test]
+    fn matrix_push_constants_use_member_stride() {
+        //testdata/matrix_push.frag: mat3 и mat2x3, столбцы vec3 выровнены по 16 байт
+        let shader = reflect(include_bytes!("../testdata/matrix_push.frag.spv"));
+        assert_eq!(shader.push_constant_size, Some(48 + 32));
+    }
+
+    #[test]
+    fn stage_interfaces_must_match() {
+        let triangle = reflect(include_bytes!("../shader/triangle.vert.spv"));
+        let sprite_vert = reflect(include_bytes!("../shader/sprite.vert.spv"));
+        let sprite_frag = reflect(include_bytes!("../shader/sprite.frag.spv"));
+        let interface = PipelineInterface::new(&[&sprite_vert, &sprite_frag]).unwrap();
+        assert_eq!(interface.bindings[0].1, vk::ShaderStageFlags::FRAGMENT);
+        assert!(interface.push_constant_range.is_none());
+        //triangle.vert не пишет fragUV, который читает sprite.frag
+        assert!(matches!(PipelineInterface::new(&[&triangle, &sprite_frag]), Err(ShaderError::Interface(_))));
+    }
+
+    #[test]
+    fn rejects_non_spirv() {
+        assert!(matches!(ShaderReflection::parse(&[0, 1, 2, 3, 4]), Err(ShaderError::Spirv(_))));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::vertex::Vertex;
+use std::f32::consts::PI;
+
+/*линии толще 1.0 через line_width требуют фичу wideLines, которой нет на многих устройствах,
+поэтому толстые линии разбиваются на треугольники на CPU и рисуются обычным pipeline*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter, //острый угол, при слишком остром угле переходит в Bevel по miter_limit
+    Bevel, //срезанный угол
+    Round, //скругленный угол
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,   //линия заканчивается ровно в конечной точке
+    Square, //линия продлевается на половину толщины
+    Round,  //полукруг радиусом в половину толщины
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    pub miter_limit: f32, //отношение длины острия к половине толщины, как в SVG stroke-miterlimit
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+/*количество сегментов дуги радиуса radius на угол angle,
+так чтобы отклонение хорды от окружности было не больше четверти пикселя*/
+pub fn arc_segments(radius: f32, angle: f32) -> u32 {
+    let tolerance = 0.25_f32;
+    let step = if radius > tolerance {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        PI / 2.0
+    };
+    ((angle.abs() / step).ceil() as u32).clamp(1, 256)
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn add_scaled(a: [f32; 2], b: [f32; 2], scale: f32) -> [f32; 2] {
+    [a[0] + b[0] * scale, a[1] + b[1] * scale]
+}
+
+fn normalize(v: [f32; 2]) -> [f32; 2] {
+    let length = (v[0] * v[0] + v[1] * v[1]).sqrt();
+    [v[0] / length, v[1] / length]
+}
+
+//левая нормаль к направлению
+fn normal(direction: [f32; 2]) -> [f32; 2] {
+    [-direction[1], direction[0]]
+}
+
+struct StrokeBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    color: [f32; 4],
+}
+
+impl StrokeBuilder {
+    fn push(&mut self, position: [f32; 2]) -> u32 {
+        self.vertices.push(Vertex::new(position, self.color));
+        self.vertices.len() as u32 - 1
+    }
+
+    fn quad(&mut self, a: [f32; 2], b: [f32; 2], c: [f32; 2], d: [f32; 2]) {
+        let (a, b, c, d) = (self.push(a), self.push(b), self.push(c), self.push(d));
+        self.indices.extend_from_slice(&[a, b, c, c, d, a]);
+    }
+
+    //веер треугольников вокруг center от угла start на угол sweep
+    fn arc(&mut self, center: [f32; 2], radius: f32, start: f32, sweep: f32) {
+        let segments = arc_segments(radius, sweep);
+        let center_index = self.push(center);
+        let first = self.vertices.len() as u32;
+        for i in 0..=segments {
+            let angle = start + sweep * i as f32 / segments as f32;
+            self.push([center[0] + radius * angle.cos(), center[1] + radius * angle.sin()]);
+        }
+        for i in 0..segments {
+            self.indices
+                .extend_from_slice(&[center_index, first + i, first + i + 1]);
+        }
+    }
+
+    /*заполнение внешней стороны излома в точке point между сегментами с нормалями n0 и n1,
+    сами сегменты уже нарисованы прямоугольниками и перекрываются на внутренней стороне*/
+    fn join(&mut self, point: [f32; 2], n0: [f32; 2], n1: [f32; 2], style: &StrokeStyle) {
+        let half_width = style.width * 0.5;
+        let cross = n0[0] * n1[1] - n0[1] * n1[0];
+        if cross.abs() < 1e-6 && n0[0] * n1[0] + n0[1] * n1[1] > 0.0 {
+            return; //сегменты на одной прямой, излома нет
+        }
+        let side = if cross > 0.0 { -1.0 } else { 1.0 }; //внешняя сторона противоположна направлению поворота
+        let outer0 = add_scaled(point, n0, side * half_width);
+        let outer1 = add_scaled(point, n1, side * half_width);
+
+        match style.join {
+            LineJoin::Miter => {
+                let bisector = normalize([n0[0] + n1[0], n0[1] + n1[1]]);
+                let cos_half = bisector[0] * n0[0] + bisector[1] * n0[1];
+                if cos_half > 1e-6 && 1.0 / cos_half <= style.miter_limit {
+                    let tip = add_scaled(point, bisector, side * half_width / cos_half);
+                    self.quad(point, outer0, tip, outer1);
+                } else {
+                    let (a, b, c) = (self.push(point), self.push(outer0), self.push(outer1));
+                    self.indices.extend_from_slice(&[a, b, c]);
+                }
+            }
+            LineJoin::Bevel => {
+                let (a, b, c) = (self.push(point), self.push(outer0), self.push(outer1));
+                self.indices.extend_from_slice(&[a, b, c]);
+            }
+            LineJoin::Round => {
+                let start = (side * n0[1]).atan2(side * n0[0]);
+                let mut sweep = (side * n1[1]).atan2(side * n1[0]) - start;
+                if sweep > PI {
+                    sweep -= 2.0 * PI;
+                } else if sweep < -PI {
+                    sweep += 2.0 * PI;
+                }
+                self.arc(point, half_width, start, sweep);
+            }
+        }
+    }
+}
+
+/*разбивает ломаную points на треугольники толщиной style.width,
+closed замыкает последнюю точку с первой (тогда вместо концов используются соединения),
+индексы отсчитываются от начала возвращенного массива вершин*/
+pub fn stroke_polyline(
+    points: &[[f32; 2]],
+    closed: bool,
+    style: &StrokeStyle,
+    color: [f32; 4],
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut builder = StrokeBuilder {
+        vertices: Vec::new(),
+        indices: Vec::new(),
+        color,
+    };
+
+    //повторяющиеся подряд точки дают сегменты нулевой длины без направления
+    let mut points: Vec<[f32; 2]> = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 2 || style.width <= 0.0 {
+        return (builder.vertices, builder.indices);
+    }
+
+    let half_width = style.width * 0.5;
+    let segment_count = if closed { points.len() } else { points.len() - 1 };
+    let directions: Vec<[f32; 2]> = (0..segment_count)
+        .map(|i| normalize(sub(points[(i + 1) % points.len()], points[i])))
+        .collect();
+
+    for (i, &direction) in directions.iter().enumerate() {
+        let mut start = points[i];
+        let mut end = points[(i + 1) % points.len()];
+        if !closed && style.cap == LineCap::Square {
+            if i == 0 {
+                start = add_scaled(start, direction, -half_width);
+            }
+            if i == segment_count - 1 {
+                end = add_scaled(end, direction, half_width);
+            }
+        }
+        let n = normal(direction);
+        builder.quad(
+            add_scaled(start, n, half_width),
+            add_scaled(end, n, half_width),
+            add_scaled(end, n, -half_width),
+            add_scaled(start, n, -half_width),
+        );
+    }
+
+    //соединения во внутренних точках, для замкнутой ломаной во всех точках
+    let joins = if closed { 0..points.len() } else { 1..points.len() - 1 };
+    for i in joins {
+        let previous = directions[(i + segment_count - 1) % segment_count];
+        let next = directions[i % segment_count];
+        builder.join(points[i], normal(previous), normal(next), style);
+    }
+
+    if !closed && style.cap == LineCap::Round {
+        let first = directions[0];
+        let last = directions[segment_count - 1];
+        let first_angle = first[0].atan2(-first[1]); //угол нормали n = (-dy, dx), полукруг от n через -direction
+        let last_angle = (-last[0]).atan2(last[1]); //угол -n, полукруг от -n через direction
+        builder.arc(points[0], half_width, first_angle, PI);
+        builder.arc(points[points.len() - 1], half_width, last_angle, PI);
+    }
+
+    (builder.vertices, builder.indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [f32; 4] = [1.0; 4];
+
+    fn style(width: f32, join: LineJoin, cap: LineCap) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            join,
+            cap,
+            ..Default::default()
+        }
+    }
+
+    fn positions(vertices: &[Vertex]) -> Vec<[f32; 2]> {
+        vertices.iter().map(|vertex| vertex.position).collect()
+    }
+
+    fn close(a: [f32; 2], b: [f32; 2]) -> bool {
+        (a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4
+    }
+
+    fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+        let [dx, dy] = sub(a, b);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    //уголок (0,0) -> (10,0) -> (10,10), внешняя сторона излома у точки (11,-1)
+    const CORNER: [[f32; 2]; 3] = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0]];
+
+    #[test]
+    fn butt_and_square_caps() {
+        let (vertices, indices) = stroke_polyline(&[[0.0, 0.0], [10.0, 0.0]], false, &style(2.0, LineJoin::Miter, LineCap::Butt), WHITE);
+        assert_eq!(positions(&vertices), vec![[0.0, 1.0], [10.0, 1.0], [10.0, -1.0], [0.0, -1.0]]);
+        assert_eq!(indices, vec![0, 1, 2, 2, 3, 0]);
+
+        //квадратный конец продлевает линию на половину толщины в обе стороны
+        let (vertices, indices) = stroke_polyline(&[[0.0, 0.0], [10.0, 0.0]], false, &style(2.0, LineJoin::Miter, LineCap::Square), WHITE);
+        assert_eq!(positions(&vertices), vec![[-1.0, 1.0], [11.0, 1.0], [11.0, -1.0], [-1.0, -1.0]]);
+        assert_eq!(indices.len(), 6);
+    }
+
+    #[test]
+    fn round_caps_are_half_circles_outside_the_segment() {
+        let (vertices, indices) = stroke_polyline(&[[0.0, 0.0], [10.0, 0.0]], false, &style(2.0, LineJoin::Miter, LineCap::Round), WHITE);
+        let segments = arc_segments(1.0, PI) as usize;
+        assert_eq!(vertices.len(), 4 + 2 * (segments + 2));
+        assert_eq!(indices.len(), 6 + 2 * 3 * segments);
+
+        let cap_size = segments + 2;
+        let (start_cap, end_cap) = (&vertices[4..4 + cap_size], &vertices[4 + cap_size..]);
+        assert!(close(start_cap[0].position, [0.0, 0.0]) && close(end_cap[0].position, [10.0, 0.0]));
+        for vertex in &start_cap[1..] {
+            assert!((distance(vertex.position, [0.0, 0.0]) - 1.0).abs() < 1e-4 && vertex.position[0] <= 1e-4);
+        }
+        for vertex in &end_cap[1..] {
+            assert!((distance(vertex.position, [10.0, 0.0]) - 1.0).abs() < 1e-4 && vertex.position[0] >= 10.0 - 1e-4);
+        }
+    }
+
+    #[test]
+    fn miter_join_falls_back_to_bevel_past_the_limit() {
+        //прямой угол: острие на расстоянии sqrt(2) половины толщины
+        let (vertices, indices) = stroke_polyline(&CORNER, false, &style(2.0, LineJoin::Miter, LineCap::Butt), WHITE);
+        assert_eq!((vertices.len(), indices.len()), (12, 18));
+        assert!(close(vertices[9].position, [10.0, -1.0]) && close(vertices[11].position, [11.0, 0.0]));
+        assert!(close(vertices[10].position, [11.0, -1.0]));
+
+        let limited = StrokeStyle {
+            miter_limit: 1.2,
+            ..style(2.0, LineJoin::Miter, LineCap::Butt)
+        };
+        let (vertices, indices) = stroke_polyline(&CORNER, false, &limited, WHITE);
+        let bevel = stroke_polyline(&CORNER, false, &style(2.0, LineJoin::Bevel, LineCap::Butt), WHITE);
+        assert_eq!((vertices.len(), indices.len()), (11, 15));
+        assert_eq!((positions(&vertices), indices), (positions(&bevel.0), bevel.1));
+        assert!(!bevel.0.iter().any(|vertex| close(vertex.position, [11.0, -1.0])));
+    }
+
+    #[test]
+    fn round_join_is_a_quarter_arc_on_the_outer_side() {
+        let (vertices, indices) = stroke_polyline(&CORNER, false, &style(2.0, LineJoin::Round, LineCap::Butt), WHITE);
+        let segments = arc_segments(1.0, PI / 2.0) as usize;
+        assert_eq!((vertices.len(), indices.len()), (8 + segments + 2, 12 + 3 * segments));
+
+        let arc = &vertices[8..];
+        assert!(close(arc[0].position, [10.0, 0.0]));
+        assert!(close(arc[1].position, [10.0, -1.0]) && close(arc[arc.len() - 1].position, [11.0, 0.0]));
+        for vertex in &arc[1..] {
+            assert!((distance(vertex.position, [10.0, 0.0]) - 1.0).abs() < 1e-4);
+            assert!(vertex.position[0] >= 10.0 - 1e-4 && vertex.position[1] <= 1e-4);
+        }
+    }
+
+    #[test]
+    fn closed_stroke_ignores_repeated_endpoint() {
+        let square = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]];
+        let bevel = style(2.0, LineJoin::Bevel, LineCap::Round); //у замкнутого контура концов нет, cap не действует
+        let (vertices, indices) = stroke_polyline(&square, true, &bevel, WHITE);
+        assert_eq!((vertices.len(), indices.len()), (4 * 4 + 4 * 3, 4 * 6 + 4 * 3));
+
+        let mut repeated = square.to_vec();
+        repeated.push(square[0]);
+        let (repeated_vertices, repeated_indices) = stroke_polyline(&repeated, true, &bevel, WHITE);
+        assert_eq!((positions(&repeated_vertices), repeated_indices), (positions(&vertices), indices));
+    }
+
+    #[test]
+    fn duplicate_points_and_degenerate_input() {
+        let butt = style(2.0, LineJoin::Miter, LineCap::Butt);
+        let (vertices, indices) = stroke_polyline(&[[0.0, 0.0], [0.0, 0.0], [10.0, 0.0], [10.0, 0.0]], false, &butt, WHITE);
+        let single = stroke_polyline(&[[0.0, 0.0], [10.0, 0.0]], false, &butt, WHITE);
+        assert_eq!((positions(&vertices), indices), (positions(&single.0), single.1));
+
+        let segment = [[0.0, 0.0], [10.0, 0.0]];
+        for (points, width) in [(&segment[..], 0.0), (&segment[..], -1.0), (&[[5.0, 5.0], [5.0, 5.0]][..], 2.0)] {
+            let (vertices, indices) = stroke_polyline(points, false, &style(width, LineJoin::Miter, LineCap::Round), WHITE);
+            assert!(vertices.is_empty() && indices.is_empty());
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::buffer::{find_memorytype_index, submit_once};
+use crate::texture::{TextureBase, TextureOptions, image_barrier};
+use crate::{AppBase, PassInfo, RenderPassDesc};
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+
+/*RenderTarget это изображение вне swapchain в которое можно рисовать тем же Batcher`ом (миникарта, портал,
+закешированный слой), а потом читать его как обычную текстуру в следующем проходе того же кадра.
+Вне render pass изображение всегда в SHADER_READ_ONLY_OPTIMAL, в COLOR_ATTACHMENT_OPTIMAL и обратно его переводит
+сам render pass, поэтому отдельные барьеры между проходами не нужны*/
+pub struct RenderTarget {
+    pub texture: TextureBase, //передается в TextureDescriptors::allocate и Sprite::new как любая текстура
+    pub render_pass: vk::RenderPass,
+    pub framebuffer: vk::Framebuffer,
+    pub samples: vk::SampleCountFlags, //всегда TYPE_1, pipeline`ы для этой цели создаются с таким же
+    pub desc: RenderPassDesc,
+} //vulkan offscreen color target
+
+impl RenderTarget {
+    /*options задают только sampler, mipmap у цели нет: уровни пришлось бы строить заново после каждого рендера*/
+    pub fn new(
+        app_base: &AppBase,
+        command_pool: vk::CommandPool,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        desc: &RenderPassDesc,
+        options: &TextureOptions,
+    ) -> VkResult<Self> {
+        if desc.post_process.is_some() {
+            return Err(vk::Result::ERROR_INITIALIZATION_FAILED); //post_process есть только у RenderBase
+        }
+        let device = &app_base.device;
+
+        //ресурсы заполняются по мере создания, при ошибке destroy освобождает созданные (null handle игнорируется)
+        let mut target = Self {
+            texture: TextureBase {
+                image: vk::Image::null(),
+                memory: vk::DeviceMemory::null(),
+                view: vk::ImageView::null(),
+                sampler: vk::Sampler::null(),
+                extent,
+                format,
+                mip_levels: 1,
+            },
+            render_pass: vk::RenderPass::null(),
+            framebuffer: vk::Framebuffer::null(),
+            samples: vk::SampleCountFlags::TYPE_1,
+            desc: *desc,
+        };
+        match target.create_resources(app_base, command_pool, options) {
+            Ok(()) => Ok(target),
+            Err(err) => {
+                unsafe { target.destroy(device) };
+                Err(err)
+            }
+        }
+    }
+
+    //image, память, view, sampler, render pass и framebuffer по порядку, см. new
+    fn create_resources(&mut self, app_base: &AppBase, command_pool: vk::CommandPool, options: &TextureOptions) -> VkResult<()> {
+        let device = &app_base.device;
+        let (extent, format, desc) = (self.texture.extent, self.texture.format, self.desc);
+        let load = desc.load_op == vk::AttachmentLoadOp::LOAD;
+
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(extent.into())
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(
+                vk::ImageUsageFlags::COLOR_ATTACHMENT //рисуем в него как в кадр swapchain
+                    | vk::ImageUsageFlags::SAMPLED //и читаем как текстуру
+                    | vk::ImageUsageFlags::TRANSFER_DST, //начальная очистка
+            )
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+        let image = unsafe { device.create_image(&image_info, None)? };
+        self.texture.image = image;
+
+        let memory_req = unsafe { device.get_image_memory_requirements(image) };
+        let memory_index = find_memorytype_index(
+            &memory_req,
+            &app_base.device_memory_properties,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )
+        .ok_or(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)?;
+        let memory = unsafe {
+            device.allocate_memory(
+                &vk::MemoryAllocateInfo::default()
+                    .allocation_size(memory_req.size)
+                    .memory_type_index(memory_index),
+                None,
+            )?
+        };
+        self.texture.memory = memory;
+        unsafe { device.bind_image_memory(image, memory, 0)? };
+
+        /*до первого рендера цель уже можно читать в шейдере и загружать LoadOp::LOAD,
+        поэтому сразу очищаем ее цветом clear_color и переводим в SHADER_READ_ONLY_OPTIMAL*/
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        submit_once(device, command_pool, app_base.present_queue, |command_buffer| {
+            image_barrier(
+                device,
+                command_buffer,
+                image,
+                0,
+                1,
+                (vk::ImageLayout::UNDEFINED, vk::AccessFlags::empty(), vk::PipelineStageFlags::TOP_OF_PIPE),
+                (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::AccessFlags::TRANSFER_WRITE, vk::PipelineStageFlags::TRANSFER),
+            );
+            unsafe {
+                device.cmd_clear_color_image(
+                    command_buffer,
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &vk::ClearColorValue { float32: desc.clear_color },
+                    &[subresource_range],
+                );
+            }
+            image_barrier(
+                device,
+                command_buffer,
+                image,
+                0,
+                1,
+                (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::AccessFlags::TRANSFER_WRITE, vk::PipelineStageFlags::TRANSFER),
+                (vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::AccessFlags::SHADER_READ, vk::PipelineStageFlags::FRAGMENT_SHADER),
+            );
+        })?;
+
+        let view_info = vk::ImageViewCreateInfo::default()
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(format)
+            .subresource_range(subresource_range);
+        self.texture.view = unsafe { device.create_image_view(&view_info, None)? };
+        self.texture.sampler = TextureBase::create_sampler(device, options, 1)?;
+
+        let attachments = [vk::AttachmentDescription::default()
+            .format(format)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .load_op(desc.load_op)
+            .store_op(desc.store_op)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            //при CLEAR старое содержимое не нужно, layout UNDEFINED позволяет драйверу его не сохранять
+            .initial_layout(if load { vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL } else { vk::ImageLayout::UNDEFINED })
+            .final_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)]; //после прохода сразу готово для sampler2D
+        let color_attachment_refs = [vk::AttachmentReference::default()
+            .attachment(0)
+            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)];
+        let subpasses = [vk::SubpassDescription::default()
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+            .color_attachments(&color_attachment_refs)];
+        let dependencies = [
+            /*чтение цели в предыдущем кадре (фрагментный шейдер) должно закончиться до того как поверх нее начнут рисовать,
+            кадры в полете используют одно и то же изображение*/
+            vk::SubpassDependency::default()
+                .src_subpass(vk::SUBPASS_EXTERNAL)
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(if load {
+                    vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                } else {
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                }),
+            //записанные пиксели должны быть видны sampler`у в следующих проходах
+            vk::SubpassDependency::default()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ),
+        ];
+        let render_pass_info = vk::RenderPassCreateInfo::default()
+            .attachments(&attachments)
+            .subpasses(&subpasses)
+            .dependencies(&dependencies);
+        self.render_pass = unsafe { device.create_render_pass(&render_pass_info, None)? };
+
+        let framebuffer_views = [self.texture.view];
+        let framebuffer_info = vk::FramebufferCreateInfo::default()
+            .render_pass(self.render_pass)
+            .attachments(&framebuffer_views)
+            .width(extent.width)
+            .height(extent.height)
+            .layers(1);
+        self.framebuffer = unsafe { device.create_framebuffer(&framebuffer_info, None)? };
+        Ok(())
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.texture.extent
+    }
+
+    //проход в цель для PipelineSet::new
+    pub fn pass_info(&self) -> PassInfo {
+        PassInfo {
+            render_pass: self.render_pass,
+            format: self.texture.format,
+            samples: self.samples,
+            desc: self.desc,
+        }
+    }
+
+    //начинает проход в цель, вызывается вне других render pass`ов
+    pub fn begin(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        let clear_values = [vk::ClearValue {
+            color: vk::ClearColorValue { float32: self.desc.clear_color },
+        }];
+        let render_pass_begin_info = vk::RenderPassBeginInfo::default()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffer)
+            .render_area(self.extent().into())
+            .clear_values(&clear_values);
+        unsafe { device.cmd_begin_render_pass(command_buffer, &render_pass_begin_info, vk::SubpassContents::INLINE) };
+    }
+
+    pub fn end(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        unsafe { device.cmd_end_render_pass(command_buffer) };
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            device.destroy_framebuffer(self.framebuffer, None);
+            device.destroy_render_pass(self.render_pass, None);
+            self.texture.destroy(device);
+        }
+    }
+}
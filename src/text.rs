@@ -0,0 +1,586 @@
+use crate::AppBase;
+use crate::atlas::{AtlasError, pack_square};
+use crate::texture::{TextureBase, TextureDescriptors, TextureError, TextureOptions};
+use ab_glyph::{Font as _, FontVec, GlyphId, InvalidFont, PxScale, ScaleFont, point};
+use ash::Device;
+use ash::vk;
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/*текст рисуется прямоугольниками из атласа глифов через Batcher, как обычные спрайты.
+Bitmap хранит покрытие пикселя и четко выглядит только около размера растеризации,
+Sdf хранит расстояние до контура глифа и масштабируется без размытия, но требует шейдер text_sdf.frag*/
+
+#[derive(Debug)]
+pub enum TextError {
+    Io(std::io::Error),
+    Font(InvalidFont),
+    Atlas(AtlasError),
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::Io(err) => write!(f, "font io error: {}", err),
+            TextError::Font(err) => write!(f, "font parse error: {}", err),
+            TextError::Atlas(err) => write!(f, "glyph atlas error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+impl From<std::io::Error> for TextError {
+    fn from(err: std::io::Error) -> Self {
+        TextError::Io(err)
+    }
+}
+
+impl From<InvalidFont> for TextError {
+    fn from(err: InvalidFont) -> Self {
+        TextError::Font(err)
+    }
+}
+
+impl From<AtlasError> for TextError {
+    fn from(err: AtlasError) -> Self {
+        TextError::Atlas(err)
+    }
+}
+
+impl From<TextureError> for TextError {
+    fn from(err: TextureError) -> Self {
+        TextError::Atlas(err.into())
+    }
+}
+
+impl From<vk::Result> for TextError {
+    fn from(err: vk::Result) -> Self {
+        TextError::Atlas(err.into())
+    }
+}
+
+//шрифт TrueType/OpenType
+pub struct Font {
+    font: FontVec,
+}
+
+impl Font {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TextError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, TextError> {
+        Ok(Self {
+            font: FontVec::try_from_vec(data)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlyphMode {
+    Bitmap,
+    Sdf { spread: u32 }, //на сколько пикселей от контура кодируется расстояние, столько же пустых пикселей добавляется вокруг глифа
+}
+
+//глиф в атласе, размеры в пикселях размера растеризации
+#[derive(Clone, Copy, Debug)]
+pub struct Glyph {
+    pub id: GlyphId,
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    pub offset: [f32; 2], //от точки на базовой линии до левого верхнего угла прямоугольника глифа
+    pub size: [f32; 2],   //0 у пробелов и других глифов без контура
+    pub advance: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle {
+    pub size: f32, //высота шрифта в пикселях экрана
+    pub align: TextAlign,
+    pub max_width: Option<f32>, //ширина для переноса строк по словам, None переносит только по '\n'
+    pub line_spacing: f32,      //множитель высоты строки
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            size: 16.0,
+            align: TextAlign::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+//прямоугольник одного глифа после раскладки, координаты от левого верхнего угла блока текста
+#[derive(Clone, Copy, Debug)]
+pub struct GlyphQuad {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TextLayout {
+    pub quads: Vec<GlyphQuad>,
+    pub size: [f32; 2], //размер блока текста
+}
+
+type GlyphBitmap = (u32, u32, Vec<f32>); //ширина, высота и покрытие пикселей глифа до упаковки
+
+/*CPU часть шрифта: метрики и положение глифов в атласе, отдельно от текстуры чтобы раскладку можно было считать без GPU*/
+pub struct GlyphSet {
+    font: Font,
+    pub px_size: f32, //размер растеризации
+    pub mode: GlyphMode,
+    pub glyphs: HashMap<char, Glyph>,
+    pub ascent: f32,
+    pub descent: f32, //отрицательный, как в ab_glyph
+    pub line_gap: f32,
+}
+
+impl GlyphSet {
+    /*растеризует символы chars в одно изображение атласа, упаковка как в AtlasBuilder,
+    в пикселях хранится белый цвет с покрытием (или расстоянием в режиме Sdf) в альфа канале*/
+    pub fn rasterize<I: IntoIterator<Item = char>>(
+        font: Font,
+        px_size: f32,
+        chars: I,
+        mode: GlyphMode,
+        max_size: u32,
+    ) -> Result<(Self, RgbaImage), TextError> {
+        let scaled = font.font.as_scaled(PxScale::from(px_size));
+        let padding = match mode {
+            GlyphMode::Bitmap => 0,
+            GlyphMode::Sdf { spread } => spread,
+        };
+
+        //'?' подставляется вместо символов которых нет в атласе
+        let mut chars: Vec<char> = chars.into_iter().chain(['?', ' ']).collect();
+        chars.sort_unstable();
+        chars.dedup();
+
+        let mut bitmaps: Vec<(char, GlyphId, [f32; 2], Option<GlyphBitmap>)> = Vec::with_capacity(chars.len());
+        for c in chars {
+            let id = font.font.glyph_id(c);
+            if id.0 == 0 && c != '?' {
+                continue; //символа нет в шрифте
+            }
+            let outlined = font
+                .font
+                .outline_glyph(id.with_scale_and_position(PxScale::from(px_size), point(0.0, 0.0)));
+            let Some(outlined) = outlined else {
+                bitmaps.push((c, id, [0.0, 0.0], None));
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            let width = bounds.width() as u32 + padding * 2;
+            let height = bounds.height() as u32 + padding * 2;
+            let mut coverage = vec![0.0; (width * height) as usize];
+            outlined.draw(|x, y, value| {
+                coverage[((y + padding) * width + x + padding) as usize] = value;
+            });
+            let offset = [bounds.min.x - padding as f32, bounds.min.y - padding as f32];
+            bitmaps.push((c, id, offset, Some((width, height, coverage))));
+        }
+
+        //высокие глифы первыми, 1 пиксель между глифами против подмешивания соседей при фильтрации
+        let mut order: Vec<usize> = (0..bitmaps.len()).filter(|&i| bitmaps[i].3.is_some()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(bitmaps[i].3.as_ref().map_or(0, |(_, height, _)| *height)));
+        let sizes: Vec<[u32; 2]> = order
+            .iter()
+            .filter_map(|&i| bitmaps[i].3.as_ref().map(|(width, height, _)| [*width, *height]))
+            .collect();
+        let (size, rects) = pack_square(&sizes, 1, max_size)?;
+        let rects = order.into_iter().zip(rects);
+
+        let mut image = RgbaImage::new(size, size);
+        let mut glyphs = HashMap::with_capacity(bitmaps.len());
+        for &(c, id, _, _) in bitmaps.iter().filter(|(_, _, _, bitmap)| bitmap.is_none()) {
+            glyphs.insert(
+                c,
+                Glyph {
+                    id,
+                    uv_min: [0.0, 0.0],
+                    uv_max: [0.0, 0.0],
+                    offset: [0.0, 0.0],
+                    size: [0.0, 0.0],
+                    advance: scaled.h_advance(id),
+                },
+            );
+        }
+        for (i, rect) in rects {
+            let (c, id, offset, bitmap) = &bitmaps[i];
+            let (width, height, coverage) = bitmap.as_ref().unwrap();
+            let values = match mode {
+                GlyphMode::Bitmap => coverage.clone(),
+                GlyphMode::Sdf { spread } => signed_distance_field(*width, *height, coverage, spread as f32),
+            };
+            for y in 0..*height {
+                for x in 0..*width {
+                    let value = (values[(y * width + x) as usize].clamp(0.0, 1.0) * 255.0).round() as u8;
+                    image.put_pixel(rect.x + x, rect.y + y, image::Rgba([255, 255, 255, value]));
+                }
+            }
+            glyphs.insert(
+                *c,
+                Glyph {
+                    id: *id,
+                    uv_min: [rect.x as f32 / size as f32, rect.y as f32 / size as f32],
+                    uv_max: [(rect.x + width) as f32 / size as f32, (rect.y + height) as f32 / size as f32],
+                    offset: *offset,
+                    size: [*width as f32, *height as f32],
+                    advance: scaled.h_advance(*id),
+                },
+            );
+        }
+
+        let (ascent, descent, line_gap) = (scaled.ascent(), scaled.descent(), scaled.line_gap());
+        Ok((
+            Self {
+                font,
+                px_size,
+                mode,
+                glyphs,
+                ascent,
+                descent,
+                line_gap,
+            },
+            image,
+        ))
+    }
+
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    //расстояние между базовыми линиями соседних строк в пикселях размера растеризации
+    pub fn line_height(&self) -> f32 {
+        self.ascent - self.descent + self.line_gap
+    }
+
+    //поправка к расстоянию между парой глифов из таблицы kern шрифта
+    fn kern(&self, first: GlyphId, second: GlyphId) -> f32 {
+        self.font.font.as_scaled(PxScale::from(self.px_size)).kern(first, second)
+    }
+
+    //ширина слова с учетом кернинга в пикселях размера растеризации
+    fn measure(&self, word: &str) -> f32 {
+        let mut width = 0.0;
+        let mut previous: Option<GlyphId> = None;
+        for c in word.chars() {
+            let Some(glyph) = self.glyph(c) else { continue };
+            if let Some(previous) = previous {
+                width += self.kern(previous, glyph.id);
+            }
+            width += glyph.advance;
+            previous = Some(glyph.id);
+        }
+        width
+    }
+
+    /*разбиение абзаца на строки по словам не шире max_width (в пикселях растеризации),
+    слово длиннее строки разбивается по символам*/
+    fn wrap<'a>(&self, paragraph: &'a str, max_width: Option<f32>) -> Vec<&'a str> {
+        let Some(max_width) = max_width else {
+            return vec![paragraph];
+        };
+        let mut lines = Vec::new();
+        let mut line_start = 0;
+        let mut line_end = 0; //конец последнего слова которое поместилось в строку
+        let mut word_start = 0;
+        for word in paragraph.split_inclusive(' ') {
+            let word_end = word_start + word.trim_end_matches(' ').len();
+            if word_end > word_start && self.measure(&paragraph[line_start..word_end]) > max_width {
+                if line_end > line_start {
+                    //слово переносится на новую строку целиком
+                    lines.push(&paragraph[line_start..line_end]);
+                    line_start = word_start;
+                }
+                //слово не помещается даже в пустую строку
+                while self.measure(&paragraph[line_start..word_end]) > max_width {
+                    let mut split = line_start;
+                    for (i, c) in paragraph[line_start..word_end].char_indices() {
+                        let end = line_start + i + c.len_utf8();
+                        if split > line_start && self.measure(&paragraph[line_start..end]) > max_width {
+                            break;
+                        }
+                        split = end;
+                    }
+                    if split >= word_end {
+                        break; //один символ шире строки
+                    }
+                    lines.push(&paragraph[line_start..split]);
+                    line_start = split;
+                }
+            }
+            if word_end > word_start {
+                line_end = word_end;
+            }
+            word_start += word.len();
+        }
+        if line_end > line_start || lines.is_empty() {
+            lines.push(&paragraph[line_start..line_end.max(line_start)]);
+        }
+        lines
+    }
+
+    /*раскладка текста: переносы строк по '\n' и по style.max_width, кернинг, выравнивание строк внутри блока,
+    блок шириной max_width если он задан, иначе шириной самой длинной строки*/
+    pub fn layout(&self, text: &str, style: &TextStyle) -> TextLayout {
+        let scale = style.size / self.px_size;
+        let max_width = style.max_width.map(|width| width / scale);
+        let lines: Vec<&str> = text.split('\n').flat_map(|paragraph| self.wrap(paragraph, max_width)).collect();
+        let widths: Vec<f32> = lines.iter().map(|line| self.measure(line)).collect();
+        let block_width = max_width.unwrap_or_else(|| widths.iter().copied().fold(0.0, f32::max));
+        let line_height = self.line_height() * style.line_spacing;
+
+        let mut layout = TextLayout {
+            quads: Vec::new(),
+            size: [
+                block_width * scale,
+                (line_height * (lines.len() - 1) as f32 + self.ascent - self.descent) * scale,
+            ],
+        };
+        for (row, (line, width)) in lines.iter().zip(widths).enumerate() {
+            let mut x = match style.align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (block_width - width) * 0.5,
+                TextAlign::Right => block_width - width,
+            };
+            let baseline = self.ascent + line_height * row as f32;
+            let mut previous: Option<GlyphId> = None;
+            for c in line.chars() {
+                let Some(glyph) = self.glyph(c) else { continue };
+                if let Some(previous) = previous {
+                    x += self.kern(previous, glyph.id);
+                }
+                if glyph.size[0] > 0.0 {
+                    layout.quads.push(GlyphQuad {
+                        position: [(x + glyph.offset[0]) * scale, (baseline + glyph.offset[1]) * scale],
+                        size: [glyph.size[0] * scale, glyph.size[1] * scale],
+                        uv_min: glyph.uv_min,
+                        uv_max: glyph.uv_max,
+                    });
+                }
+                x += glyph.advance;
+                previous = Some(glyph.id);
+            }
+        }
+        layout
+    }
+}
+
+/*поле расстояний по покрытию пикселей: точное евклидово преобразование расстояний (Felzenszwalb-Huttenlocher)
+отдельно для внутренних и внешних пикселей, 0.5 на контуре, 0 и 1 на расстоянии spread снаружи и внутри*/
+fn signed_distance_field(width: u32, height: u32, coverage: &[f32], spread: f32) -> Vec<f32> {
+    let inside: Vec<bool> = coverage.iter().map(|&value| value >= 0.5).collect();
+    let to_inside = distance_transform(width as usize, height as usize, |i| inside[i]);
+    let to_outside = distance_transform(width as usize, height as usize, |i| !inside[i]);
+    to_inside
+        .iter()
+        .zip(&to_outside)
+        .map(|(&a, &b)| {
+            let distance = if a > 0.0 { -(a.sqrt() - 0.5) } else { b.sqrt() - 0.5 };
+            0.5 + distance / (2.0 * spread)
+        })
+        .collect()
+}
+
+//квадрат расстояния от каждого пикселя до ближайшего пикселя для которого target истинно
+fn distance_transform<F: Fn(usize) -> bool>(width: usize, height: usize, target: F) -> Vec<f32> {
+    const INF: f32 = 1e20;
+    let mut grid: Vec<f32> = (0..width * height).map(|i| if target(i) { 0.0 } else { INF }).collect();
+    let mut line = vec![0.0; width.max(height)];
+    let mut result = vec![0.0; width.max(height)];
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        distance_transform_1d(&line[..height], &mut result[..height]);
+        for y in 0..height {
+            grid[y * width + x] = result[y];
+        }
+    }
+    for y in 0..height {
+        distance_transform_1d(&grid[y * width..(y + 1) * width], &mut result[..width]);
+        grid[y * width..(y + 1) * width].copy_from_slice(&result[..width]);
+    }
+    grid
+}
+
+//нижняя огибающая парабол f[q] + (x - q)^2 в точках x = 0..n
+fn distance_transform_1d(f: &[f32], d: &mut [f32]) {
+    let n = f.len();
+    let mut v = vec![0usize; n]; //вершины парабол огибающей
+    let mut z = vec![0.0f32; n + 1]; //границы между ними
+    let mut k = 0;
+    z[0] = f32::NEG_INFINITY;
+    z[1] = f32::INFINITY;
+    for q in 1..n {
+        let intersection = |p: usize| ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2 * (q - p)) as f32;
+        let mut s = intersection(v[k]);
+        while s <= z[k] {
+            k -= 1; //z[0] = -inf, поэтому k не уходит ниже нуля
+            s = intersection(v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f32::INFINITY;
+    }
+    k = 0;
+    for (x, value) in d.iter_mut().enumerate() {
+        while z[k + 1] < x as f32 {
+            k += 1;
+        }
+        *value = (x as f32 - v[k] as f32).powi(2) + f[v[k]];
+    }
+}
+
+/*шрифт загруженный на GPU: атлас глифов и набор дескрипторов для Batcher::draw_text*/
+pub struct FontAtlas {
+    pub glyphs: GlyphSet,
+    pub texture: TextureBase,
+    pub descriptor_set: vk::DescriptorSet,
+}
+
+impl FontAtlas {
+    pub fn new<I: IntoIterator<Item = char>>(
+        app_base: &AppBase,
+        command_pool: vk::CommandPool,
+        descriptors: &TextureDescriptors,
+        font: Font,
+        px_size: f32,
+        chars: I,
+        mode: GlyphMode,
+    ) -> Result<Self, TextError> {
+        let (glyphs, image) = GlyphSet::rasterize(font, px_size, chars, mode, 4096)?;
+        let options = TextureOptions {
+            mipmaps: false, //текст рисуется около размера растеризации, а Sdf масштабируется сам
+            ..Default::default()
+        };
+        let texture = TextureBase::from_rgba(app_base, command_pool, image.width(), image.height(), image.as_raw(), &options)?;
+        let descriptor_set = descriptors.allocate(&app_base.device, &texture)?;
+        Ok(Self {
+            glyphs,
+            texture,
+            descriptor_set,
+        })
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe { self.texture.destroy(device) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /*testdata/boxes.ttf: 1000 единиц на em, ascent 800, descent -200, глифы прямоугольники высотой 700 с отступом 50 слева,
+    ширины ' ' 250, 'A' и 'V' 600, '?' 500, 'i' 200, в таблице kern пара AV сдвинута на -100.
+    При размере 20 пикселей единица шрифта это 0.02 пикселя*/
+    fn boxes() -> GlyphSet {
+        let font = Font::from_bytes(include_bytes!("../testdata/boxes.ttf").to_vec()).unwrap();
+        GlyphSet::rasterize(font, 20.0, "AVi".chars(), GlyphMode::Bitmap, 256).unwrap().0
+    }
+
+    fn style(align: TextAlign, max_width: Option<f32>) -> TextStyle {
+        TextStyle {
+            size: 20.0,
+            align,
+            max_width,
+            ..Default::default()
+        }
+    }
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn kerning_pair_changes_advance() {
+        let glyphs = boxes();
+        let left = style(TextAlign::Left, None);
+        let kerned = glyphs.layout("AV", &left);
+        let plain = glyphs.layout("AA", &left);
+        assert!(approx(kerned.size[0], 22.0) && approx(plain.size[0], 24.0));
+        assert!(approx(kerned.quads[1].position[0] - kerned.quads[0].position[0], 10.0));
+        assert!(approx(plain.quads[1].position[0] - plain.quads[0].position[0], 12.0));
+        assert!(approx(kerned.size[1], 20.0));
+    }
+
+    #[test]
+    fn wraps_words_at_max_width() {
+        let glyphs = boxes();
+        //"AV AV" шириной 22 + 5 + 22 = 49 помещается в 50, третье слово переносится
+        let layout = glyphs.layout("AV AV AV", &style(TextAlign::Left, Some(50.0)));
+        assert_eq!(layout.quads.len(), 6);
+        assert!(approx(layout.size[0], 50.0) && approx(layout.size[1], 40.0));
+        let rows: Vec<f32> = layout.quads.iter().map(|quad| quad.position[1] - layout.quads[0].position[1]).collect();
+        assert!(rows[..4].iter().all(|&dy| approx(dy, 0.0)) && rows[4..].iter().all(|&dy| approx(dy, 20.0)));
+        assert!(approx(layout.quads[4].position[0], layout.quads[0].position[0]));
+
+        //слово шире строки разбивается по символам
+        let layout = glyphs.layout("AAA", &style(TextAlign::Left, Some(13.0)));
+        assert!(approx(layout.size[1], 60.0));
+    }
+
+    #[test]
+    fn aligns_lines_inside_the_block() {
+        let glyphs = boxes();
+        //блок шириной строки "AV" (22), строка "i" шириной 4 сдвигается на 0, 9 и 18
+        let x = |align| {
+            let layout = glyphs.layout("AV\ni", &style(align, None));
+            assert!(approx(layout.size[0], 22.0));
+            layout.quads[2].position[0]
+        };
+        let left = x(TextAlign::Left);
+        assert!(approx(x(TextAlign::Center) - left, 9.0) && approx(x(TextAlign::Right) - left, 18.0));
+    }
+
+    #[test]
+    fn sdf_of_square() {
+        //квадрат 4x4 в центре поля 12x12
+        let coverage: Vec<f32> = (0..144).map(|i| if (4..8).contains(&(i % 12)) && (4..8).contains(&(i / 12)) { 1.0 } else { 0.0 }).collect();
+        let field = signed_distance_field(12, 12, &coverage, 4.0);
+        assert!(field[5 * 12 + 5] > 0.5); //внутри
+        assert!(field[0] < 0.5); //снаружи
+        assert!(field[5 * 12 + 1] < field[5 * 12 + 3]); //растет к контуру
+        assert!((field[5 * 12 + 4] - 0.5).abs() < 0.1 && (field[5 * 12 + 3] - 0.5).abs() < 0.1); //контур между пикселями 3 и 4
+    }
+
+    proptest! {
+        #[test]
+        fn distance_transform_matches_brute_force(targets in prop::collection::vec(prop::bool::weighted(0.2), 1..40), width in 1usize..8) {
+            let height = targets.len().div_ceil(width);
+            let target = |i: usize| targets.get(i).copied().unwrap_or(false);
+            let field = distance_transform(width, height, target);
+            for (i, &value) in field.iter().enumerate() {
+                let (x, y) = ((i % width) as f32, (i / width) as f32);
+                let expected = (0..width * height)
+                    .filter(|&j| target(j))
+                    .map(|j| (x - (j % width) as f32).powi(2) + (y - (j / width) as f32).powi(2))
+                    .fold(f32::INFINITY, f32::min);
+                if expected.is_finite() {
+                    prop_assert_eq!(value, expected);
+                } else {
+                    prop_assert!(value >= 1e19);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use crate::AppBase;
+use crate::buffer::{BufferBase, find_memorytype_index, submit_once};
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum TextureError {
+    Image(image::ImageError), //файл не найден или формат не поддерживается
+    Vulkan(vk::Result),
+    Pixels { width: u32, height: u32, len: usize }, //пустое изображение или длина данных не равна width * height * 4
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::Image(err) => write!(f, "texture image error: {}", err),
+            TextureError::Vulkan(err) => write!(f, "texture vulkan error: {}", err),
+            TextureError::Pixels { width, height, len } => {
+                write!(f, "{} bytes are not a non-empty {}x{} RGBA8 image", len, width, height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+impl From<image::ImageError> for TextureError {
+    fn from(err: image::ImageError) -> Self {
+        TextureError::Image(err)
+    }
+}
+
+impl From<vk::Result> for TextureError {
+    fn from(err: vk::Result) -> Self {
+        TextureError::Vulkan(err)
+    }
+}
+
+/*настройки текстуры и ее sampler`а*/
+#[derive(Clone, Copy, Debug)]
+pub struct TextureOptions {
+    pub mag_filter: vk::Filter, //фильтрация при увеличении, NEAREST для пиксельной графики
+    pub min_filter: vk::Filter, //фильтрация при уменьшении
+    pub mipmap_mode: vk::SamplerMipmapMode, //смешивание между уровнями mipmap
+    pub address_mode: vk::SamplerAddressMode, //что возвращать за пределами 0..1: REPEAT, CLAMP_TO_EDGE, MIRRORED_REPEAT
+    pub mipmaps: bool, //генерировать уменьшенные копии изображения
+    pub srgb: bool, //данные в sRGB, GPU переведет их в линейное пространство при чтении
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            mipmaps: true,
+            srgb: false, //цвета вершин пишутся как есть, текстуры по умолчанию трактуются так же
+        }
+    }
+}
+
+/*барьер смены layout для уровней mip изображения, layout это внутренняя организация памяти изображения
+под конкретную операцию: копирование, чтение в шейдере, рендер в него*/
+#[allow(clippy::too_many_arguments)]
+pub fn image_barrier(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    base_mip_level: u32,
+    level_count: u32,
+    (old_layout, src_access_mask, src_stage_mask): (vk::ImageLayout, vk::AccessFlags, vk::PipelineStageFlags),
+    (new_layout, dst_access_mask, dst_stage_mask): (vk::ImageLayout, vk::AccessFlags, vk::PipelineStageFlags),
+) {
+    let barrier = vk::ImageMemoryBarrier::default()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_access_mask(src_access_mask) //какие записи должны завершиться до барьера
+        .dst_access_mask(dst_access_mask) //какие операции ждут барьер
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level,
+            level_count,
+            base_array_layer: 0,
+            layer_count: 1,
+        });
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            src_stage_mask,
+            dst_stage_mask,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[barrier],
+        );
+    }
+}
+
+/*изображение в памяти GPU вместе с ImageView и Sampler, готовое для чтения во фрагментном шейдере*/
+pub struct TextureBase {
+    pub image: vk::Image,
+    pub memory: vk::DeviceMemory,
+    pub view: vk::ImageView,
+    pub sampler: vk::Sampler,
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+    pub mip_levels: u32,
+} //vulkan sampled image resources
+
+impl TextureBase {
+    //загрузка PNG/JPEG с диска, формат определяется по содержимому файла
+    pub fn load<P: AsRef<Path>>(
+        app_base: &AppBase,
+        command_pool: vk::CommandPool,
+        path: P,
+        options: &TextureOptions,
+    ) -> Result<Self, TextureError> {
+        let image = image::open(path)?.to_rgba8();
+        let (width, height) = image.dimensions();
+        Self::from_rgba(app_base, command_pool, width, height, image.as_raw(), options)
+    }
+
+    //pixels это width * height пикселей по 4 байта RGBA построчно сверху вниз, пустое изображение недопустимо
+    pub fn from_rgba(
+        app_base: &AppBase,
+        command_pool: vk::CommandPool,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        options: &TextureOptions,
+    ) -> Result<Self, TextureError> {
+        //размер считается в usize, в u32 он переполняется уже на 32768x32768
+        let expected_len = (width as usize).checked_mul(height as usize).and_then(|count| count.checked_mul(4));
+        if width == 0 || height == 0 || expected_len != Some(pixels.len()) {
+            return Err(TextureError::Pixels {
+                width,
+                height,
+                len: pixels.len(),
+            });
+        }
+        let device = &app_base.device;
+
+        let format = if options.srgb {
+            vk::Format::R8G8B8A8_SRGB
+        } else {
+            vk::Format::R8G8B8A8_UNORM
+        };
+
+        //уменьшенные копии строятся через vkCmdBlitImage с линейной фильтрацией, формат должен это поддерживать
+        let format_properties = unsafe {
+            app_base
+                .instance
+                .get_physical_device_format_properties(app_base.physical_device, format)
+        };
+        let linear_blit = format_properties
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR);
+
+        let mip_levels = if options.mipmaps && linear_blit {
+            32 - width.max(height).leading_zeros() //floor(log2(max)) + 1, до размера 1x1
+        } else {
+            1
+        };
+
+        let extent = vk::Extent2D { width, height };
+
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(extent.into())
+            .mip_levels(mip_levels)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL) //порядок пикселей выбирает драйвер, CPU напрямую в такое изображение не пишет
+            .usage(
+                vk::ImageUsageFlags::TRANSFER_SRC //источник для генерации mip уровней
+                    | vk::ImageUsageFlags::TRANSFER_DST
+                    | vk::ImageUsageFlags::SAMPLED,
+            )
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+
+        let image = unsafe { device.create_image(&image_info, None)? };
+        //если что-то дальше не удалось, созданное до ошибки уничтожается, удаление null handle допустимо
+        let mut memory = vk::DeviceMemory::null();
+        let mut view = vk::ImageView::null();
+        let sampler = (|| -> VkResult<vk::Sampler> {
+            let memory_req = unsafe { device.get_image_memory_requirements(image) };
+            let memory_index = find_memorytype_index(
+                &memory_req,
+                &app_base.device_memory_properties,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL, //быстрая видеопамять, CPU к ней доступа не имеет
+            )
+            .ok_or(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)?;
+            memory = unsafe {
+                device.allocate_memory(
+                    &vk::MemoryAllocateInfo::default()
+                        .allocation_size(memory_req.size)
+                        .memory_type_index(memory_index),
+                    None,
+                )?
+            };
+            unsafe { device.bind_image_memory(image, memory, 0)? };
+
+            //staging буффер: CPU пишет пиксели в видимую ему память, GPU копирует их в изображение
+            let staging = BufferBase::new(
+                device,
+                &app_base.device_memory_properties,
+                pixels.len() as vk::DeviceSize,
+                vk::BufferUsageFlags::TRANSFER_SRC,
+            )?;
+            staging.write(0, pixels);
+
+            let upload = submit_once(device, command_pool, app_base.present_queue, |command_buffer| {
+                image_barrier(
+                    device,
+                    command_buffer,
+                    image,
+                    0,
+                    mip_levels,
+                    (vk::ImageLayout::UNDEFINED, vk::AccessFlags::empty(), vk::PipelineStageFlags::TOP_OF_PIPE),
+                    (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::AccessFlags::TRANSFER_WRITE, vk::PipelineStageFlags::TRANSFER),
+                );
+
+                let region = vk::BufferImageCopy::default()
+                    .image_subresource(vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    })
+                    .image_extent(extent.into());
+                unsafe {
+                    device.cmd_copy_buffer_to_image(
+                        command_buffer,
+                        staging.buffer,
+                        image,
+                        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        &[region],
+                    );
+                }
+
+                generate_mipmaps(device, command_buffer, image, extent, mip_levels);
+            });
+            unsafe { staging.destroy(device) };
+            upload?;
+
+            let view_info = vk::ImageViewCreateInfo::default()
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(format)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: mip_levels,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                })
+                .image(image);
+            view = unsafe { device.create_image_view(&view_info, None)? };
+
+            Self::create_sampler(device, options, mip_levels)
+        })();
+        let sampler = match sampler {
+            Ok(sampler) => sampler,
+            Err(err) => {
+                unsafe {
+                    device.destroy_image_view(view, None);
+                    device.destroy_image(image, None);
+                    device.free_memory(memory, None);
+                }
+                return Err(err.into());
+            }
+        };
+
+        Ok(Self {
+            image,
+            memory,
+            view,
+            sampler,
+            extent,
+            format,
+            mip_levels,
+        })
+    }
+
+    pub(crate) fn create_sampler(device: &Device, options: &TextureOptions, mip_levels: u32) -> VkResult<vk::Sampler> {
+        let sampler_info = vk::SamplerCreateInfo::default()
+            .mag_filter(options.mag_filter)
+            .min_filter(options.min_filter)
+            .mipmap_mode(options.mipmap_mode)
+            .address_mode_u(options.address_mode)
+            .address_mode_v(options.address_mode)
+            .address_mode_w(options.address_mode)
+            .min_lod(0.0)
+            .max_lod(mip_levels as f32)
+            .border_color(vk::BorderColor::FLOAT_TRANSPARENT_BLACK);
+        unsafe { device.create_sampler(&sampler_info, None) }
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            device.destroy_sampler(self.sampler, None);
+            device.destroy_image_view(self.view, None);
+            device.destroy_image(self.image, None);
+            device.free_memory(self.memory, None);
+        }
+    }
+}
+
+/*каждый уровень mip получается уменьшением предыдущего в два раза, после копирования уровень
+переводится в SHADER_READ_ONLY_OPTIMAL, на входе все уровни в TRANSFER_DST_OPTIMAL*/
+fn generate_mipmaps(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    mip_levels: u32,
+) {
+    let mut width = extent.width as i32;
+    let mut height = extent.height as i32;
+
+    for level in 1..mip_levels {
+        image_barrier(
+            device,
+            command_buffer,
+            image,
+            level - 1,
+            1,
+            (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::AccessFlags::TRANSFER_WRITE, vk::PipelineStageFlags::TRANSFER),
+            (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::AccessFlags::TRANSFER_READ, vk::PipelineStageFlags::TRANSFER),
+        );
+
+        let next_width = (width / 2).max(1);
+        let next_height = (height / 2).max(1);
+        let blit = vk::ImageBlit::default()
+            .src_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: level - 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .src_offsets([vk::Offset3D::default(), vk::Offset3D { x: width, y: height, z: 1 }])
+            .dst_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: level,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .dst_offsets([vk::Offset3D::default(), vk::Offset3D { x: next_width, y: next_height, z: 1 }]);
+
+        unsafe {
+            device.cmd_blit_image(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[blit],
+                vk::Filter::LINEAR,
+            );
+        }
+
+        image_barrier(
+            device,
+            command_buffer,
+            image,
+            level - 1,
+            1,
+            (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::AccessFlags::TRANSFER_READ, vk::PipelineStageFlags::TRANSFER),
+            (vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::AccessFlags::SHADER_READ, vk::PipelineStageFlags::FRAGMENT_SHADER),
+        );
+
+        width = next_width;
+        height = next_height;
+    }
+
+    //последний уровень был только приемником копирования
+    image_barrier(
+        device,
+        command_buffer,
+        image,
+        mip_levels - 1,
+        1,
+        (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::AccessFlags::TRANSFER_WRITE, vk::PipelineStageFlags::TRANSFER),
+        (vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::AccessFlags::SHADER_READ, vk::PipelineStageFlags::FRAGMENT_SHADER),
+    );
+}
+
+/*раскладка и пул наборов дескрипторов для текстур, один набор это одна текстура
+layout(set = 0, binding = 0) uniform sampler2D texSampler в sprite.frag*/
+pub struct TextureDescriptors {
+    pub layout: vk::DescriptorSetLayout,
+    pub pool: vk::DescriptorPool,
+} //vulkan texture descriptor resources
+
+impl TextureDescriptors {
+    pub fn new(device: &Device, max_textures: u32) -> VkResult<Self> {
+        let bindings = [vk::DescriptorSetLayoutBinding::default()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER) //изображение и sampler в одном дескрипторе
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)];
+        let layout = unsafe {
+            device.create_descriptor_set_layout(
+                &vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings),
+                None,
+            )?
+        };
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: max_textures,
+        }];
+        let pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::default()
+                    .flags(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET) //наборы можно освобождать по одному
+                    .max_sets(max_textures)
+                    .pool_sizes(&pool_sizes),
+                None,
+            )?
+        };
+
+        Ok(Self { layout, pool })
+    }
+
+    //набор дескрипторов для текстуры, передается в Batcher::draw_sprite
+    pub fn allocate(&self, device: &Device, texture: &TextureBase) -> VkResult<vk::DescriptorSet> {
+        let set_layouts = [self.layout];
+        let descriptor_set = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::default()
+                    .descriptor_pool(self.pool)
+                    .set_layouts(&set_layouts),
+            )?[0]
+        };
+
+        let image_infos = [vk::DescriptorImageInfo {
+            sampler: texture.sampler,
+            image_view: texture.view,
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }];
+        let write = vk::WriteDescriptorSet::default()
+            .dst_set(descriptor_set)
+            .dst_binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&image_infos);
+        unsafe { device.update_descriptor_sets(&[write], &[]) };
+
+        Ok(descriptor_set)
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            device.destroy_descriptor_pool(self.pool, None); //наборы освобождаются вместе с пулом
+            device.destroy_descriptor_set_layout(self.layout, None);
+        }
+    }
+}
+
+/*прямоугольная часть текстуры для отрисовки через Batcher::draw_sprite*/
+#[derive(Clone, Copy, Debug)]
+pub struct Sprite {
+    pub texture: vk::DescriptorSet,
+    pub uv_min: [f32; 2], //левый верхний угол в текстурных координатах 0..1
+    pub uv_max: [f32; 2], //правый нижний угол
+    pub size: [f32; 2],   //размер в пикселях по умолчанию
+}
+
+impl Sprite {
+    //спрайт на всю текстуру в ее натуральном размере
+    pub fn new(texture: vk::DescriptorSet, texture_base: &TextureBase) -> Self {
+        Self {
+            texture,
+            uv_min: [0.0, 0.0],
+            uv_max: [1.0, 1.0],
+            size: [texture_base.extent.width as f32, texture_base.extent.height as f32],
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use crate::AppBase;
+use ash::Device;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const MAX_SCOPES: u32 = 16; //участков на кадр, лишние не замеряются
+const AVERAGE_WINDOW: usize = 60; //кадров в скользящем среднем
+
+/*статистика по последним window значениям: среднее по сумме, которая обновляется при добавлении,
+минимум, максимум и перцентили считаются по запросу*/
+#[derive(Clone, Debug)]
+pub struct RollingStats {
+    samples: VecDeque<f64>,
+    sum: f64,
+    window: usize,
+}
+
+impl RollingStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(window),
+            sum: 0.0,
+            window: window.max(1),
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.samples.len() == self.window {
+            self.sum -= self.samples.pop_front().unwrap_or_default();
+        }
+        self.samples.push_back(value);
+        self.sum += value;
+    }
+
+    pub fn average(&self) -> f64 {
+        if self.samples.is_empty() { 0.0 } else { self.sum / self.samples.len() as f64 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn min(&self) -> f64 {
+        self.samples.iter().copied().reduce(f64::min).unwrap_or_default()
+    }
+
+    pub fn max(&self) -> f64 {
+        self.samples.iter().copied().reduce(f64::max).unwrap_or_default()
+    }
+
+    //значение ближайшего ранга для доли p от 0 до 1, p = 0.99 это время, которое не превысили 99% кадров
+    pub fn percentile(&self, p: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let rank = (p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64).round() as usize;
+        sorted[rank]
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "min {:.2} avg {:.2} p95 {:.2} p99 {:.2} max {:.2}",
+            self.min(),
+            self.average(),
+            self.percentile(0.95),
+            self.percentile(0.99),
+            self.max()
+        )
+    }
+}
+
+/*время CPU на этапы одного кадра в CommandBase::render_frame*/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameTimings {
+    pub acquire: Duration, //ожидание fence кадра и acquire_next_image
+    pub submit: Duration, //запись command buffer и queue_submit
+    pub present: Duration, //вызов queue_present, при FIFO/MAILBOX может блокироваться до vsync
+}
+
+/*статистика кадров на CPU в миллисекундах: интервал между кадрами и этапы из FrameTimings*/
+pub struct FrameStats {
+    pub frame: RollingStats,
+    pub acquire: RollingStats,
+    pub submit: RollingStats,
+    pub present: RollingStats,
+    last_frame: Option<Instant>,
+}
+
+impl FrameStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            frame: RollingStats::new(window),
+            acquire: RollingStats::new(window),
+            submit: RollingStats::new(window),
+            present: RollingStats::new(window),
+            last_frame: None,
+        }
+    }
+
+    //вызывается после каждого показанного кадра, интервал считается от предыдущего вызова
+    pub fn record(&mut self, now: Instant, timings: &FrameTimings) {
+        if let Some(last_frame) = self.last_frame {
+            self.frame.push(as_ms(now - last_frame));
+        }
+        self.last_frame = Some(now);
+        self.acquire.push(as_ms(timings.acquire));
+        self.submit.push(as_ms(timings.submit));
+        self.present.push(as_ms(timings.present));
+    }
+
+    //после паузы (свернутое окно, пересоздание swapchain) длинный интервал не должен попасть в статистику
+    pub fn skip(&mut self) {
+        self.last_frame = None;
+    }
+
+    pub fn fps(&self) -> f64 {
+        let average = self.frame.average();
+        if average > 0.0 { 1000.0 / average } else { 0.0 }
+    }
+
+    pub fn report(&self) -> String {
+        format!(
+            "frame: {}\nacquire: {}\nsubmit: {}\npresent: {}",
+            self.frame.summary(),
+            self.acquire.summary(),
+            self.submit.summary(),
+            self.present.summary()
+        )
+    }
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/*ограничение частоты кадров независимо от present mode: поток спит до следующего срока,
+срок сдвигается на интервал от предыдущего срока, а не от момента пробуждения, чтобы неточность sleep не накапливалась*/
+pub struct FrameLimiter {
+    interval: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl FrameLimiter {
+    //fps = 0 или None без ограничения
+    pub fn new(fps: Option<u32>) -> Self {
+        Self {
+            interval: fps.filter(|&fps| fps > 0).map(|fps| Duration::from_secs_f64(1.0 / fps as f64)),
+            deadline: None,
+        }
+    }
+
+    pub fn wait(&mut self) {
+        let Some(interval) = self.interval else { return };
+        let now = Instant::now();
+        let deadline = next_deadline(self.deadline, now, interval);
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        }
+        self.deadline = Some(deadline);
+    }
+}
+
+//если отстали больше чем на интервал, отсчет начинается заново от now, иначе кадры пошли бы без пауз догоняя
+fn next_deadline(previous: Option<Instant>, now: Instant, interval: Duration) -> Instant {
+    match previous {
+        Some(previous) if previous + interval + interval > now => previous + interval,
+        _ => now,
+    }
+}
+
+/*разница двух меток в миллисекундах: значимы только младшие valid_bits бит счетчика,
+поэтому разность берется по модулю 2^valid_bits и переживает переполнение счетчика между метками*/
+fn ticks_to_ms(start: u64, end: u64, valid_bits: u32, timestamp_period: f32) -> f64 {
+    let mask = if valid_bits >= 64 { u64::MAX } else { (1u64 << valid_bits) - 1 };
+    (end.wrapping_sub(start) & mask) as f64 * timestamp_period as f64 / 1_000_000.0
+}
+
+/*замер времени на GPU: у каждого кадра в полете свой query pool, поэтому результаты кадра читаются
+без ожидания, когда CommandBase::render_frame уже дождался его fence, то есть через MAX_FRAMES_IN_FLIGHT кадров.
+Участок это пара меток cmd_write_timestamp вокруг прохода, имена участков хранятся до чтения результатов*/
+pub struct GpuTimer {
+    query_pools: Vec<vk::QueryPool>,
+    scopes: Vec<Vec<String>>, //записанные в command buffer кадра участки, индекс участка = индекс пары запросов
+    timestamp_period: f32, //наносекунд на тик счетчика, из vk::PhysicalDeviceLimits
+    valid_bits: u32,
+    averages: Vec<(String, RollingStats)>, //в порядке первого появления участка
+} //vulkan timestamp query resources
+
+//по пулу на кадр в полете, если создать не удалось, уже созданные пулы уничтожаются
+pub(crate) fn create_query_pools(device: &Device, info: &vk::QueryPoolCreateInfo, count: usize) -> VkResult<Vec<vk::QueryPool>> {
+    let mut query_pools = Vec::with_capacity(count);
+    for _ in 0..count {
+        match unsafe { device.create_query_pool(info, None) } {
+            Ok(query_pool) => query_pools.push(query_pool),
+            Err(err) => {
+                for &query_pool in &query_pools {
+                    unsafe { device.destroy_query_pool(query_pool, None) };
+                }
+                return Err(err);
+            }
+        }
+    }
+    Ok(query_pools)
+}
+
+impl GpuTimer {
+    /*None если очередь не пишет метки времени (timestamp_valid_bits = 0) или период неизвестен*/
+    pub fn new(app_base: &AppBase, limits: &vk::PhysicalDeviceLimits, frames_in_flight: usize) -> VkResult<Option<Self>> {
+        let valid_bits = unsafe {
+            app_base
+                .instance
+                .get_physical_device_queue_family_properties(app_base.physical_device)[app_base.queue_family_index as usize]
+                .timestamp_valid_bits
+        };
+        if valid_bits == 0 || limits.timestamp_period <= 0.0 {
+            return Ok(None);
+        }
+
+        let pool_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(MAX_SCOPES * 2); //начало и конец каждого участка
+        let query_pools = create_query_pools(&app_base.device, &pool_info, frames_in_flight)?;
+
+        Ok(Some(Self {
+            query_pools,
+            scopes: vec![Vec::new(); frames_in_flight],
+            timestamp_period: limits.timestamp_period,
+            valid_bits,
+            averages: Vec::new(),
+        }))
+    }
+
+    /*вызывается в начале записи command buffer кадра, после ожидания его fence:
+    забирает результаты прошлого использования пула и сбрасывает пул для новых меток*/
+    pub fn begin_frame(&mut self, device: &Device, command_buffer: vk::CommandBuffer, frame: usize) {
+        let scopes = std::mem::take(&mut self.scopes[frame]);
+        if !scopes.is_empty() {
+            let mut timestamps = vec![0u64; scopes.len() * 2];
+            //без WAIT: fence уже сигнализирован, а NOT_READY (кадр не был отправлен) просто пропускаем
+            let result = unsafe {
+                device.get_query_pool_results(
+                    self.query_pools[frame],
+                    0,
+                    &mut timestamps,
+                    vk::QueryResultFlags::TYPE_64,
+                )
+            };
+            if result.is_ok() {
+                let frame_ms = ticks_to_ms(timestamps[0], timestamps[timestamps.len() - 1], self.valid_bits, self.timestamp_period);
+                self.record("frame", frame_ms);
+                for (index, name) in scopes.iter().enumerate() {
+                    let ms = ticks_to_ms(timestamps[index * 2], timestamps[index * 2 + 1], self.valid_bits, self.timestamp_period);
+                    self.record(name, ms);
+                }
+            }
+        }
+        unsafe { device.cmd_reset_query_pool(command_buffer, self.query_pools[frame], 0, MAX_SCOPES * 2) };
+    }
+
+    fn record(&mut self, name: &str, ms: f64) {
+        match self.averages.iter_mut().find(|(scope, _)| scope == name) {
+            Some((_, average)) => average.push(ms),
+            None => {
+                let mut average = RollingStats::new(AVERAGE_WINDOW);
+                average.push(ms);
+                self.averages.push((name.to_string(), average));
+            }
+        }
+    }
+
+    /*метка начала участка, вызывается вне render pass: TOP_OF_PIPE означает что все предыдущие команды уже начаты,
+    возвращает индекс для end_scope или None если участков в кадре больше MAX_SCOPES*/
+    pub fn begin_scope(&mut self, device: &Device, command_buffer: vk::CommandBuffer, frame: usize, name: &str) -> Option<u32> {
+        let index = self.scopes[frame].len() as u32;
+        if index >= MAX_SCOPES {
+            return None;
+        }
+        self.scopes[frame].push(name.to_string());
+        unsafe {
+            device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, self.query_pools[frame], index * 2)
+        };
+        Some(index)
+    }
+
+    //метка конца: BOTTOM_OF_PIPE записывается когда все команды участка завершены
+    pub fn end_scope(&self, device: &Device, command_buffer: vk::CommandBuffer, frame: usize, scope: Option<u32>) {
+        if let Some(index) = scope {
+            unsafe {
+                device.cmd_write_timestamp(
+                    command_buffer,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    self.query_pools[frame],
+                    index * 2 + 1,
+                )
+            };
+        }
+    }
+
+    /*f между метками участка name, без таймера просто вызывает f,
+    имя строится лениво, чтобы не выделять строки когда замер выключен*/
+    pub fn scoped<R>(
+        timer: Option<&mut Self>,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        frame: usize,
+        name: impl FnOnce() -> String,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        match timer {
+            Some(timer) => {
+                let scope = timer.begin_scope(device, command_buffer, frame, &name());
+                let result = f();
+                timer.end_scope(device, command_buffer, frame, scope);
+                result
+            }
+            None => f(),
+        }
+    }
+
+    //средние времена участков в миллисекундах, первым идет весь кадр от первой до последней метки
+    pub fn averages(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.averages.iter().map(|(name, average)| (name.as_str(), average.average()))
+    }
+
+    pub fn report(&self) -> String {
+        self.averages()
+            .map(|(name, ms)| format!("{name} {ms:.2}"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        unsafe {
+            for &query_pool in &self.query_pools {
+                device.destroy_query_pool(query_pool, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_average_drops_old_samples() {
+        let mut average = RollingStats::new(2);
+        assert_eq!(average.average(), 0.0);
+        average.push(1.0);
+        average.push(3.0);
+        assert_eq!(average.average(), 2.0);
+        average.push(5.0);
+        assert_eq!(average.average(), 4.0);
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let mut stats = RollingStats::new(100);
+        for value in (1..=100).rev() {
+            stats.push(value as f64);
+        }
+        assert_eq!(stats.min(), 1.0);
+        assert_eq!(stats.max(), 100.0);
+        assert_eq!(stats.percentile(0.0), 1.0);
+        assert_eq!(stats.percentile(0.5), 51.0);
+        assert_eq!(stats.percentile(0.99), 99.0);
+    }
+
+    #[test]
+    fn limiter_deadline_does_not_accumulate_lag() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(10);
+        assert_eq!(next_deadline(None, start, interval), start);
+        //проснулись чуть позже срока, следующий срок все равно отсчитывается от предыдущего
+        assert_eq!(next_deadline(Some(start), start + Duration::from_millis(12), interval), start + interval);
+        //отстали на несколько кадров, отсчет заново
+        let late = start + Duration::from_millis(50);
+        assert_eq!(next_deadline(Some(start), late, interval), late);
+    }
+
+    #[test]
+    fn ticks_wrap_within_valid_bits() {
+        //период 1000 нс: 2000 тиков это 2 мс
+        assert_eq!(ticks_to_ms(1_000, 3_000, 64, 1000.0), 2.0);
+        //36-битный счетчик переполнился между метками
+        let wrap = 1u64 << 36;
+        assert_eq!(ticks_to_ms(wrap - 1_000, 1_000, 36, 1000.0), 2.0);
+    }
+}
@@ -0,0 +1,704 @@
+/*триангуляция произвольных 2D контуров методом отсечения ушей (ear clipping), порт алгоритма earcut:
+поддерживает вогнутые многоугольники, дыры (дыра соединяется с внешним контуром мостом и становится его частью)
+и контуры которые касаются сами себя в вершинах, результат это список индексов для Batcher::draw_mesh*/
+
+const NONE: usize = usize::MAX;
+
+#[derive(Clone, Copy)]
+struct Node {
+    i: u32, //индекс вершины во входных данных
+    x: f64,
+    y: f64,
+    prev: usize,
+    next: usize,
+    steiner: bool, //дыра из одной точки, такую вершину нельзя выкидывать при фильтрации
+}
+
+//контуры хранятся как кольцевые двусвязные списки поверх одного вектора
+struct Nodes {
+    nodes: Vec<Node>,
+}
+
+impl Nodes {
+    fn insert(&mut self, i: u32, [x, y]: [f32; 2], last: usize) -> usize {
+        let index = self.nodes.len();
+        let mut node = Node {
+            i,
+            x: x as f64,
+            y: y as f64,
+            prev: index,
+            next: index,
+            steiner: false,
+        };
+        if last != NONE {
+            node.next = self.nodes[last].next;
+            node.prev = last;
+            let last_next = self.nodes[last].next;
+            self.nodes[last_next].prev = index;
+            self.nodes[last].next = index;
+        }
+        self.nodes.push(node);
+        index
+    }
+
+    fn remove(&mut self, p: usize) {
+        let Node { prev, next, .. } = self.nodes[p];
+        self.nodes[next].prev = prev;
+        self.nodes[prev].next = next;
+    }
+
+    fn prev(&self, p: usize) -> usize {
+        self.nodes[p].prev
+    }
+
+    fn next(&self, p: usize) -> usize {
+        self.nodes[p].next
+    }
+
+    //удвоенная ориентированная площадь треугольника, знак показывает направление поворота p -> q -> r
+    fn area(&self, p: usize, q: usize, r: usize) -> f64 {
+        let (p, q, r) = (&self.nodes[p], &self.nodes[q], &self.nodes[r]);
+        (q.y - p.y) * (r.x - q.x) - (q.x - p.x) * (r.y - q.y)
+    }
+
+    fn equals(&self, a: usize, b: usize) -> bool {
+        self.nodes[a].x == self.nodes[b].x && self.nodes[a].y == self.nodes[b].y
+    }
+
+    //создает кольцо из контура в нужном направлении обхода, возвращает последний узел или NONE для пустого контура
+    fn linked_list(&mut self, points: &[[f32; 2]], offset: u32, clockwise: bool) -> usize {
+        let mut last = NONE;
+        if clockwise == (signed_area(points) > 0.0) {
+            for (i, &point) in points.iter().enumerate() {
+                last = self.insert(offset + i as u32, point, last);
+            }
+        } else {
+            for (i, &point) in points.iter().enumerate().rev() {
+                last = self.insert(offset + i as u32, point, last);
+            }
+        }
+        if last != NONE && self.equals(last, self.next(last)) {
+            let next = self.next(last);
+            self.remove(last);
+            last = next;
+        }
+        last
+    }
+
+    //убирает повторяющиеся и лежащие на одной прямой вершины
+    fn filter_points(&mut self, start: usize, end: usize) -> usize {
+        let mut end = if end == NONE { start } else { end };
+        let mut p = start;
+        loop {
+            let mut again = false;
+            let next = self.next(p);
+            if !self.nodes[p].steiner
+                && (self.equals(p, next) || self.area(self.prev(p), p, next) == 0.0)
+            {
+                self.remove(p);
+                p = self.prev(p);
+                end = p;
+                if p == self.next(p) {
+                    break;
+                }
+                again = true;
+            } else {
+                p = next;
+            }
+            if !again && p == end {
+                break;
+            }
+        }
+        end
+    }
+
+    fn earcut_linked(&mut self, ear: usize, triangles: &mut Vec<u32>, pass: u8) {
+        if ear == NONE {
+            return;
+        }
+        let mut ear = ear;
+        let mut stop = ear;
+
+        while self.prev(ear) != self.next(ear) {
+            let prev = self.prev(ear);
+            let next = self.next(ear);
+
+            if self.is_ear(ear) {
+                triangles.extend_from_slice(&[self.nodes[prev].i, self.nodes[ear].i, self.nodes[next].i]);
+                self.remove(ear);
+                ear = self.next(next);
+                stop = ear;
+                continue;
+            }
+
+            ear = next;
+
+            if ear == stop {
+                //ушей не нашлось: сначала чистим вырожденные вершины, затем лечим локальные самопересечения,
+                //в крайнем случае режем многоугольник диагональю на два и триангулируем их отдельно
+                match pass {
+                    0 => {
+                        let ear = self.filter_points(ear, NONE);
+                        self.earcut_linked(ear, triangles, 1);
+                    }
+                    1 => {
+                        let ear = self.filter_points(ear, NONE);
+                        let ear = self.cure_local_intersections(ear, triangles);
+                        self.earcut_linked(ear, triangles, 2);
+                    }
+                    _ => self.split_earcut(ear, triangles),
+                }
+                break;
+            }
+        }
+    }
+
+    //ухо это выпуклая вершина, в треугольнике которой нет других вершин контура
+    fn is_ear(&self, ear: usize) -> bool {
+        let a = self.prev(ear);
+        let c = self.next(ear);
+        if self.area(a, ear, c) >= 0.0 {
+            return false; //вогнутая вершина
+        }
+
+        let (na, nb, nc) = (&self.nodes[a], &self.nodes[ear], &self.nodes[c]);
+        let (x0, x1) = (na.x.min(nb.x).min(nc.x), na.x.max(nb.x).max(nc.x));
+        let (y0, y1) = (na.y.min(nb.y).min(nc.y), na.y.max(nb.y).max(nc.y));
+
+        let mut p = self.next(c);
+        while p != a {
+            let np = &self.nodes[p];
+            if np.x >= x0
+                && np.x <= x1
+                && np.y >= y0
+                && np.y <= y1
+                && point_in_triangle([na.x, na.y], [nb.x, nb.y], [nc.x, nc.y], [np.x, np.y])
+                && self.area(self.prev(p), p, self.next(p)) >= 0.0
+            {
+                return false;
+            }
+            p = self.next(p);
+        }
+        true
+    }
+
+    fn cure_local_intersections(&mut self, start: usize, triangles: &mut Vec<u32>) -> usize {
+        let mut start = start;
+        let mut p = start;
+        loop {
+            let a = self.prev(p);
+            let b = self.next(self.next(p));
+
+            if !self.equals(a, b)
+                && self.intersects(a, p, self.next(p), b)
+                && self.locally_inside(a, b)
+                && self.locally_inside(b, a)
+            {
+                triangles.extend_from_slice(&[self.nodes[a].i, self.nodes[p].i, self.nodes[b].i]);
+                let next = self.next(p);
+                self.remove(p);
+                self.remove(next);
+                p = b;
+                start = b;
+            }
+            p = self.next(p);
+            if p == start {
+                break;
+            }
+        }
+        self.filter_points(p, NONE)
+    }
+
+    fn split_earcut(&mut self, start: usize, triangles: &mut Vec<u32>) {
+        let mut a = start;
+        loop {
+            let mut b = self.next(self.next(a));
+            while b != self.prev(a) {
+                if self.nodes[a].i != self.nodes[b].i && self.is_valid_diagonal(a, b) {
+                    let c = self.split_polygon(a, b);
+                    let a = self.filter_points(a, self.next(a));
+                    let c = self.filter_points(c, self.next(c));
+                    self.earcut_linked(a, triangles, 0);
+                    self.earcut_linked(c, triangles, 0);
+                    return;
+                }
+                b = self.next(b);
+            }
+            a = self.next(a);
+            if a == start {
+                break;
+            }
+        }
+    }
+
+    fn eliminate_holes<H: AsRef<[[f32; 2]]>>(&mut self, holes: &[H], mut offset: u32, outer: usize) -> usize {
+        let mut queue = Vec::with_capacity(holes.len());
+        for hole in holes {
+            let hole = hole.as_ref();
+            let list = self.linked_list(hole, offset, false);
+            offset += hole.len() as u32;
+            if list == NONE {
+                continue;
+            }
+            if list == self.next(list) {
+                self.nodes[list].steiner = true;
+            }
+            queue.push(self.leftmost(list));
+        }
+        //дыры обрабатываются слева направо, так мосты не пересекают еще не присоединенные дыры
+        queue.sort_by(|&a, &b| self.nodes[a].x.total_cmp(&self.nodes[b].x));
+
+        let mut outer = outer;
+        for hole in queue {
+            outer = self.eliminate_hole(hole, outer);
+        }
+        outer
+    }
+
+    fn eliminate_hole(&mut self, hole: usize, outer: usize) -> usize {
+        let bridge = self.find_hole_bridge(hole, outer);
+        if bridge == NONE {
+            return outer;
+        }
+        let bridge_reverse = self.split_polygon(bridge, hole);
+        self.filter_points(bridge_reverse, self.next(bridge_reverse));
+        self.filter_points(bridge, self.next(bridge))
+    }
+
+    //ищем вершину внешнего контура которую видно из самой левой точки дыры
+    fn find_hole_bridge(&self, hole: usize, outer: usize) -> usize {
+        let (hx, hy) = (self.nodes[hole].x, self.nodes[hole].y);
+        let mut qx = f64::NEG_INFINITY;
+        let mut m = NONE;
+
+        //луч влево из точки дыры, ищем ближайшее пересечение с ребром контура
+        let mut p = outer;
+        loop {
+            let (np, nn) = (&self.nodes[p], &self.nodes[self.next(p)]);
+            if hy <= np.y && hy >= nn.y && nn.y != np.y {
+                let x = np.x + (hy - np.y) * (nn.x - np.x) / (nn.y - np.y);
+                if x <= hx && x > qx {
+                    qx = x;
+                    m = if np.x < nn.x { p } else { self.next(p) };
+                    if x == hx {
+                        return m; //дыра касается контура, мост нулевой длины
+                    }
+                }
+            }
+            p = self.next(p);
+            if p == outer {
+                break;
+            }
+        }
+        if m == NONE {
+            return NONE;
+        }
+
+        //если внутри треугольника (точка дыры, пересечение, m) есть вершины контура, берем ту что с наименьшим углом к лучу
+        let stop = m;
+        let (mx, my) = (self.nodes[m].x, self.nodes[m].y);
+        let mut tan_min = f64::INFINITY;
+        let mut p = m;
+        loop {
+            let (px, py) = (self.nodes[p].x, self.nodes[p].y);
+            if hx >= px
+                && px >= mx
+                && hx != px
+                && point_in_triangle(
+                    [if hy < my { hx } else { qx }, hy],
+                    [mx, my],
+                    [if hy < my { qx } else { hx }, hy],
+                    [px, py],
+                )
+            {
+                let tan = (hy - py).abs() / (hx - px);
+                if self.locally_inside(p, hole)
+                    && (tan < tan_min
+                        || (tan == tan_min
+                            && (px > self.nodes[m].x
+                                || (px == self.nodes[m].x && self.sector_contains_sector(m, p)))))
+                {
+                    m = p;
+                    tan_min = tan;
+                }
+            }
+            p = self.next(p);
+            if p == stop {
+                break;
+            }
+        }
+        m
+    }
+
+    fn sector_contains_sector(&self, m: usize, p: usize) -> bool {
+        self.area(self.prev(m), m, self.prev(p)) < 0.0 && self.area(self.next(p), m, self.next(m)) < 0.0
+    }
+
+    fn leftmost(&self, start: usize) -> usize {
+        let mut p = start;
+        let mut leftmost = start;
+        loop {
+            let (np, nl) = (&self.nodes[p], &self.nodes[leftmost]);
+            if np.x < nl.x || (np.x == nl.x && np.y < nl.y) {
+                leftmost = p;
+            }
+            p = self.next(p);
+            if p == start {
+                break;
+            }
+        }
+        leftmost
+    }
+
+    //диагональ a-b лежит внутри многоугольника и не пересекает его ребра
+    fn is_valid_diagonal(&self, a: usize, b: usize) -> bool {
+        let ib = self.nodes[b].i;
+        self.nodes[self.next(a)].i != ib
+            && self.nodes[self.prev(a)].i != ib
+            && !self.intersects_polygon(a, b)
+            && ((self.locally_inside(a, b)
+                && self.locally_inside(b, a)
+                && self.middle_inside(a, b)
+                && (self.area(self.prev(a), a, self.prev(b)) != 0.0
+                    || self.area(a, self.prev(b), b) != 0.0))
+                || (self.equals(a, b)
+                    && self.area(self.prev(a), a, self.next(a)) > 0.0
+                    && self.area(self.prev(b), b, self.next(b)) > 0.0))
+    }
+
+    fn intersects(&self, p1: usize, q1: usize, p2: usize, q2: usize) -> bool {
+        let o1 = sign(self.area(p1, q1, p2));
+        let o2 = sign(self.area(p1, q1, q2));
+        let o3 = sign(self.area(p2, q2, p1));
+        let o4 = sign(self.area(p2, q2, q1));
+
+        (o1 != o2 && o3 != o4)
+            || (o1 == 0 && self.on_segment(p1, p2, q1)) //p2 лежит на отрезке p1q1
+            || (o2 == 0 && self.on_segment(p1, q2, q1))
+            || (o3 == 0 && self.on_segment(p2, p1, q2))
+            || (o4 == 0 && self.on_segment(p2, q1, q2))
+    }
+
+    //q лежит в прямоугольнике отрезка pr, вызывается только для точек на одной прямой
+    fn on_segment(&self, p: usize, q: usize, r: usize) -> bool {
+        let (p, q, r) = (&self.nodes[p], &self.nodes[q], &self.nodes[r]);
+        q.x <= p.x.max(r.x) && q.x >= p.x.min(r.x) && q.y <= p.y.max(r.y) && q.y >= p.y.min(r.y)
+    }
+
+    fn intersects_polygon(&self, a: usize, b: usize) -> bool {
+        let (ia, ib) = (self.nodes[a].i, self.nodes[b].i);
+        let mut p = a;
+        loop {
+            let next = self.next(p);
+            let (ip, inext) = (self.nodes[p].i, self.nodes[next].i);
+            if ip != ia && inext != ia && ip != ib && inext != ib && self.intersects(p, next, a, b) {
+                return true;
+            }
+            p = next;
+            if p == a {
+                return false;
+            }
+        }
+    }
+
+    //диагональ a-b выходит из вершины a внутрь многоугольника
+    fn locally_inside(&self, a: usize, b: usize) -> bool {
+        let (prev, next) = (self.prev(a), self.next(a));
+        if self.area(prev, a, next) < 0.0 {
+            self.area(a, b, next) >= 0.0 && self.area(a, prev, b) >= 0.0
+        } else {
+            self.area(a, b, prev) < 0.0 || self.area(a, next, b) < 0.0
+        }
+    }
+
+    //середина диагонали внутри многоугольника, проверка лучом по правилу четности
+    fn middle_inside(&self, a: usize, b: usize) -> bool {
+        let px = (self.nodes[a].x + self.nodes[b].x) / 2.0;
+        let py = (self.nodes[a].y + self.nodes[b].y) / 2.0;
+        let mut inside = false;
+        let mut p = a;
+        loop {
+            let (np, nn) = (&self.nodes[p], &self.nodes[self.next(p)]);
+            if (np.y > py) != (nn.y > py)
+                && nn.y != np.y
+                && px < (nn.x - np.x) * (py - np.y) / (nn.y - np.y) + np.x
+            {
+                inside = !inside;
+            }
+            p = self.next(p);
+            if p == a {
+                return inside;
+            }
+        }
+    }
+
+    //соединяет вершины a и b диагональю, разрезая кольцо на два, возвращает копию b во втором кольце
+    fn split_polygon(&mut self, a: usize, b: usize) -> usize {
+        let a2 = self.nodes.len();
+        let b2 = a2 + 1;
+        let an = self.next(a);
+        let bp = self.prev(b);
+
+        self.nodes.push(Node { prev: b2, next: an, steiner: false, ..self.nodes[a] });
+        self.nodes.push(Node { prev: bp, next: a2, steiner: false, ..self.nodes[b] });
+
+        self.nodes[a].next = b;
+        self.nodes[b].prev = a;
+        self.nodes[an].prev = a2;
+        self.nodes[bp].next = b2;
+        b2
+    }
+}
+
+fn sign(value: f64) -> i8 {
+    if value > 0.0 {
+        1
+    } else if value < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+fn point_in_triangle(a: [f64; 2], b: [f64; 2], c: [f64; 2], p: [f64; 2]) -> bool {
+    (c[0] - p[0]) * (a[1] - p[1]) >= (a[0] - p[0]) * (c[1] - p[1])
+        && (a[0] - p[0]) * (b[1] - p[1]) >= (b[0] - p[0]) * (a[1] - p[1])
+        && (b[0] - p[0]) * (c[1] - p[1]) >= (c[0] - p[0]) * (b[1] - p[1])
+}
+
+//удвоенная ориентированная площадь контура по формуле шнурования
+fn signed_area(points: &[[f32; 2]]) -> f64 {
+    let mut sum = 0.0;
+    let mut j = points.len().wrapping_sub(1);
+    for (i, point) in points.iter().enumerate() {
+        sum += (points[j][0] as f64 - point[0] as f64) * (point[1] as f64 + points[j][1] as f64);
+        j = i;
+    }
+    sum
+}
+
+/*триангулирует внешний контур outer с дырами holes, направление обхода контуров не важно,
+индексы ссылаются на вершины в порядке outer, затем holes по очереди, как будто контуры склеены в один массив*/
+pub fn triangulate<H: AsRef<[[f32; 2]]>>(outer: &[[f32; 2]], holes: &[H]) -> Vec<u32> {
+    let mut nodes = Nodes {
+        nodes: Vec::with_capacity((outer.len() + holes.iter().map(|hole| hole.as_ref().len()).sum::<usize>()) * 3 / 2),
+    };
+    let mut triangles = Vec::new();
+
+    let mut outer_node = nodes.linked_list(outer, 0, true);
+    if outer_node == NONE || nodes.next(outer_node) == nodes.prev(outer_node) {
+        return triangles; //меньше трех вершин
+    }
+
+    if !holes.is_empty() {
+        outer_node = nodes.eliminate_holes(holes, outer.len() as u32, outer_node);
+    }
+
+    nodes.earcut_linked(outer_node, &mut triangles, 0);
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    //площадь контура без учета направления обхода
+    fn polygon_area(points: &[[f32; 2]]) -> f64 {
+        signed_area(points).abs() / 2.0
+    }
+
+    //сумма площадей треугольников
+    fn triangles_area(points: &[[f32; 2]], indices: &[u32]) -> f64 {
+        indices
+            .chunks(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|k| points[triangle[k] as usize]);
+                polygon_area(&[a, b, c])
+            })
+            .sum()
+    }
+
+    fn assert_area(outer: &[[f32; 2]], holes: &[Vec<[f32; 2]>], indices: &[u32]) {
+        let mut points = outer.to_vec();
+        for hole in holes {
+            points.extend_from_slice(hole);
+        }
+        assert_eq!(indices.len() % 3, 0);
+        assert!(indices.iter().all(|&index| (index as usize) < points.len()));
+
+        let expected = polygon_area(outer) - holes.iter().map(|hole| polygon_area(hole)).sum::<f64>();
+        let actual = triangles_area(&points, indices);
+        let deviation = if expected == 0.0 { actual } else { ((actual - expected) / expected).abs() };
+        assert!(deviation < 1e-6, "expected area {expected}, triangles cover {actual}");
+    }
+
+    #[test]
+    fn triangle() {
+        let outer = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]];
+        assert_eq!(triangulate::<&[[f32; 2]]>(&outer, &[]).len(), 3);
+    }
+
+    #[test]
+    fn degenerate_input() {
+        assert!(triangulate::<&[[f32; 2]]>(&[], &[]).is_empty());
+        assert!(triangulate::<&[[f32; 2]]>(&[[0.0, 0.0], [1.0, 1.0]], &[]).is_empty());
+        assert!(triangulate::<&[[f32; 2]]>(&[[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]], &[]).is_empty());
+    }
+
+    #[test]
+    fn concave_l_shape() {
+        let outer = [[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [1.0, 1.0], [1.0, 2.0], [0.0, 2.0]];
+        let indices = triangulate::<&[[f32; 2]]>(&outer, &[]);
+        assert_eq!(indices.len(), 4 * 3);
+        assert_area(&outer, &[], &indices);
+    }
+
+    #[test]
+    fn both_windings() {
+        let mut outer = vec![[0.0, 0.0], [3.0, 0.0], [3.0, 3.0], [2.0, 1.0], [1.0, 3.0], [0.0, 3.0]];
+        assert_area(&outer, &[], &triangulate::<&[[f32; 2]]>(&outer, &[]));
+        outer.reverse();
+        assert_area(&outer, &[], &triangulate::<&[[f32; 2]]>(&outer, &[]));
+    }
+
+    #[test]
+    fn square_with_hole() {
+        let outer = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]];
+        let holes = vec![vec![[3.0, 3.0], [3.0, 7.0], [7.0, 7.0], [7.0, 3.0]]];
+        let indices = triangulate(&outer, &holes);
+        assert_eq!(indices.len(), 8 * 3);
+        assert_area(&outer, &holes, &indices);
+    }
+
+    #[test]
+    fn several_holes() {
+        let outer = [[0.0, 0.0], [20.0, 0.0], [20.0, 10.0], [0.0, 10.0]];
+        let holes = vec![
+            vec![[2.0, 2.0], [6.0, 2.0], [6.0, 8.0], [2.0, 8.0]],
+            vec![[8.0, 2.0], [12.0, 5.0], [8.0, 8.0]],
+            vec![[14.0, 2.0], [18.0, 2.0], [18.0, 4.0], [16.0, 3.0], [14.0, 4.0]],
+        ];
+        assert_area(&outer, &holes, &triangulate(&outer, &holes));
+    }
+
+    #[test]
+    fn hole_touching_outer() {
+        //левая вершина дыры лежит на ребре внешнего контура
+        let outer = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]];
+        let holes = vec![vec![[0.0, 5.0], [5.0, 3.0], [5.0, 7.0]]];
+        assert_area(&outer, &holes, &triangulate(&outer, &holes));
+    }
+
+    #[test]
+    fn self_touching_contour() {
+        //две области соединены одной общей вершиной (2, 2)
+        let outer = [
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 2.0],
+            [4.0, 2.0],
+            [4.0, 4.0],
+            [2.0, 4.0],
+            [2.0, 2.0],
+            [0.0, 2.0],
+        ];
+        assert_area(&outer, &[], &triangulate::<&[[f32; 2]]>(&outer, &[]));
+    }
+
+    #[test]
+    fn collinear_and_duplicate_points() {
+        let outer = [
+            [0.0, 0.0],
+            [1.0, 0.0],
+            [1.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 2.0],
+            [1.0, 2.0],
+            [0.0, 2.0],
+            [0.0, 1.0],
+        ];
+        assert_area(&outer, &[], &triangulate::<&[[f32; 2]]>(&outer, &[]));
+    }
+
+    /*звездообразный многоугольник: угол каждой вершины лежит в своем секторе 2pi/n, радиусы случайные,
+    поэтому контур простой (без самопересечений) но в общем случае вогнутый*/
+    fn star(center: [f32; 2], jitters: &[f32], radii: &[f32]) -> Vec<[f32; 2]> {
+        let step = std::f32::consts::TAU / jitters.len() as f32;
+        jitters
+            .iter()
+            .zip(radii)
+            .enumerate()
+            .map(|(i, (jitter, radius))| {
+                let angle = (i as f32 + jitter * 0.5) * step;
+                [center[0] + radius * angle.cos(), center[1] + radius * angle.sin()]
+            })
+            .collect()
+    }
+
+    fn star_strategy(min_radius: f32, max_radius: f32) -> impl Strategy<Value = (Vec<f32>, Vec<f32>)> {
+        (4usize..48).prop_flat_map(move |count| {
+            (
+                prop::collection::vec(0.0f32..1.0, count),
+                prop::collection::vec(min_radius..max_radius, count),
+            )
+        })
+    }
+
+    //расстояние от начала координат до ближайшего ребра контура
+    fn inner_radius(points: &[[f32; 2]]) -> f32 {
+        (0..points.len())
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+                let t = (-(a[0] * dx + a[1] * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+                let (x, y) = (a[0] + t * dx, a[1] + t * dy);
+                (x * x + y * y).sqrt()
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(512))]
+
+        #[test]
+        fn star_polygon_area_preserved((jitters, radii) in star_strategy(1.0, 100.0), reverse in any::<bool>()) {
+            let mut outer = star([0.0, 0.0], &jitters, &radii);
+            if reverse {
+                outer.reverse();
+            }
+            let indices = triangulate::<&[[f32; 2]]>(&outer, &[]);
+            assert_area(&outer, &[], &indices);
+        }
+
+        #[test]
+        fn star_polygon_with_hole_area_preserved(
+            (jitters, radii) in star_strategy(50.0, 100.0),
+            (hole_jitters, hole_radii) in star_strategy(5.0, 40.0),
+            (dx, dy) in (-5.0f32..5.0, -5.0f32..5.0),
+        ) {
+            let outer = star([0.0, 0.0], &jitters, &radii);
+            let hole = star([dx, dy], &hole_jitters, &hole_radii);
+            let limit = inner_radius(&outer) - 0.5; //дыра должна целиком лежать внутри внешнего контура
+            prop_assume!(hole.iter().all(|p| (p[0] * p[0] + p[1] * p[1]).sqrt() < limit));
+
+            let holes = vec![hole];
+            let indices = triangulate(&outer, &holes);
+            assert_area(&outer, &holes, &indices);
+        }
+
+        #[test]
+        fn grid_of_holes_area_preserved(columns in 1usize..6, rows in 1usize..6, size in 0.2f32..0.9) {
+            let outer = [[0.0, 0.0], [columns as f32, 0.0], [columns as f32, rows as f32], [0.0, rows as f32]];
+            let offset = (1.0 - size) / 2.0;
+            let holes: Vec<Vec<[f32; 2]>> = (0..columns * rows)
+                .map(|cell| {
+                    let (x, y) = ((cell % columns) as f32 + offset, (cell / columns) as f32 + offset);
+                    vec![[x, y], [x + size, y], [x + size, y + size], [x, y + size]]
+                })
+                .collect();
+            let indices = triangulate(&outer, &holes);
+            assert_area(&outer, &holes, &indices);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use ash::vk;
+use std::mem::offset_of;
+
+/*формат вершины совпадает с входами triangle.vert и sprite.vert:
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec4 inColor;
+layout(location = 2) in vec2 inUV; (только sprite.vert, triangle.vert этот атрибут просто не читает)
+Атрибуты pipeline`а выводятся из входов шейдера (ShaderReflection::vertex_attributes): каждый вход берет поле
+с тем же location из Vertex::attributes со смещением поля в структуре*/
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4], //RGBA, альфа используется режимами смешивания BlendMode
+    pub uv: [f32; 2],
+}
+
+impl Vertex {
+    pub fn new(position: [f32; 2], color: [f32; 4]) -> Self {
+        Self {
+            position,
+            color,
+            uv: [0.0, 0.0],
+        }
+    }
+
+    //вершина с текстурными координатами, color умножается на цвет текстуры
+    pub fn textured(position: [f32; 2], color: [f32; 4], uv: [f32; 2]) -> Self {
+        Self { position, color, uv }
+    }
+
+    //описание одного вершинного буффера, шаг между вершинами равен размеру структуры
+    pub fn binding_descriptions() -> [vk::VertexInputBindingDescription; 1] {
+        [vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: size_of::<Vertex>() as u32,
+            input_rate: vk::VertexInputRate::VERTEX, //данные берутся на каждую вершину, а не на экземпляр
+        }]
+    }
+
+    //поля вершины в буффере binding 0 по location входов шейдера
+    pub fn attributes() -> [vk::VertexInputAttributeDescription; 3] {
+        let field = |location, format, offset: usize| vk::VertexInputAttributeDescription {
+            location,
+            binding: 0,
+            format,
+            offset: offset as u32,
+        };
+        [
+            field(0, vk::Format::R32G32_SFLOAT, offset_of!(Vertex, position)),
+            field(1, vk::Format::R32G32B32A32_SFLOAT, offset_of!(Vertex, color)),
+            field(2, vk::Format::R32G32_SFLOAT, offset_of!(Vertex, uv)),
+        ]
+    }
+}
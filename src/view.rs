@@ -0,0 +1,159 @@
+use ash::vk;
+
+/*камера вида: точка мира в центре вида, масштаб (пикселей вида на единицу мира) и поворот в радианах,
+при повороте камеры на угол мир на экране поворачивается в обратную сторону*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub center: [f32; 2],
+    pub zoom: f32,
+    pub rotation: f32,
+}
+
+impl Camera {
+    //мировые координаты совпадают с пикселями вида размером size, так Batcher рисует без вызова begin_view
+    pub fn screen(size: [f32; 2]) -> Self {
+        Self {
+            center: [size[0] * 0.5, size[1] * 0.5],
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    //область мира размером world целиком в виде размером size, с сохранением пропорций
+    pub fn fit(world: [f32; 2], size: [f32; 2]) -> Self {
+        Self {
+            zoom: (size[0] / world[0]).min(size[1] / world[1]),
+            ..Self::screen(world)
+        }
+    }
+
+    //точка мира в пиксели от левого верхнего угла вида размером size
+    pub fn world_to_view(&self, [x, y]: [f32; 2], size: [f32; 2]) -> [f32; 2] {
+        let (dx, dy) = (x - self.center[0], y - self.center[1]);
+        let (sin, cos) = (-self.rotation).sin_cos();
+        [
+            (dx * cos - dy * sin) * self.zoom + size[0] * 0.5,
+            (dx * sin + dy * cos) * self.zoom + size[1] * 0.5,
+        ]
+    }
+}
+
+/*прямоугольник вида в долях размера окна, пиксели считаются при записи кадра,
+поэтому после изменения размера окна виды сохраняют раскладку, а pipeline`ы с динамическими viewport/scissor не пересоздаются*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewRect {
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    //count одинаковых видов слева направо (split-screen)
+    pub fn columns(count: usize) -> Vec<Self> {
+        let width = 1.0 / count as f32;
+        (0..count)
+            .map(|i| Self {
+                x: i as f32 * width,
+                width,
+                ..Self::FULL
+            })
+            .collect()
+    }
+
+    //count одинаковых видов сверху вниз
+    pub fn rows(count: usize) -> Vec<Self> {
+        let height = 1.0 / count as f32;
+        (0..count)
+            .map(|i| Self {
+                y: i as f32 * height,
+                height,
+                ..Self::FULL
+            })
+            .collect()
+    }
+
+    //картинка в картинке: доля scale окна в правом нижнем углу с отступом margin (тоже в долях)
+    pub fn picture_in_picture(scale: f32, margin: f32) -> Self {
+        Self {
+            x: 1.0 - scale - margin,
+            y: 1.0 - scale - margin,
+            width: scale,
+            height: scale,
+        }
+    }
+
+    /*область в пикселях окна extent, края округляются отдельно, чтобы соседние виды стыковались без щелей и наложений*/
+    pub fn pixels(&self, extent: vk::Extent2D) -> vk::Rect2D {
+        let edge = |fraction: f32, size: u32| (fraction.clamp(0.0, 1.0) * size as f32).round() as u32;
+        let (x0, x1) = (edge(self.x, extent.width), edge(self.x + self.width, extent.width));
+        let (y0, y1) = (edge(self.y, extent.height), edge(self.y + self.height, extent.height));
+        vk::Rect2D {
+            offset: vk::Offset2D { x: x0 as i32, y: y0 as i32 },
+            extent: vk::Extent2D {
+                width: x1.saturating_sub(x0),
+                height: y1.saturating_sub(y0),
+            },
+        }
+    }
+}
+
+//вид это область окна и камера, через которую в нее рисуется мир
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub rect: ViewRect,
+    pub camera: Camera,
+}
+
+//viewport на всю область rect, глубина не используется
+pub fn viewport(rect: vk::Rect2D) -> vk::Viewport {
+    vk::Viewport {
+        x: rect.offset.x as f32,
+        y: rect.offset.y as f32,
+        width: rect.extent.width as f32,
+        height: rect.extent.height as f32,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_tile_the_window() {
+        let extent = vk::Extent2D { width: 1001, height: 600 };
+        let rects: Vec<vk::Rect2D> = ViewRect::columns(3).iter().map(|rect| rect.pixels(extent)).collect();
+        assert_eq!(rects[0].offset.x, 0);
+        for pair in rects.windows(2) {
+            assert_eq!(pair[0].offset.x + pair[0].extent.width as i32, pair[1].offset.x);
+        }
+        assert_eq!(rects[2].offset.x + rects[2].extent.width as i32, 1001);
+        assert!(rects.iter().all(|rect| rect.extent.height == 600));
+    }
+
+    #[test]
+    fn camera_maps_world_to_view() {
+        let size = [400.0, 300.0];
+        let screen = Camera::screen(size);
+        assert_eq!(screen.world_to_view([12.0, 34.0], size), [12.0, 34.0]);
+
+        //камера повернута на четверть оборота: точка правее центра мира оказывается над центром вида
+        let camera = Camera {
+            center: [100.0, -50.0],
+            zoom: 2.0,
+            rotation: std::f32::consts::FRAC_PI_2,
+        };
+        assert_eq!(camera.world_to_view(camera.center, size), [200.0, 150.0]);
+        let [x, y] = camera.world_to_view([110.0, -50.0], size);
+        assert!((x - 200.0).abs() < 1e-3 && (y - 130.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,9 @@
+#version 450
+layout(push_constant) uniform Push {
+    mat3 rotation;
+    mat2x3 skew;
+} push;
+layout(location = 0) out vec4 outColor;
+void main() {
+    outColor = vec4(push.rotation[0] + push.skew[1], 1.0);
+}
@@ -0,0 +1,9 @@
+#version 450
+layout(location = 0) in vec2 inPosition;
+layout(location = 2) in vec2 inUV;
+layout(location = 0) out vec2 fragUV;
+
+void main() {
+    gl_Position = vec4(inPosition, 0.0, 1.0);
+    fragUV = inUV;
+}